@@ -42,6 +42,36 @@ const COMMANDS: &[&str] = &[
     "decryption_password",
     "set_decryption_password",
     "last_found_update",
+    "channels",
+    "current_enrollment",
+    "enroll_channel",
+    "leave_channel",
+    "start_updater",
+    "is_started",
+    "record_update_consent",
+    "should_prompt_for_permission",
+    "set_should_prompt_for_permission",
+    "diagnostics",
+    "reply_user_driver",
+    "pending_user_driver_requests",
+    "cancel_update",
+    "skip_update",
+    "remind_later",
+    "install_update_now",
+    "block_install",
+    "unblock_install",
+    "install_block_status",
+    "ready_to_quit",
+    "consume_whats_new",
+    "mark_update_healthy",
+    "update_health",
+    "update_required",
+    "check_minimum_version",
+    "install_required_update",
+    "current_version_revoked",
+    "check_revoked_releases",
+    "release_notes",
+    "cumulative_release_notes",
 ];
 
 fn main() {
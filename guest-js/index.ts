@@ -3,6 +3,13 @@ import { listen, UnlistenFn } from '@tauri-apps/api/event';
 
 export interface UpdateInfo {
   version: string;
+  /** Build version (`sparkle:version`), compared against `CFBundleVersion`. */
+  buildVersion: string;
+  /** Enclosure URL. */
+  fileUrl?: string;
+  /** Expected enclosure size in bytes. */
+  contentLength?: number;
+  isDelta: boolean;
   releaseNotes?: string;
   title?: string;
   releaseNotesUrl?: string;
@@ -23,25 +30,117 @@ export interface UpdateInfo {
   ignoreSkippedUpgradesBelowVersion?: string;
   dateString?: string;
   itemDescriptionFormat?: string;
+  /** Custom appcast elements keyed by qualified name, e.g. `myapp:migrationRequired`. */
+  extra?: Record<string, unknown>;
 }
 
+/** Identifies the update a download, extraction or installation event refers to. */
 export interface VersionInfo {
   version: string;
+  buildVersion: string;
+  fileUrl?: string;
+  contentLength?: number;
+  isDelta: boolean;
 }
 
+export type SparkleErrorCode =
+  | 'noPublicKey'
+  | 'insufficientSigning'
+  | 'insecureFeedUrl'
+  | 'invalidFeedUrl'
+  | 'invalidUpdater'
+  | 'invalidHostBundleIdentifier'
+  | 'invalidHostVersion'
+  | 'appcastParse'
+  | 'noUpdate'
+  | 'appcast'
+  | 'runningFromDiskImage'
+  | 'resumeAppcast'
+  | 'runningTranslocated'
+  | 'webKitTermination'
+  | 'releaseNotes'
+  | 'temporaryDirectory'
+  | 'download'
+  | 'unarchiving'
+  | 'signatureVerification'
+  | 'validation'
+  | 'fileCopy'
+  | 'installationAuthorization'
+  | 'missingUpdate'
+  | 'missingInstallerTool'
+  | 'relaunch'
+  | 'installation'
+  | 'downgrade'
+  | 'installationCanceled'
+  | 'installationAuthorizeLater'
+  | 'notValidUpdate'
+  | 'agentInvalidation'
+  | 'installationRootInteractive'
+  | 'installationWriteNoPermission'
+  | 'incorrectApiUsage'
+  | 'unknown';
+
+export type ErrorCategory =
+  | 'configuration'
+  | 'appcast'
+  | 'download'
+  | 'extraction'
+  | 'installation'
+  | 'apiMisuse'
+  | 'unknown';
+
 export interface UpdateError {
   message: string;
   code: number;
   domain: string;
+  /** `code` mapped to Sparkle's `SUError` codes. */
+  kind: SparkleErrorCode;
+  category: ErrorCategory;
+  retryable: boolean;
+}
+
+/** Shape of errors rejected by the plugin's commands. */
+export interface PluginError {
+  kind:
+    | 'io'
+    | 'json'
+    | 'tauri'
+    | 'invalidFeedUrl'
+    | 'feedUrlRejected'
+    | 'invalidConfig'
+    | 'invalidAppcast'
+    | 'sparkleInit'
+    | 'sparkle'
+    | 'updaterNotReady'
+    | 'updaterNotStarted'
+    | 'unknownChannel'
+    | 'userDriverNotHeadless'
+    | 'unknownUserDriverRequest'
+    | 'invalidUserDriverReply'
+    | 'noMatchingSession'
+    | 'unknownInstallBlocker'
+    | 'fetchFailed'
+    | 'invalidSignature'
+    | 'updateRequired';
+  message: string;
+  details:
+    | { url: string }
+    | { channel: string }
+    | { requestId: number }
+    | { token: number }
+    | { minimumVersion: string }
+    | { code: SparkleErrorCode; rawCode: number; category: ErrorCategory; retryable: boolean }
+    | null;
 }
 
+export type UpdateCheck = 'userInitiated' | 'background' | 'information';
+
 export interface UpdateCycleInfo {
-  updateCheck: 'userInitiated' | 'background' | 'information';
+  updateCheck: UpdateCheck;
   error?: UpdateError;
 }
 
-export interface DownloadFailedInfo {
-  version: string;
+export interface DownloadFailedInfo extends VersionInfo {
   error: UpdateError;
 }
 
@@ -55,6 +154,292 @@ export interface ScheduleInfo {
   delay: number;
 }
 
+export interface UpdateRejectedInfo {
+  version: string;
+  updateCheck: UpdateCheck;
+  reason: string;
+}
+
+export interface UpdateCheckDeniedInfo {
+  updateCheck: UpdateCheck;
+  reason: string;
+}
+
+export interface Channel {
+  id: string;
+  displayName: string;
+}
+
+export interface ChannelChange {
+  channel: string;
+  enrolled: boolean;
+  enrollment: Channel[];
+  /**
+   * Whether the running build is newer than the latest build on the default channel.
+   * Unset until an appcast has loaded.
+   */
+  aheadOfStable?: boolean;
+  stableVersion?: string;
+}
+
+/** The user's answer to the app's own update consent prompt. */
+export interface UpdateConsent {
+  automaticallyChecksForUpdates: boolean;
+  automaticallyDownloadsUpdates: boolean;
+}
+
+export type StartMode = 'immediate' | 'deferred';
+
+export type UserDriverMode = 'standard' | 'headless';
+
+/** What the headless user driver is waiting on. */
+export type PromptKind =
+  | 'permission'
+  | 'userInitiatedCheck'
+  | 'updateFound'
+  | 'updateNotFound'
+  | 'updaterError'
+  | 'download'
+  | 'readyToInstall'
+  | 'installing'
+  | 'updateInstalled';
+
+/** Answer to a headless user driver question. */
+export type UserDriverReply =
+  | { action: 'install' }
+  | { action: 'skip' }
+  | { action: 'remindLater' }
+  | { action: 'cancel' }
+  | { action: 'acknowledge' }
+  | ({ action: 'permission'; sendSystemProfile?: boolean } & UpdateConsent);
+
+export interface PendingRequest {
+  requestId: number;
+  kind: PromptKind;
+}
+
+export interface UserDriverRequestInfo {
+  requestId: number;
+}
+
+export interface UpdateFoundInfo extends UpdateInfo {
+  requestId: number;
+  stage: 'notDownloaded' | 'downloaded' | 'installing';
+  userInitiated: boolean;
+}
+
+export interface ReleaseNotesInfo {
+  notes: string;
+  mimeType?: string;
+}
+
+export interface AcknowledgementInfo {
+  requestId: number;
+  error: UpdateError;
+}
+
+export interface ExtractionProgressInfo {
+  /** Between 0 and 1. */
+  progress: number;
+}
+
+export interface DownloadProgressInfo {
+  /** Size of the download in bytes, from the server or else the appcast enclosure. */
+  expectedContentLength: number | null;
+  bytesReceived: number;
+}
+
+export interface LaunchedVersion {
+  version: string;
+  buildVersion: string;
+}
+
+export interface DidUpdateInfo {
+  from: LaunchedVersion;
+  to: LaunchedVersion;
+  /** The installed appcast item with its release notes, if Sparkle installed it. */
+  update?: UpdateInfo;
+}
+
+export interface MigrationFailure {
+  id: string;
+  /** Build version that introduced the migration. */
+  version: string;
+  error: string;
+}
+
+export interface PendingVerification {
+  from: LaunchedVersion;
+  to: LaunchedVersion;
+  /** Launches of `to` so far, including the running one. */
+  launches: number;
+}
+
+export type RollbackReason = 'failedLaunches' | 'timeout';
+
+export interface RollbackInfo {
+  /** The version that worked before the update. */
+  previous: LaunchedVersion;
+  current: LaunchedVersion;
+  reason: RollbackReason;
+  /** Launches that ended without `markUpdateHealthy`. */
+  failedLaunches: number;
+  /** Channel the feed was switched to, if configured. */
+  rollbackChannel?: string;
+}
+
+export interface UpdateHealth {
+  pending: PendingVerification | null;
+  rollbackRecommended: RollbackInfo | null;
+}
+
+export interface PreInstallAbort {
+  /** Name of the hook that aborted the install. */
+  hook: string;
+  reason: string;
+  /** Build version of the update that was held back. */
+  version: string;
+  onQuit: boolean;
+}
+
+export type MinimumVersionSource = 'appcast' | 'document';
+
+export interface UpdateRequiredInfo {
+  /** Lowest build version still allowed to run. */
+  minimumVersion: string;
+  currentVersion: string;
+  message?: string;
+  source: MinimumVersionSource;
+}
+
+export type RevocationSource = 'appcast' | 'document';
+
+export interface RecommendedRelease {
+  version: string;
+  buildVersion: string;
+  /** Older than the running build. Sparkle never downgrades, so install it by other means. */
+  downgrade: boolean;
+}
+
+export interface CurrentVersionRevoked {
+  currentVersion: string;
+  reason?: string;
+  source: RevocationSource;
+  /** Highest release in the last loaded appcast that is not revoked. */
+  recommended: RecommendedRelease | null;
+}
+
+export type ReleaseNotesFormat = 'html' | 'markdown' | 'plainText';
+
+export interface ReleaseNotes {
+  version: string;
+  buildVersion: string;
+  /** How the notes were written before rendering. */
+  format: ReleaseNotesFormat;
+  /** URL the notes were downloaded from, when they were not inline. */
+  url?: string;
+  /** Sanitized HTML, safe to insert into the page. Empty when the update has no notes. */
+  html: string;
+}
+
+export interface CumulativeReleaseNotes {
+  /** Build version the update starts from. */
+  from: string;
+  /** Build version the update ends at. */
+  to: string;
+  /** Newest first. */
+  releases: ReleaseNotes[];
+  /** `releases` merged into one document, one `<section>` with a version heading each. */
+  html: string;
+}
+
+export interface CumulativeNotesOptions {
+  /** Build version to start from. Defaults to the running build. */
+  currentVersion?: string;
+  /** Build version to end at. Defaults to the newest release the appcast offers. */
+  targetVersion?: string;
+  /** Appcast to read the releases from. */
+  appcastXml?: string;
+  /**
+   * URL to download the appcast from when `appcastXml` is not set. Without either, the
+   * appcast Sparkle loaded last is used.
+   */
+  appcastUrl?: string;
+  /** Whether to include the notes of the target release itself. */
+  includeTarget?: boolean;
+}
+
+export interface BeforeInstallQuitInfo {
+  /** Seconds the webview has to save before the save window ends anyway. */
+  timeout: number;
+  /** Install blockers that hold the quit back until they are released, past the timeout. */
+  blockedBy: number;
+}
+
+export interface BlockerInfo {
+  token: number;
+  reason: string;
+}
+
+export interface InstallBlockStatus {
+  /** Number of blockers held. */
+  blockedBy: number;
+  blockers: BlockerInfo[];
+  /** Whether an install is waiting for the last blocker to be released. */
+  installDeferred: boolean;
+}
+
+export interface InstallingInfo {
+  requestId: number;
+  applicationTerminated: boolean;
+}
+
+export interface UpdateInstalledInfo {
+  requestId: number;
+  relaunched: boolean;
+}
+
+export interface FeedPolicy {
+  requireHttps: boolean;
+  allowedHosts: string[];
+}
+
+export interface DiagnosticsReport {
+  pluginVersion: string;
+  bundle: {
+    detected: boolean;
+    identifier?: string;
+    path?: string;
+    /** Why the bundle was not accepted, if it wasn't. */
+    reason?: string;
+  };
+  infoPlist: { key: string; present: boolean; value?: string }[];
+  sparkleVersion?: string;
+  feedUrl?: { url: string; origin: 'override' | 'userDefaults' | 'config' | 'infoPlist' };
+  updater: {
+    created: boolean;
+    started: boolean;
+    startMode: StartMode;
+    startupError?: string;
+    customBackend: boolean;
+    userDriver: UserDriverMode;
+  };
+  /** Only available once the updater has been created. */
+  policies?: {
+    mayCheckForUpdates: boolean;
+    shouldProceedWithUpdate: boolean;
+    promptForPermission: boolean;
+    automaticallyChecksForUpdates: boolean;
+    automaticallyDownloadsUpdates: boolean;
+    updateCheckInterval: number;
+    allowedChannels?: string[];
+    proceedPolicy: boolean;
+    mayCheckPolicies: number;
+    feedUrlPolicy: boolean;
+    feedPolicy: FeedPolicy;
+  };
+}
+
 export type DidFinishLoadingAppcastPayload = Record<string, never>;
 export type DidFindValidUpdatePayload = UpdateInfo;
 export type DidNotFindUpdatePayload = Record<string, never>;
@@ -72,6 +457,33 @@ export type UserDidMakeChoicePayload = UserChoiceInfo;
 export type WillScheduleUpdateCheckPayload = ScheduleInfo;
 export type WillNotScheduleUpdateCheckPayload = Record<string, never>;
 export type WillInstallUpdateOnQuitPayload = VersionInfo;
+export type DidRejectUpdatePayload = UpdateRejectedInfo;
+export type DidDenyUpdateCheckPayload = UpdateCheckDeniedInfo;
+export type ChannelChangedPayload = ChannelChange;
+export type ShowUpdatePermissionRequestPayload = UserDriverRequestInfo;
+export type ShowUserInitiatedUpdateCheckPayload = UserDriverRequestInfo;
+export type ShowUpdateFoundPayload = UpdateFoundInfo;
+export type ShowUpdateReleaseNotesPayload = ReleaseNotesInfo;
+export type ShowUpdateReleaseNotesFailedPayload = UpdateError;
+export type ShowUpdateNotFoundPayload = AcknowledgementInfo;
+export type ShowUpdaterErrorPayload = AcknowledgementInfo;
+export type ShowDownloadInitiatedPayload = UserDriverRequestInfo;
+export type ShowExtractingUpdatePayload = Record<string, never>;
+export type ShowExtractionProgressPayload = ExtractionProgressInfo;
+export type ShowReadyToInstallPayload = UserDriverRequestInfo;
+export type ShowInstallingUpdatePayload = InstallingInfo;
+export type ShowUpdateInstalledPayload = UpdateInstalledInfo;
+export type ShowUpdateInFocusPayload = Record<string, never>;
+export type DismissUpdateInstallationPayload = Record<string, never>;
+export type DownloadProgressPayload = DownloadProgressInfo;
+export type InstallDeferredPayload = InstallBlockStatus;
+export type BeforeInstallQuitPayload = BeforeInstallQuitInfo;
+export type DidUpdatePayload = DidUpdateInfo;
+export type MigrationFailedPayload = MigrationFailure;
+export type RollbackRecommendedPayload = RollbackInfo;
+export type PreInstallAbortedPayload = PreInstallAbort;
+export type UpdateRequiredPayload = UpdateRequiredInfo;
+export type CurrentVersionRevokedPayload = CurrentVersionRevoked;
 
 export async function checkForUpdates(): Promise<void> {
   return invoke('plugin:sparkle-updater|check_for_updates');
@@ -307,6 +719,204 @@ export async function lastFoundUpdate(): Promise<UpdateInfo | null> {
   return invoke('plugin:sparkle-updater|last_found_update');
 }
 
+/** Returns the channels declared by the app. */
+export async function channels(): Promise<Channel[]> {
+  return invoke('plugin:sparkle-updater|channels');
+}
+
+/** Returns the channels the user is currently enrolled in. */
+export async function currentEnrollment(): Promise<Channel[]> {
+  return invoke('plugin:sparkle-updater|current_enrollment');
+}
+
+/**
+ * Enrolls in a declared channel. The enrollment is persisted when persistence is enabled.
+ *
+ * @param channel - The channel id
+ * @param resetUpdateCycle - Whether to reset the update cycle so the channel is checked right away
+ */
+export async function enrollChannel(channel: string, resetUpdateCycle = false): Promise<ChannelChange> {
+  return invoke('plugin:sparkle-updater|enroll_channel', { channel, resetUpdateCycle });
+}
+
+/**
+ * Leaves a channel. Check `aheadOfStable` on the result to tell the user they are running
+ * a newer build than the default channel offers.
+ *
+ * @param channel - The channel id
+ * @param resetUpdateCycle - Whether to reset the update cycle so the change takes effect right away
+ */
+export async function leaveChannel(channel: string, resetUpdateCycle = false): Promise<ChannelChange> {
+  return invoke('plugin:sparkle-updater|leave_channel', { channel, resetUpdateCycle });
+}
+
+/**
+ * Starts the updater when the plugin is configured with `startMode: "deferred"`.
+ * Does nothing if it is already running.
+ */
+export async function startUpdater(): Promise<void> {
+  return invoke('plugin:sparkle-updater|start_updater');
+}
+
+export async function isStarted(): Promise<boolean> {
+  return invoke('plugin:sparkle-updater|is_started');
+}
+
+/**
+ * Records the user's answer to the app's update consent prompt. Sparkle will not show
+ * its own permission prompt afterwards.
+ */
+export async function recordUpdateConsent(consent: UpdateConsent): Promise<void> {
+  return invoke('plugin:sparkle-updater|record_update_consent', { consent });
+}
+
+export async function shouldPromptForPermission(): Promise<boolean> {
+  return invoke('plugin:sparkle-updater|should_prompt_for_permission');
+}
+
+export async function setShouldPromptForPermission(enabled: boolean): Promise<void> {
+  return invoke('plugin:sparkle-updater|set_should_prompt_for_permission', { enabled });
+}
+
+/**
+ * Returns a report on how the updater was set up. Also works when the updater is
+ * unavailable, e.g. outside a valid app bundle.
+ */
+export async function diagnostics(): Promise<DiagnosticsReport> {
+  return invoke('plugin:sparkle-updater|diagnostics');
+}
+
+/**
+ * Answers a question of the headless user driver (`userDriver: "headless"`), identified
+ * by the `requestId` of the event that asked it.
+ */
+export async function replyUserDriver(requestId: number, reply: UserDriverReply): Promise<void> {
+  return invoke('plugin:sparkle-updater|reply_user_driver', { requestId, reply });
+}
+
+/** Headless user driver questions still waiting for an answer, e.g. after a reload. */
+export async function pendingUserDriverRequests(): Promise<PendingRequest[]> {
+  return invoke('plugin:sparkle-updater|pending_user_driver_requests');
+}
+
+/** Cancels the running user-initiated check or download. Requires the headless user driver. */
+export async function cancelUpdate(): Promise<void> {
+  return invoke('plugin:sparkle-updater|cancel_update');
+}
+
+/** Skips the update the user is being asked about. Requires the headless user driver. */
+export async function skipUpdate(): Promise<void> {
+  return invoke('plugin:sparkle-updater|skip_update');
+}
+
+/**
+ * Dismisses the update the user is being asked about until the next scheduled check.
+ * Requires the headless user driver.
+ */
+export async function remindLater(): Promise<void> {
+  return invoke('plugin:sparkle-updater|remind_later');
+}
+
+/**
+ * Installs an update right away: the one the headless user driver is asking about, or else
+ * the downloaded update Sparkle would install on quit or whose install a pre-install hook
+ * aborted. The pre-install hooks run first.
+ */
+export async function installUpdateNow(): Promise<void> {
+  return invoke('plugin:sparkle-updater|install_update_now');
+}
+
+/**
+ * Defers installing updates until `unblockInstall` is called with the returned token, e.g.
+ * while a document has unsaved changes.
+ */
+export async function blockInstall(reason: string): Promise<number> {
+  return invoke('plugin:sparkle-updater|block_install', { reason });
+}
+
+export async function unblockInstall(token: number): Promise<void> {
+  return invoke('plugin:sparkle-updater|unblock_install', { token });
+}
+
+export async function installBlockStatus(): Promise<InstallBlockStatus> {
+  return invoke('plugin:sparkle-updater|install_block_status');
+}
+
+/**
+ * Lets the app quit after `before-install-quit`, once the webview has saved its state. The
+ * quit still waits for install blockers to be released.
+ */
+export async function readyToQuit(): Promise<void> {
+  return invoke('plugin:sparkle-updater|ready_to_quit');
+}
+
+/**
+ * Returns the update this launch follows the first time the calling window asks, so
+ * every window shows "What's New" exactly once. `null` afterwards or when the app was not
+ * just updated.
+ */
+export async function consumeWhatsNew(): Promise<DidUpdateInfo | null> {
+  return invoke('plugin:sparkle-updater|consume_whats_new');
+}
+
+/**
+ * Confirms the running update works. Requires `healthCheck` in the plugin configuration.
+ * Returns `false` when no update was waiting for verification.
+ */
+export async function markUpdateHealthy(): Promise<boolean> {
+  return invoke('plugin:sparkle-updater|mark_update_healthy');
+}
+
+export async function updateHealth(): Promise<UpdateHealth> {
+  return invoke('plugin:sparkle-updater|update_health');
+}
+
+/** The minimum supported version the running build is below, or `null`. */
+export async function updateRequired(): Promise<UpdateRequiredInfo | null> {
+  return invoke('plugin:sparkle-updater|update_required');
+}
+
+/** Downloads the signed minimum version document again, if one is configured. */
+export async function checkMinimumVersion(): Promise<UpdateRequiredInfo | null> {
+  return invoke('plugin:sparkle-updater|check_minimum_version');
+}
+
+/**
+ * Starts installing the update the running build requires. With the headless user driver,
+ * the update cannot be skipped or postponed.
+ */
+export async function installRequiredUpdate(): Promise<void> {
+  return invoke('plugin:sparkle-updater|install_required_update');
+}
+
+/** The revocation of the running build, or `null` if it has not been revoked. */
+export async function currentVersionRevoked(): Promise<CurrentVersionRevoked | null> {
+  return invoke('plugin:sparkle-updater|current_version_revoked');
+}
+
+/** Downloads the signed revoked releases document again, if one is configured. */
+export async function checkRevokedReleases(): Promise<CurrentVersionRevoked | null> {
+  return invoke('plugin:sparkle-updater|check_revoked_releases');
+}
+
+/**
+ * The release notes of an update as sanitized HTML, downloaded from `releaseNotesUrl` if
+ * set. Cached per build version.
+ */
+export async function releaseNotes(update: UpdateInfo): Promise<ReleaseNotes> {
+  return invoke('plugin:sparkle-updater|release_notes', { update });
+}
+
+/**
+ * The notes of every release between the running build and the newest one on the default
+ * or an allowed channel, so users see everything an update skips.
+ */
+export async function cumulativeReleaseNotes(
+  options?: CumulativeNotesOptions
+): Promise<CumulativeReleaseNotes> {
+  return invoke('plugin:sparkle-updater|cumulative_release_notes', { options });
+}
+
 export const Events = {
   DID_FINISH_LOADING_APPCAST: 'sparkle://did-finish-loading-appcast',
   DID_FIND_VALID_UPDATE: 'sparkle://did-find-valid-update',
@@ -325,6 +935,33 @@ export const Events = {
   WILL_SCHEDULE_UPDATE_CHECK: 'sparkle://will-schedule-update-check',
   WILL_NOT_SCHEDULE_UPDATE_CHECK: 'sparkle://will-not-schedule-update-check',
   WILL_INSTALL_UPDATE_ON_QUIT: 'sparkle://will-install-update-on-quit',
+  DID_REJECT_UPDATE: 'sparkle://did-reject-update',
+  DID_DENY_UPDATE_CHECK: 'sparkle://did-deny-update-check',
+  CHANNEL_CHANGED: 'sparkle://channel-changed',
+  INSTALL_DEFERRED: 'sparkle://install-deferred',
+  BEFORE_INSTALL_QUIT: 'sparkle://before-install-quit',
+  DID_UPDATE: 'sparkle://did-update',
+  MIGRATION_FAILED: 'sparkle://migration-failed',
+  ROLLBACK_RECOMMENDED: 'sparkle://rollback-recommended',
+  PRE_INSTALL_ABORTED: 'sparkle://pre-install-aborted',
+  UPDATE_REQUIRED: 'sparkle://update-required',
+  CURRENT_VERSION_REVOKED: 'sparkle://current-version-revoked',
+  SHOW_UPDATE_PERMISSION_REQUEST: 'sparkle://show-update-permission-request',
+  SHOW_USER_INITIATED_UPDATE_CHECK: 'sparkle://show-user-initiated-update-check',
+  SHOW_UPDATE_FOUND: 'sparkle://show-update-found',
+  SHOW_UPDATE_RELEASE_NOTES: 'sparkle://show-update-release-notes',
+  SHOW_UPDATE_RELEASE_NOTES_FAILED: 'sparkle://show-update-release-notes-failed',
+  SHOW_UPDATE_NOT_FOUND: 'sparkle://show-update-not-found',
+  SHOW_UPDATER_ERROR: 'sparkle://show-updater-error',
+  SHOW_DOWNLOAD_INITIATED: 'sparkle://show-download-initiated',
+  SHOW_EXTRACTING_UPDATE: 'sparkle://show-extracting-update',
+  SHOW_EXTRACTION_PROGRESS: 'sparkle://show-extraction-progress',
+  SHOW_READY_TO_INSTALL: 'sparkle://show-ready-to-install',
+  SHOW_INSTALLING_UPDATE: 'sparkle://show-installing-update',
+  SHOW_UPDATE_INSTALLED: 'sparkle://show-update-installed',
+  SHOW_UPDATE_IN_FOCUS: 'sparkle://show-update-in-focus',
+  DISMISS_UPDATE_INSTALLATION: 'sparkle://dismiss-update-installation',
+  DOWNLOAD_PROGRESS: 'sparkle://download-progress',
 } as const;
 
 function createListener<T>(event: string) {
@@ -349,6 +986,41 @@ export const onUserDidMakeChoice = createListener<UserDidMakeChoicePayload>(Even
 export const onWillScheduleUpdateCheck = createListener<WillScheduleUpdateCheckPayload>(Events.WILL_SCHEDULE_UPDATE_CHECK);
 export const onWillNotScheduleUpdateCheck = createListener<WillNotScheduleUpdateCheckPayload>(Events.WILL_NOT_SCHEDULE_UPDATE_CHECK);
 export const onWillInstallUpdateOnQuit = createListener<WillInstallUpdateOnQuitPayload>(Events.WILL_INSTALL_UPDATE_ON_QUIT);
+export const onDidRejectUpdate = createListener<DidRejectUpdatePayload>(Events.DID_REJECT_UPDATE);
+export const onDidDenyUpdateCheck = createListener<DidDenyUpdateCheckPayload>(Events.DID_DENY_UPDATE_CHECK);
+export const onChannelChanged = createListener<ChannelChangedPayload>(Events.CHANNEL_CHANGED);
+export const onInstallDeferred = createListener<InstallDeferredPayload>(Events.INSTALL_DEFERRED);
+export const onBeforeInstallQuit = createListener<BeforeInstallQuitPayload>(Events.BEFORE_INSTALL_QUIT);
+/**
+ * Emitted during plugin setup, before any webview can listen, so windows never receive it.
+ * Use `consumeWhatsNew` instead.
+ */
+export const onDidUpdate = createListener<DidUpdatePayload>(Events.DID_UPDATE);
+export const onMigrationFailed = createListener<MigrationFailedPayload>(Events.MIGRATION_FAILED);
+/**
+ * Only fires for recommendations made after the window started listening, such as the
+ * health check timeout. A recommendation made during setup is returned by `updateHealth`.
+ */
+export const onRollbackRecommended = createListener<RollbackRecommendedPayload>(Events.ROLLBACK_RECOMMENDED);
+export const onPreInstallAborted = createListener<PreInstallAbortedPayload>(Events.PRE_INSTALL_ABORTED);
+export const onUpdateRequired = createListener<UpdateRequiredPayload>(Events.UPDATE_REQUIRED);
+export const onCurrentVersionRevoked = createListener<CurrentVersionRevokedPayload>(Events.CURRENT_VERSION_REVOKED);
+export const onShowUpdatePermissionRequest = createListener<ShowUpdatePermissionRequestPayload>(Events.SHOW_UPDATE_PERMISSION_REQUEST);
+export const onShowUserInitiatedUpdateCheck = createListener<ShowUserInitiatedUpdateCheckPayload>(Events.SHOW_USER_INITIATED_UPDATE_CHECK);
+export const onShowUpdateFound = createListener<ShowUpdateFoundPayload>(Events.SHOW_UPDATE_FOUND);
+export const onShowUpdateReleaseNotes = createListener<ShowUpdateReleaseNotesPayload>(Events.SHOW_UPDATE_RELEASE_NOTES);
+export const onShowUpdateReleaseNotesFailed = createListener<ShowUpdateReleaseNotesFailedPayload>(Events.SHOW_UPDATE_RELEASE_NOTES_FAILED);
+export const onShowUpdateNotFound = createListener<ShowUpdateNotFoundPayload>(Events.SHOW_UPDATE_NOT_FOUND);
+export const onShowUpdaterError = createListener<ShowUpdaterErrorPayload>(Events.SHOW_UPDATER_ERROR);
+export const onShowDownloadInitiated = createListener<ShowDownloadInitiatedPayload>(Events.SHOW_DOWNLOAD_INITIATED);
+export const onShowExtractingUpdate = createListener<ShowExtractingUpdatePayload>(Events.SHOW_EXTRACTING_UPDATE);
+export const onShowExtractionProgress = createListener<ShowExtractionProgressPayload>(Events.SHOW_EXTRACTION_PROGRESS);
+export const onShowReadyToInstall = createListener<ShowReadyToInstallPayload>(Events.SHOW_READY_TO_INSTALL);
+export const onShowInstallingUpdate = createListener<ShowInstallingUpdatePayload>(Events.SHOW_INSTALLING_UPDATE);
+export const onShowUpdateInstalled = createListener<ShowUpdateInstalledPayload>(Events.SHOW_UPDATE_INSTALLED);
+export const onShowUpdateInFocus = createListener<ShowUpdateInFocusPayload>(Events.SHOW_UPDATE_IN_FOCUS);
+export const onDismissUpdateInstallation = createListener<DismissUpdateInstallationPayload>(Events.DISMISS_UPDATE_INSTALLATION);
+export const onDownloadProgress = createListener<DownloadProgressPayload>(Events.DOWNLOAD_PROGRESS);
 
 const ALL_EVENTS = Object.values(Events);
 
@@ -0,0 +1,508 @@
+//! Reads custom elements and release notes of appcast items from raw appcast XML.
+//!
+//! Custom elements match what Sparkle exposes through `SPUAppcastItem.propertiesDictionary`
+//! and the plugin reports as [`UpdateInfo::extra`](crate::UpdateInfo::extra), so server-side
+//! tooling can validate an appcast against what the app will see.
+
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::{NsReader, XmlVersion};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::events::UpdateInfo;
+use crate::{Error, Result};
+
+/// Namespace URI of Sparkle's appcast elements.
+pub const SPARKLE_NAMESPACE: &str = "http://www.andymatuschak.org/xml-namespaces/sparkle";
+
+/// RSS item elements Sparkle maps to [`UpdateInfo`](crate::UpdateInfo) fields.
+const RSS_ELEMENTS: &[&str] = &[
+    "title",
+    "link",
+    "description",
+    "pubDate",
+    "enclosure",
+    "guid",
+];
+
+/// Whether an item element is already reported through dedicated `UpdateInfo` fields.
+///
+/// Sparkle matches its own elements by the literal `sparkle:` prefix, so this does too.
+pub(crate) fn is_standard_element(qualified_name: &str) -> bool {
+    qualified_name.starts_with("sparkle:") || RSS_ELEMENTS.contains(&qualified_name)
+}
+
+/// A custom element of an appcast item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ElementName {
+    /// Name as written in the appcast, e.g. `myapp:migrationRequired`.
+    pub qualified: String,
+    /// Namespace URI the prefix is bound to, if any.
+    pub namespace: Option<String>,
+    pub local: String,
+}
+
+/// Custom elements of one appcast item.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemExtras {
+    /// `sparkle:version` of the item, from the element or the enclosure attribute.
+    pub build_version: Option<String>,
+    /// Custom elements keyed by qualified name, as in `UpdateInfo::extra`. Later duplicates
+    /// replace earlier ones.
+    pub extra: Map<String, Value>,
+    pub names: Vec<ElementName>,
+}
+
+impl ItemExtras {
+    /// Looks up a custom element by namespace URI and local name, whatever prefix the
+    /// appcast binds the namespace to.
+    pub fn get(&self, namespace: &str, local_name: &str) -> Option<&Value> {
+        self.names
+            .iter()
+            .find(|name| name.namespace.as_deref() == Some(namespace) && name.local == local_name)
+            .and_then(|name| self.extra.get(&name.qualified))
+    }
+}
+
+/// The release notes of one appcast item and what selects them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppcastRelease {
+    /// Display version (`sparkle:shortVersionString`), or the build version without one.
+    pub version: String,
+    pub build_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Inline notes from `description`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_notes: Option<String>,
+    /// `sparkle:releaseNotesLink`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_notes_url: Option<String>,
+    /// `sparkle:format` of the description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_description_format: Option<String>,
+    /// `pubDate` as written in the appcast.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_string: Option<String>,
+}
+
+impl From<&UpdateInfo> for AppcastRelease {
+    fn from(update: &UpdateInfo) -> Self {
+        Self {
+            version: update.version.clone(),
+            build_version: update.build_version.clone(),
+            channel: update.channel.clone(),
+            release_notes: update.release_notes.clone(),
+            release_notes_url: update.release_notes_url.clone(),
+            item_description_format: update.item_description_format.clone(),
+            date_string: update.date_string.clone(),
+        }
+    }
+}
+
+/// An appcast item as read by [`parse_items`].
+#[derive(Default)]
+struct ParsedItem {
+    extras: ItemExtras,
+    /// Text of the RSS and Sparkle elements by qualified name. The first occurrence wins.
+    standard: HashMap<String, String>,
+    /// `sparkle:shortVersionString` attribute of the enclosure.
+    enclosure_short_version: Option<String>,
+    /// `sparkle:format` attribute of the description.
+    description_format: Option<String>,
+}
+
+impl ParsedItem {
+    fn release(mut self) -> Option<AppcastRelease> {
+        let build_version = self.extras.build_version.filter(|v| !v.is_empty())?;
+        let mut standard = |name: &str| self.standard.remove(name).filter(|v| !v.is_empty());
+        Some(AppcastRelease {
+            version: standard("sparkle:shortVersionString")
+                .or(self.enclosure_short_version)
+                .unwrap_or_else(|| build_version.clone()),
+            channel: standard("sparkle:channel"),
+            release_notes: standard("description"),
+            release_notes_url: standard("sparkle:releaseNotesLink"),
+            date_string: standard("pubDate"),
+            item_description_format: self.description_format,
+            build_version,
+        })
+    }
+}
+
+/// Reads the release notes of every `<item>` with a build version, in document order.
+pub fn parse_releases(xml: &str) -> Result<Vec<AppcastRelease>> {
+    Ok(parse_items(xml)?
+        .into_iter()
+        .filter_map(ParsedItem::release)
+        .collect())
+}
+
+/// An item child element whose text is being collected.
+struct OpenElement {
+    name: ElementName,
+    text: String,
+}
+
+/// Extracts the custom elements of every `<item>` in an appcast, in document order.
+pub fn parse_item_extras(xml: &str) -> Result<Vec<ItemExtras>> {
+    Ok(parse_items(xml)?
+        .into_iter()
+        .map(|item| item.extras)
+        .collect())
+}
+
+fn parse_items(xml: &str) -> Result<Vec<ParsedItem>> {
+    let mut reader = NsReader::from_str(xml);
+    let mut items = Vec::new();
+    let mut item: Option<ParsedItem> = None;
+    let mut element: Option<OpenElement> = None;
+    // Depth below the current <item>: 1 for its children, 2 and more for their descendants.
+    let mut depth = 0usize;
+
+    loop {
+        let (resolved, event) = reader.read_resolved_event().map_err(appcast_error)?;
+        match event {
+            Event::Start(start) => {
+                if let Some(current) = item.as_mut() {
+                    depth += 1;
+                    if depth == 1 {
+                        read_attributes(current, &start)?;
+                        element = Some(OpenElement {
+                            name: element_name(&start, resolved),
+                            text: String::new(),
+                        });
+                    }
+                } else if is_item(&start, &resolved) {
+                    item = Some(ParsedItem::default());
+                    depth = 0;
+                }
+            }
+            Event::Empty(start) => {
+                if let Some(current) = item.as_mut() {
+                    if depth == 0 {
+                        read_attributes(current, &start)?;
+                        finish_element(
+                            current,
+                            OpenElement {
+                                name: element_name(&start, resolved),
+                                text: String::new(),
+                            },
+                        );
+                    }
+                } else if is_item(&start, &resolved) {
+                    items.push(ParsedItem::default());
+                }
+            }
+            Event::Text(text) => {
+                if let Some(open) = element.as_mut() {
+                    open.text.push_str(&text.xml10_content());
+                }
+            }
+            Event::CData(data) => {
+                if let Some(open) = element.as_mut() {
+                    open.text.push_str(&data.xml10_content());
+                }
+            }
+            Event::GeneralRef(reference) => {
+                if let Some(open) = element.as_mut() {
+                    let resolved = match reference.resolve_char_ref().map_err(appcast_error)? {
+                        Some(c) => c.to_string(),
+                        None => quick_xml::escape::resolve_predefined_entity(&reference)
+                            .ok_or_else(|| {
+                                Error::InvalidAppcast(format!("unknown entity &{};", &*reference))
+                            })?
+                            .to_string(),
+                    };
+                    open.text.push_str(&resolved);
+                }
+            }
+            Event::End(_) => {
+                if let Some(current) = item.as_mut() {
+                    if depth == 0 {
+                        items.extend(item.take());
+                        continue;
+                    }
+                    depth -= 1;
+                    if depth == 0 {
+                        if let Some(open) = element.take() {
+                            finish_element(current, open);
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(items)
+}
+
+fn is_item(start: &BytesStart, resolved: &ResolveResult) -> bool {
+    matches!(resolved, ResolveResult::Unbound) && start.local_name().as_ref() == "item"
+}
+
+fn element_name(start: &BytesStart, resolved: ResolveResult) -> ElementName {
+    ElementName {
+        qualified: start.name().as_ref().to_string(),
+        namespace: match resolved {
+            ResolveResult::Bound(namespace) => Some(namespace.as_ref().to_string()),
+            _ => None,
+        },
+        local: start.local_name().as_ref().to_string(),
+    }
+}
+
+/// Reads the versions of the enclosure and the format of the description.
+fn read_attributes(item: &mut ParsedItem, start: &BytesStart) -> Result<()> {
+    let element = start.name();
+    if element.as_ref() != "enclosure" && element.as_ref() != "description" {
+        return Ok(());
+    }
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(appcast_error)?;
+        let target = match (element.as_ref(), attribute.key.as_ref()) {
+            ("enclosure", "sparkle:version") if item.extras.build_version.is_none() => {
+                &mut item.extras.build_version
+            }
+            ("enclosure", "sparkle:shortVersionString") => &mut item.enclosure_short_version,
+            ("description", "sparkle:format") => &mut item.description_format,
+            _ => continue,
+        };
+        let value = attribute
+            .normalized_value(XmlVersion::Implicit1_0)
+            .map_err(appcast_error)?;
+        *target = Some(value.into_owned());
+    }
+    Ok(())
+}
+
+fn finish_element(parsed: &mut ParsedItem, element: OpenElement) {
+    let OpenElement { name, text } = element;
+    let item = &mut parsed.extras;
+    if name.qualified == "sparkle:version" {
+        item.build_version = Some(text.trim().to_string());
+    } else if is_standard_element(&name.qualified) {
+        parsed
+            .standard
+            .entry(name.qualified)
+            .or_insert_with(|| text.trim().to_string());
+    } else {
+        item.extra.insert(
+            name.qualified.clone(),
+            Value::String(text.trim().to_string()),
+        );
+        item.names
+            .retain(|existing| existing.qualified != name.qualified);
+        item.names.push(name);
+    }
+}
+
+fn appcast_error(error: impl std::fmt::Display) -> Error {
+    Error::InvalidAppcast(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APPCAST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:sparkle="http://www.andymatuschak.org/xml-namespaces/sparkle"
+    xmlns:myapp="https://example.com/appcast">
+  <channel>
+    <title>Example</title>
+    <item>
+      <title>Version 2.0</title>
+      <sparkle:version>200</sparkle:version>
+      <sparkle:shortVersionString>2.0</sparkle:shortVersionString>
+      <sparkle:channel>beta</sparkle:channel>
+      <sparkle:releaseNotesLink>https://example.com/2.0.md</sparkle:releaseNotesLink>
+      <pubDate>Mon, 05 Oct 2026 10:00:00 +0000</pubDate>
+      <description sparkle:format="markdown"><![CDATA[# Faster & <b>smaller</b>]]></description>
+      <myapp:migrationRequired>true</myapp:migrationRequired>
+      <myapp:notes>first</myapp:notes>
+      <myapp:notes>  second &amp; last  </myapp:notes>
+      <myapp:nested><inner>text</inner></myapp:nested>
+      <plain/>
+      <enclosure url="https://example.com/2.0.zip" length="10" sparkle:version="199" />
+    </item>
+    <item>
+      <description>Fixes &lt;crashes&gt;</description>
+      <enclosure url="https://example.com/1.1.zip" sparkle:version="110"
+          sparkle:shortVersionString="1.1" />
+    </item>
+    <item>
+      <title>No version</title>
+      <myapp:notes>orphan</myapp:notes>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn parses_releases() {
+        let releases = parse_releases(APPCAST).unwrap();
+        assert_eq!(
+            releases,
+            [
+                AppcastRelease {
+                    version: "2.0".to_string(),
+                    build_version: "200".to_string(),
+                    channel: Some("beta".to_string()),
+                    release_notes: Some("# Faster & <b>smaller</b>".to_string()),
+                    release_notes_url: Some("https://example.com/2.0.md".to_string()),
+                    item_description_format: Some("markdown".to_string()),
+                    date_string: Some("Mon, 05 Oct 2026 10:00:00 +0000".to_string()),
+                },
+                AppcastRelease {
+                    version: "1.1".to_string(),
+                    build_version: "110".to_string(),
+                    release_notes: Some("Fixes <crashes>".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn version_falls_back_to_build_version() {
+        let releases = parse_releases(
+            r#"<rss><channel><item><enclosure sparkle:version="7"/></item></channel></rss>"#,
+        )
+        .unwrap();
+        assert_eq!(releases[0].version, "7");
+        assert_eq!(releases[0].build_version, "7");
+    }
+
+    #[test]
+    fn parses_extras() {
+        let items = parse_item_extras(APPCAST).unwrap();
+        assert_eq!(items.len(), 3);
+
+        let first = &items[0];
+        assert_eq!(first.build_version.as_deref(), Some("200"));
+        let mut keys: Vec<&str> = first.extra.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            [
+                "myapp:migrationRequired",
+                "myapp:nested",
+                "myapp:notes",
+                "plain"
+            ]
+        );
+        // The last duplicate wins, trimmed.
+        assert_eq!(first.extra["myapp:notes"], "second & last");
+        assert_eq!(first.extra["myapp:nested"], "text");
+        assert_eq!(first.extra["plain"], "");
+        assert!(first
+            .names
+            .iter()
+            .all(|name| name.qualified != "sparkle:channel"));
+        assert_eq!(
+            first
+                .names
+                .iter()
+                .filter(|name| name.local == "notes")
+                .count(),
+            1
+        );
+
+        assert_eq!(items[1].build_version.as_deref(), Some("110"));
+        assert!(items[1].extra.is_empty());
+        assert_eq!(items[2].build_version, None);
+        assert_eq!(items[2].extra["myapp:notes"], "orphan");
+    }
+
+    #[test]
+    fn records_namespaces_of_extras() {
+        let first = &parse_item_extras(APPCAST).unwrap()[0];
+        let name = |qualified: &str| {
+            first
+                .names
+                .iter()
+                .find(|name| name.qualified == qualified)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(
+            name("myapp:migrationRequired"),
+            ElementName {
+                qualified: "myapp:migrationRequired".to_string(),
+                namespace: Some("https://example.com/appcast".to_string()),
+                local: "migrationRequired".to_string(),
+            }
+        );
+        assert_eq!(name("plain").namespace, None);
+        assert_eq!(
+            first.get("https://example.com/appcast", "migrationRequired"),
+            Some(&Value::from("true"))
+        );
+        assert_eq!(
+            first.get("https://example.com/other", "migrationRequired"),
+            None
+        );
+        assert_eq!(first.get("https://example.com/appcast", "missing"), None);
+    }
+
+    #[test]
+    fn get_finds_extras_under_a_rebound_prefix() {
+        let xml = r#"<rss xmlns:sparkle="http://www.andymatuschak.org/xml-namespaces/sparkle">
+  <channel>
+    <item xmlns:custom="https://example.com/appcast">
+      <sparkle:version>3</sparkle:version>
+      <custom:migrationRequired>yes</custom:migrationRequired>
+      <other:migrationRequired xmlns:other="https://example.com/other">no</other:migrationRequired>
+    </item>
+    <item xmlns:myapp="https://example.com/other">
+      <sparkle:version>4</sparkle:version>
+      <myapp:migrationRequired>no</myapp:migrationRequired>
+    </item>
+  </channel>
+</rss>"#;
+        let items = parse_item_extras(xml).unwrap();
+
+        assert_eq!(items[0].extra["custom:migrationRequired"], "yes");
+        assert_eq!(
+            items[0].get("https://example.com/appcast", "migrationRequired"),
+            Some(&Value::from("yes"))
+        );
+        assert_eq!(
+            items[0].get("https://example.com/other", "migrationRequired"),
+            Some(&Value::from("no"))
+        );
+        // `myapp` is bound to another namespace here, so the element does not match.
+        assert_eq!(
+            items[1].get("https://example.com/appcast", "migrationRequired"),
+            None
+        );
+    }
+
+    #[test]
+    fn sparkle_elements_match_by_prefix() {
+        assert!(is_standard_element("sparkle:version"));
+        assert!(is_standard_element("enclosure"));
+        assert!(is_standard_element("pubDate"));
+        assert!(!is_standard_element("myapp:version"));
+        assert!(!is_standard_element("version"));
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        for xml in [
+            "<rss><channel><item><title>2.0</item></channel></rss>",
+            "<rss><channel><item><title>&bogus;</title></item></channel></rss>",
+        ] {
+            assert!(
+                matches!(parse_releases(xml), Err(Error::InvalidAppcast(_))),
+                "{}",
+                xml
+            );
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use crate::Result;
+
+/// Performs update checks in place of Sparkle's updater.
+///
+/// Only the check operations are routed to the backend. Settings, policies and events keep
+/// going through Sparkle and the plugin's delegate, so a backend can drive checks from an
+/// app-specific transport or a test harness.
+pub trait UpdaterBackend: Send + Sync {
+    fn check_for_updates(&self) -> Result<()>;
+
+    fn check_for_updates_in_background(&self) -> Result<()>;
+
+    fn check_for_update_information(&self) -> Result<()>;
+
+    fn can_check_for_updates(&self) -> Result<bool> {
+        Ok(true)
+    }
+}
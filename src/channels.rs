@@ -0,0 +1,175 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::version::VersionComparator;
+
+/// An update channel users can enroll in, e.g. `beta`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Channel {
+    /// Channel name as used in the appcast (`<sparkle:channel>`).
+    pub id: String,
+    pub display_name: String,
+}
+
+impl Channel {
+    pub fn new(id: impl Into<String>, display_name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            display_name: display_name.into(),
+        }
+    }
+}
+
+/// Payload of `sparkle://channel-changed`, also returned by enroll and leave.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelChange {
+    pub channel: String,
+    pub enrolled: bool,
+    pub enrollment: Vec<Channel>,
+    /// Whether the running build is newer than the latest build on the default channel.
+    /// Only ever true when leaving a channel. `None` until an appcast has loaded, since the
+    /// latest stable build is not known before.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ahead_of_stable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stable_version: Option<String>,
+}
+
+/// Returns the allowed channels after enrolling in `channel`.
+pub(crate) fn enroll(current: Option<Vec<String>>, channel: &str) -> Vec<String> {
+    let mut channels = current.unwrap_or_default();
+    if !channels.iter().any(|c| c == channel) {
+        channels.push(channel.to_string());
+    }
+    channels
+}
+
+/// Returns the allowed channels after leaving `channel`, or `None` when only the default
+/// channel remains.
+pub(crate) fn leave(current: Option<Vec<String>>, channel: &str) -> Option<Vec<String>> {
+    let channels: Vec<String> = current
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c != channel)
+        .collect();
+    (!channels.is_empty()).then_some(channels)
+}
+
+/// Resolves enrolled channel ids against the declared channels. Undeclared ids keep their
+/// id as display name.
+pub(crate) fn resolve(known: &[Channel], enrolled: &[String]) -> Vec<Channel> {
+    enrolled
+        .iter()
+        .map(|id| {
+            known
+                .iter()
+                .find(|c| &c.id == id)
+                .cloned()
+                .unwrap_or_else(|| Channel::new(id.clone(), id.clone()))
+        })
+        .collect()
+}
+
+/// Whether `installed` is newer than the latest stable build, or `None` when no appcast
+/// has reported one yet.
+pub(crate) fn is_ahead_of_stable(
+    comparator: &dyn VersionComparator,
+    installed: &str,
+    stable: Option<&str>,
+) -> Option<bool> {
+    stable.map(|stable| comparator.compare(installed, stable) == Ordering::Greater)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::StandardVersionComparator;
+
+    fn ids(channels: &[&str]) -> Vec<String> {
+        channels.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn enroll_adds_channel_once() {
+        assert_eq!(enroll(None, "beta"), ids(&["beta"]));
+        assert_eq!(enroll(Some(ids(&["beta"])), "beta"), ids(&["beta"]));
+        assert_eq!(
+            enroll(Some(ids(&["beta"])), "nightly"),
+            ids(&["beta", "nightly"])
+        );
+    }
+
+    #[test]
+    fn leave_keeps_stable_implicit() {
+        assert_eq!(leave(Some(ids(&["beta"])), "beta"), None);
+        assert_eq!(leave(None, "beta"), None);
+        assert_eq!(leave(Some(Vec::new()), "beta"), None);
+        assert_eq!(
+            leave(Some(ids(&["beta", "nightly"])), "beta"),
+            Some(ids(&["nightly"]))
+        );
+        assert_eq!(
+            leave(Some(ids(&["nightly"])), "beta"),
+            Some(ids(&["nightly"]))
+        );
+    }
+
+    #[test]
+    fn resolve_uses_declared_names() {
+        let known = [
+            Channel::new("beta", "Beta"),
+            Channel::new("nightly", "Nightly"),
+        ];
+        assert_eq!(
+            resolve(&known, &ids(&["nightly", "internal"])),
+            [
+                Channel::new("nightly", "Nightly"),
+                Channel::new("internal", "internal"),
+            ]
+        );
+        assert!(resolve(&known, &[]).is_empty());
+    }
+
+    #[test]
+    fn ahead_of_stable_compares_builds() {
+        let comparator = StandardVersionComparator;
+        assert_eq!(
+            is_ahead_of_stable(&comparator, "201", Some("200")),
+            Some(true)
+        );
+        assert_eq!(
+            is_ahead_of_stable(&comparator, "200", Some("200")),
+            Some(false)
+        );
+        assert_eq!(
+            is_ahead_of_stable(&comparator, "199", Some("200")),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn ahead_of_stable_is_unknown_before_an_appcast_loads() {
+        assert_eq!(
+            is_ahead_of_stable(&StandardVersionComparator, "201", None),
+            None
+        );
+    }
+
+    #[test]
+    fn unknown_ahead_of_stable_is_not_serialized() {
+        let change = ChannelChange {
+            channel: "beta".to_string(),
+            enrolled: false,
+            enrollment: Vec::new(),
+            ahead_of_stable: None,
+            stable_version: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            serde_json::json!({ "channel": "beta", "enrolled": false, "enrollment": [] })
+        );
+    }
+}
@@ -1,8 +1,18 @@
 use std::collections::HashMap;
 
-use tauri::{command, AppHandle, Runtime};
+use tauri::{command, AppHandle, Runtime, Window};
 
+use crate::channels::{Channel, ChannelChange};
+use crate::consent::UpdateConsent;
+use crate::diagnostics::DiagnosticsReport;
 use crate::events::UpdateInfo;
+use crate::health::UpdateHealth;
+use crate::install_blockers::InstallBlockStatus;
+use crate::minimum_version::UpdateRequiredInfo;
+use crate::release_notes::{CumulativeNotesOptions, CumulativeReleaseNotes, ReleaseNotes};
+use crate::revocation::CurrentVersionRevoked;
+use crate::user_driver::{PendingRequest, UserDriverReply};
+use crate::whats_new::DidUpdateInfo;
 use crate::Error;
 use crate::Result;
 use crate::SparkleUpdaterExt;
@@ -290,3 +300,192 @@ pub(crate) async fn set_download_request_headers<R: Runtime>(
 pub(crate) async fn last_found_update<R: Runtime>(app: AppHandle<R>) -> Result<Option<UpdateInfo>> {
     get_updater!(app).last_found_update()
 }
+
+#[command]
+pub(crate) async fn channels<R: Runtime>(app: AppHandle<R>) -> Result<Vec<Channel>> {
+    get_updater!(app).channels()
+}
+
+#[command]
+pub(crate) async fn current_enrollment<R: Runtime>(app: AppHandle<R>) -> Result<Vec<Channel>> {
+    get_updater!(app).current_enrollment()
+}
+
+#[command]
+pub(crate) async fn enroll_channel<R: Runtime>(
+    app: AppHandle<R>,
+    channel: String,
+    reset_update_cycle: bool,
+) -> Result<ChannelChange> {
+    get_updater!(app).enroll(&channel, reset_update_cycle)
+}
+
+#[command]
+pub(crate) async fn leave_channel<R: Runtime>(
+    app: AppHandle<R>,
+    channel: String,
+    reset_update_cycle: bool,
+) -> Result<ChannelChange> {
+    get_updater!(app).leave(&channel, reset_update_cycle)
+}
+
+#[command]
+pub(crate) async fn start_updater<R: Runtime>(app: AppHandle<R>) -> Result<()> {
+    get_updater!(app).start_updater()
+}
+
+#[command]
+pub(crate) async fn is_started<R: Runtime>(app: AppHandle<R>) -> Result<bool> {
+    Ok(get_updater!(app).is_started())
+}
+
+#[command]
+pub(crate) async fn record_update_consent<R: Runtime>(
+    app: AppHandle<R>,
+    consent: UpdateConsent,
+) -> Result<()> {
+    get_updater!(app).record_update_consent(consent)
+}
+
+#[command]
+pub(crate) async fn should_prompt_for_permission<R: Runtime>(app: AppHandle<R>) -> Result<bool> {
+    get_updater!(app).should_prompt_for_permission()
+}
+
+#[command]
+pub(crate) async fn set_should_prompt_for_permission<R: Runtime>(
+    app: AppHandle<R>,
+    enabled: bool,
+) -> Result<()> {
+    get_updater!(app).set_should_prompt_for_permission(enabled)
+}
+
+#[command]
+pub(crate) async fn diagnostics<R: Runtime>(app: AppHandle<R>) -> Result<DiagnosticsReport> {
+    Ok(app.sparkle_diagnostics())
+}
+
+#[command]
+pub(crate) async fn reply_user_driver<R: Runtime>(
+    app: AppHandle<R>,
+    request_id: u64,
+    reply: UserDriverReply,
+) -> Result<()> {
+    get_updater!(app).reply_user_driver(request_id, reply)
+}
+
+#[command]
+pub(crate) async fn pending_user_driver_requests<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<PendingRequest>> {
+    get_updater!(app).pending_user_driver_requests()
+}
+
+#[command]
+pub(crate) async fn cancel_update<R: Runtime>(app: AppHandle<R>) -> Result<()> {
+    get_updater!(app).cancel_update()
+}
+
+#[command]
+pub(crate) async fn skip_update<R: Runtime>(app: AppHandle<R>) -> Result<()> {
+    get_updater!(app).skip_update()
+}
+
+#[command]
+pub(crate) async fn remind_later<R: Runtime>(app: AppHandle<R>) -> Result<()> {
+    get_updater!(app).remind_later()
+}
+
+#[command]
+pub(crate) async fn install_update_now<R: Runtime>(app: AppHandle<R>) -> Result<()> {
+    get_updater!(app).install_update_now()
+}
+
+#[command]
+pub(crate) async fn block_install<R: Runtime>(app: AppHandle<R>, reason: String) -> Result<u64> {
+    Ok(get_updater!(app).acquire_install_blocker(reason))
+}
+
+#[command]
+pub(crate) async fn unblock_install<R: Runtime>(app: AppHandle<R>, token: u64) -> Result<()> {
+    get_updater!(app).release_install_blocker(token)
+}
+
+#[command]
+pub(crate) async fn install_block_status<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<InstallBlockStatus> {
+    Ok(get_updater!(app).install_block_status())
+}
+
+#[command]
+pub(crate) async fn ready_to_quit<R: Runtime>(app: AppHandle<R>) -> Result<()> {
+    get_updater!(app).ready_to_quit()
+}
+
+#[command]
+pub(crate) async fn consume_whats_new<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+) -> Result<Option<DidUpdateInfo>> {
+    Ok(get_updater!(app).consume_whats_new(window.label()))
+}
+
+#[command]
+pub(crate) async fn mark_update_healthy<R: Runtime>(app: AppHandle<R>) -> Result<bool> {
+    Ok(get_updater!(app).mark_update_healthy())
+}
+
+#[command]
+pub(crate) async fn update_health<R: Runtime>(app: AppHandle<R>) -> Result<UpdateHealth> {
+    Ok(get_updater!(app).update_health())
+}
+
+#[command]
+pub(crate) async fn update_required<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Option<UpdateRequiredInfo>> {
+    Ok(get_updater!(app).update_required())
+}
+
+#[command]
+pub(crate) async fn check_minimum_version<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Option<UpdateRequiredInfo>> {
+    get_updater!(app).check_minimum_version()
+}
+
+#[command]
+pub(crate) async fn install_required_update<R: Runtime>(app: AppHandle<R>) -> Result<()> {
+    get_updater!(app).install_required_update()
+}
+
+#[command]
+pub(crate) async fn current_version_revoked<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Option<CurrentVersionRevoked>> {
+    Ok(get_updater!(app).current_version_revoked())
+}
+
+#[command]
+pub(crate) async fn check_revoked_releases<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Option<CurrentVersionRevoked>> {
+    get_updater!(app).check_revoked_releases()
+}
+
+#[command]
+pub(crate) async fn release_notes<R: Runtime>(
+    app: AppHandle<R>,
+    update: UpdateInfo,
+) -> Result<ReleaseNotes> {
+    get_updater!(app).release_notes(&update)
+}
+
+#[command]
+pub(crate) async fn cumulative_release_notes<R: Runtime>(
+    app: AppHandle<R>,
+    options: Option<CumulativeNotesOptions>,
+) -> Result<CumulativeReleaseNotes> {
+    get_updater!(app).cumulative_release_notes(options.unwrap_or_default())
+}
@@ -0,0 +1,486 @@
+//! Plugin configuration read from `plugins.sparkle-updater` in `tauri.conf.json`.
+//!
+//! Precedence, highest first:
+//! 1. Values changed at runtime through the plugin API (Sparkle keeps them in user defaults,
+//!    delegate-side settings live in the settings store when persistence is enabled)
+//! 2. `plugins.sparkle-updater` in `tauri.conf.json`
+//! 3. Info.plist keys (`SUFeedURL`, `SUEnableAutomaticChecks`, ...)
+//! 4. Sparkle's built-in defaults
+//!
+//! Sparkle settings are registered in the user defaults registration domain, which sits
+//! below the values Sparkle writes itself and above Info.plist.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::channels::Channel;
+use crate::health::HealthCheckConfig;
+use crate::minimum_version::MinimumVersionConfig;
+use crate::revocation::RevokedReleasesConfig;
+use crate::{Error, Result};
+
+/// Placeholders available in `httpHeaders` values.
+pub const HEADER_TEMPLATE_VARIABLES: &[&str] = &["version", "build", "arch", "identifier"];
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Config {
+    /// Appcast feed URL (`SUFeedURL`).
+    pub feed_url: Option<String>,
+    /// Channels users can enroll in.
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+    /// Update check interval in seconds (`SUScheduledCheckInterval`).
+    pub check_interval: Option<f64>,
+    /// `SUEnableAutomaticChecks`
+    pub automatically_checks_for_updates: Option<bool>,
+    /// `SUAutomaticallyUpdate`
+    pub automatically_downloads_updates: Option<bool>,
+    /// Whether to relaunch the app after installing an update.
+    pub relaunch: Option<bool>,
+    /// HTTP headers sent with update requests. Values may contain `{version}`, `{build}`,
+    /// `{arch}` and `{identifier}` placeholders.
+    #[serde(default)]
+    pub http_headers: HashMap<String, String>,
+    /// Restrictions applied to every feed URL the plugin is asked to use.
+    #[serde(default)]
+    pub feed_policy: FeedPolicy,
+    /// Whether Sparkle starts during plugin setup or waits for `start_updater`.
+    pub start_mode: Option<StartMode>,
+    /// Answer to Sparkle's "should prompt for permission" question. `false` suppresses
+    /// Sparkle's own prompt, e.g. when the app asks for update consent itself.
+    pub prompt_for_permission: Option<bool>,
+    /// Whether Sparkle shows its own windows or leaves the update UI to the webview.
+    pub user_driver: Option<UserDriverMode>,
+    /// Seconds the webview has to answer a headless user driver question before the
+    /// default answer is given.
+    pub reply_timeout: Option<f64>,
+    /// Minimum seconds between two `sparkle://download-progress` events. `0` emits one per
+    /// chunk Sparkle receives.
+    pub download_progress_interval: Option<f64>,
+    /// Seconds the webview has to save its state when the app quits with an update staged
+    /// for install on quit. `0` skips saving; the quit still waits for install blockers.
+    pub install_quit_timeout: Option<f64>,
+    /// Requires the app to confirm every installed update with `mark_update_healthy`.
+    pub health_check: Option<HealthCheckConfig>,
+    /// Where the minimum supported version is declared.
+    pub minimum_version: Option<MinimumVersionConfig>,
+    /// Where releases pulled after shipping are listed.
+    pub revoked_releases: Option<RevokedReleasesConfig>,
+}
+
+/// When Sparkle's updater is started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StartMode {
+    /// Start during plugin setup.
+    #[default]
+    Immediate,
+    /// Create the updater but wait for `start_updater`, e.g. until the user has given
+    /// update consent. Scheduled checks and check commands are unavailable until then.
+    Deferred,
+}
+
+/// Who renders the update UI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UserDriverMode {
+    /// Sparkle's own AppKit windows.
+    #[default]
+    Standard,
+    /// No Sparkle windows. Every UI decision is emitted as an event and answered with
+    /// `reply_user_driver`.
+    Headless,
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(url) = &self.feed_url {
+            self.feed_policy.check(url).map_err(|e| match e {
+                Error::InvalidFeedUrl(_) => invalid(format!("feedUrl is not a valid URL: {}", url)),
+                e => e,
+            })?;
+        }
+
+        if let Some(interval) = self.check_interval {
+            if !interval.is_finite() || interval <= 0.0 {
+                return Err(invalid(format!(
+                    "checkInterval must be a positive number of seconds, got {}",
+                    interval
+                )));
+            }
+        }
+
+        if let Some(timeout) = self.reply_timeout {
+            if !timeout.is_finite() || timeout <= 0.0 {
+                return Err(invalid(format!(
+                    "replyTimeout must be a positive number of seconds, got {}",
+                    timeout
+                )));
+            }
+        }
+
+        if let Some(interval) = self.download_progress_interval {
+            if !interval.is_finite() || interval < 0.0 {
+                return Err(invalid(format!(
+                    "downloadProgressInterval must be a non-negative number of seconds, got {}",
+                    interval
+                )));
+            }
+        }
+
+        if let Some(timeout) = self.install_quit_timeout {
+            if !timeout.is_finite() || timeout < 0.0 {
+                return Err(invalid(format!(
+                    "installQuitTimeout must be a non-negative number of seconds, got {}",
+                    timeout
+                )));
+            }
+        }
+
+        if let Some(health_check) = &self.health_check {
+            if health_check.max_launches == Some(0) {
+                return Err(invalid(
+                    "healthCheck.maxLaunches must be at least 1".to_string(),
+                ));
+            }
+            if let Some(timeout) = health_check.timeout {
+                if !timeout.is_finite() || timeout <= 0.0 {
+                    return Err(invalid(format!(
+                        "healthCheck.timeout must be a positive number of seconds, got {}",
+                        timeout
+                    )));
+                }
+            }
+            if health_check.rollback_channel.as_deref() == Some("") {
+                return Err(invalid(
+                    "healthCheck.rollbackChannel must not be empty".to_string(),
+                ));
+            }
+        }
+
+        if let Some(minimum_version) = &self.minimum_version {
+            minimum_version.validate("minimumVersion")?;
+        }
+
+        if let Some(revoked_releases) = &self.revoked_releases {
+            revoked_releases.validate("revokedReleases")?;
+        }
+
+        for (index, channel) in self.channels.iter().enumerate() {
+            if channel.id.is_empty() {
+                return Err(invalid("channel ids must not be empty".to_string()));
+            }
+            if self.channels[..index].iter().any(|c| c.id == channel.id) {
+                return Err(invalid(format!("duplicate channel id: {}", channel.id)));
+            }
+        }
+
+        let placeholders: Vec<(&str, &str)> = HEADER_TEMPLATE_VARIABLES
+            .iter()
+            .map(|name| (*name, ""))
+            .collect();
+        for (name, value) in &self.http_headers {
+            if name.is_empty() {
+                return Err(invalid("HTTP header names must not be empty".to_string()));
+            }
+            render_template(value, &placeholders)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `httpHeaders` with placeholders replaced.
+    pub fn render_http_headers(&self, vars: &[(&str, &str)]) -> Result<HashMap<String, String>> {
+        self.http_headers
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), render_template(value, vars)?)))
+            .collect()
+    }
+}
+
+/// Restrictions on feed URLs, applied to the configured feed URL as well as
+/// `set_feed_url` and `set_feed_url_override`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FeedPolicy {
+    /// Only accept `https` feed URLs.
+    #[serde(default)]
+    pub require_https: bool,
+    /// Hosts feed URLs may point to. `*.example.com` matches any subdomain.
+    /// An empty list allows every host.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+}
+
+impl FeedPolicy {
+    pub fn check(&self, url: &str) -> Result<()> {
+        let parsed = Url::parse(url).map_err(|_| Error::InvalidFeedUrl(url.to_string()))?;
+
+        if self.require_https && parsed.scheme() != "https" {
+            return Err(Error::FeedUrlRejected(format!(
+                "{} does not use https",
+                url
+            )));
+        }
+
+        if !self.allowed_hosts.is_empty() {
+            let host = parsed.host_str().unwrap_or_default();
+            if !self
+                .allowed_hosts
+                .iter()
+                .any(|pattern| host_matches(pattern, host))
+            {
+                return Err(Error::FeedUrlRejected(format!(
+                    "{} is not an allowed feed host",
+                    host
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.ends_with('.') && sub.len() > 1),
+        None => pattern.eq_ignore_ascii_case(host),
+    }
+}
+
+/// Replaces `{name}` placeholders. Unknown placeholders are an error.
+pub(crate) fn render_template(template: &str, vars: &[(&str, &str)]) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| invalid(format!("unclosed placeholder in {:?}", template)))?;
+        let name = &after[..end];
+        let value = vars
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "unknown placeholder {{{}}} in {:?}",
+                    name, template
+                ))
+            })?;
+        rendered.push_str(value);
+        rest = &after[end + 1..];
+    }
+
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidConfig(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn config(value: serde_json::Value) -> Result<Config> {
+        let config: Config = serde_json::from_value(value)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn invalid_config(value: serde_json::Value) -> String {
+        match config(value) {
+            Err(Error::InvalidConfig(message)) => message,
+            other => panic!("expected an invalid config, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parses_full_config() {
+        let config = config(json!({
+            "feedUrl": "https://updates.example.com/appcast.xml",
+            "channels": [{ "id": "beta", "displayName": "Beta" }],
+            "checkInterval": 3600,
+            "relaunch": false,
+            "httpHeaders": { "X-App-Version": "{version} ({build})" },
+            "feedPolicy": { "requireHttps": true, "allowedHosts": ["*.example.com"] },
+            "startMode": "deferred",
+            "userDriver": "headless",
+            "installQuitTimeout": 0,
+        }))
+        .unwrap();
+        assert_eq!(config.channels, [Channel::new("beta", "Beta")]);
+        assert_eq!(config.start_mode, Some(StartMode::Deferred));
+        assert_eq!(config.user_driver, Some(UserDriverMode::Headless));
+        assert_eq!(config.relaunch, Some(false));
+    }
+
+    #[test]
+    fn empty_config_is_valid() {
+        assert!(config(json!({})).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(matches!(
+            config(json!({ "feedURL": "https://example.com/appcast.xml" })),
+            Err(Error::Json(_))
+        ));
+        assert!(matches!(
+            config(json!({ "feedPolicy": { "requireHTTPS": true } })),
+            Err(Error::Json(_))
+        ));
+        assert!(matches!(
+            config(json!({ "minimumVersion": { "url": "https://example.com", "key": "" } })),
+            Err(Error::Json(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        for (value, message) in [
+            (json!({ "feedUrl": "not a url" }), "feedUrl"),
+            (json!({ "checkInterval": 0 }), "checkInterval"),
+            (json!({ "checkInterval": -60 }), "checkInterval"),
+            (json!({ "replyTimeout": 0 }), "replyTimeout"),
+            (
+                json!({ "downloadProgressInterval": -1 }),
+                "downloadProgressInterval",
+            ),
+            (json!({ "installQuitTimeout": -1 }), "installQuitTimeout"),
+            (
+                json!({ "healthCheck": { "maxLaunches": 0 } }),
+                "maxLaunches",
+            ),
+            (
+                json!({ "healthCheck": { "timeout": 0 } }),
+                "healthCheck.timeout",
+            ),
+            (
+                json!({ "healthCheck": { "rollbackChannel": "" } }),
+                "rollbackChannel",
+            ),
+            (json!({ "minimumVersion": {} }), "minimumVersion"),
+            (json!({ "revokedReleases": {} }), "revokedReleases"),
+            (
+                json!({ "channels": [{ "id": "", "displayName": "None" }] }),
+                "empty",
+            ),
+            (json!({ "httpHeaders": { "": "value" } }), "header names"),
+            (json!({ "httpHeaders": { "X-Os": "{os}" } }), "{os}"),
+        ] {
+            let error = invalid_config(value);
+            assert!(
+                error.contains(message),
+                "{:?} should mention {:?}",
+                error,
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_channels() {
+        let error = invalid_config(json!({
+            "channels": [
+                { "id": "beta", "displayName": "Beta" },
+                { "id": "beta", "displayName": "Beta 2" },
+            ]
+        }));
+        assert_eq!(error, "duplicate channel id: beta");
+    }
+
+    #[test]
+    fn feed_url_must_pass_feed_policy() {
+        assert!(matches!(
+            config(json!({
+                "feedUrl": "http://example.com/appcast.xml",
+                "feedPolicy": { "requireHttps": true },
+            })),
+            Err(Error::FeedUrlRejected(_))
+        ));
+    }
+
+    #[test]
+    fn renders_templates() {
+        let vars = [("version", "1.2.0"), ("build", "120")];
+        assert_eq!(
+            render_template("{version} ({build})", &vars).unwrap(),
+            "1.2.0 (120)"
+        );
+        assert_eq!(render_template("plain", &vars).unwrap(), "plain");
+        assert_eq!(render_template("{build}{build}", &vars).unwrap(), "120120");
+        assert!(matches!(
+            render_template("{arch}", &vars),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            render_template("{version", &vars),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn renders_http_headers() {
+        let config = config(json!({ "httpHeaders": { "X-Build": "build-{build}" } })).unwrap();
+        let headers = config.render_http_headers(&[("build", "120")]).unwrap();
+        assert_eq!(headers["X-Build"], "build-120");
+    }
+
+    #[test]
+    fn host_patterns() {
+        assert!(host_matches("example.com", "example.com"));
+        assert!(host_matches("EXAMPLE.com", "example.com"));
+        assert!(!host_matches("example.com", "updates.example.com"));
+
+        assert!(host_matches("*.example.com", "updates.example.com"));
+        assert!(host_matches("*.Example.COM", "a.b.example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", ".example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+    }
+
+    #[test]
+    fn feed_policy_checks_scheme_and_host() {
+        let policy = FeedPolicy {
+            require_https: true,
+            allowed_hosts: vec!["*.example.com".to_string(), "cdn.test".to_string()],
+        };
+        assert!(policy
+            .check("https://updates.example.com/appcast.xml")
+            .is_ok());
+        // Ports and case do not affect the host match.
+        assert!(policy.check("https://CDN.test:8443/appcast.xml").is_ok());
+        assert!(matches!(
+            policy.check("http://updates.example.com/appcast.xml"),
+            Err(Error::FeedUrlRejected(_))
+        ));
+        assert!(matches!(
+            policy.check("https://evil.test/appcast.xml"),
+            Err(Error::FeedUrlRejected(_))
+        ));
+        assert!(matches!(
+            policy.check("appcast.xml"),
+            Err(Error::InvalidFeedUrl(_))
+        ));
+    }
+
+    #[test]
+    fn default_feed_policy_allows_any_url() {
+        assert!(FeedPolicy::default()
+            .check("http://localhost:8000/appcast.xml")
+            .is_ok());
+    }
+}
@@ -0,0 +1,178 @@
+//! Deferred start of Sparkle's updater and the app's own update consent prompt.
+//!
+//! With [`StartMode::Deferred`], the updater is created during plugin setup but only
+//! started through `start_updater`, e.g. once the user answered the app's consent prompt.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::StartMode;
+use crate::{Error, Result};
+
+/// The user's answer to the app's own update consent prompt.
+///
+/// Recording it writes Sparkle's settings, which also stops Sparkle from showing its own
+/// permission prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateConsent {
+    pub automatically_checks_for_updates: bool,
+    pub automatically_downloads_updates: bool,
+}
+
+/// Whether Sparkle's updater is running, and why the last start failed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StartState {
+    started: bool,
+    error: Option<String>,
+}
+
+impl StartState {
+    /// The state after plugin setup. `start` is only called with [`StartMode::Immediate`].
+    /// A failed start is recorded rather than returned, so the plugin stays usable and
+    /// `start_updater` can retry, as with a deferred start.
+    pub fn launch(mode: StartMode, start: impl FnOnce() -> Result<()>) -> Self {
+        let mut state = Self::default();
+        if mode == StartMode::Immediate {
+            let _ = state.record(start());
+        }
+        state
+    }
+
+    /// Records the outcome of a start and returns it.
+    pub fn record(&mut self, result: Result<()>) -> Result<()> {
+        match &result {
+            Ok(()) => {
+                self.started = true;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        result
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Why the last start failed, if it did.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Fails with [`Error::UpdaterNotStarted`] until the updater is running.
+    pub fn ensure_started(&self) -> Result<()> {
+        if self.started {
+            Ok(())
+        } else {
+            Err(Error::UpdaterNotStarted)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn failure() -> Result<()> {
+        Err(Error::SparkleInit("no feed URL".to_string()))
+    }
+
+    #[test]
+    fn immediate_start_runs_during_setup() {
+        let state = StartState::launch(StartMode::Immediate, || Ok(()));
+        assert!(state.is_started());
+        assert_eq!(state.error(), None);
+        assert!(state.ensure_started().is_ok());
+    }
+
+    #[test]
+    fn deferred_start_waits() {
+        let called = Cell::new(false);
+        let state = StartState::launch(StartMode::Deferred, || {
+            called.set(true);
+            Ok(())
+        });
+        assert!(!called.get());
+        assert!(!state.is_started());
+        assert_eq!(state.error(), None);
+        assert!(matches!(
+            state.ensure_started(),
+            Err(Error::UpdaterNotStarted)
+        ));
+    }
+
+    #[test]
+    fn deferred_start_completes_later() {
+        let mut state = StartState::launch(StartMode::Deferred, || Ok(()));
+        assert!(state.record(Ok(())).is_ok());
+        assert!(state.is_started());
+        assert!(state.ensure_started().is_ok());
+    }
+
+    #[test]
+    fn failed_immediate_start_is_recorded() {
+        let state = StartState::launch(StartMode::Immediate, failure);
+        assert!(!state.is_started());
+        assert_eq!(
+            state.error(),
+            Some("Sparkle initialization failed: no feed URL")
+        );
+        assert!(matches!(
+            state.ensure_started(),
+            Err(Error::UpdaterNotStarted)
+        ));
+    }
+
+    #[test]
+    fn retry_clears_the_error() {
+        let mut state = StartState::launch(StartMode::Immediate, failure);
+        assert!(state.record(failure()).is_err());
+        assert!(state.error().is_some());
+
+        assert!(state.record(Ok(())).is_ok());
+        assert!(state.is_started());
+        assert_eq!(state.error(), None);
+    }
+
+    #[test]
+    fn start_mode_defaults_to_immediate() {
+        assert_eq!(StartMode::default(), StartMode::Immediate);
+        assert_eq!(
+            serde_json::from_value::<StartMode>(json!("deferred")).unwrap(),
+            StartMode::Deferred
+        );
+    }
+
+    #[test]
+    fn consent_uses_the_js_shape() {
+        let consent: UpdateConsent = serde_json::from_value(json!({
+            "automaticallyChecksForUpdates": true,
+            "automaticallyDownloadsUpdates": false,
+        }))
+        .unwrap();
+        assert_eq!(
+            consent,
+            UpdateConsent {
+                automatically_checks_for_updates: true,
+                automatically_downloads_updates: false,
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(consent).unwrap(),
+            json!({
+                "automaticallyChecksForUpdates": true,
+                "automaticallyDownloadsUpdates": false,
+            })
+        );
+    }
+
+    #[test]
+    fn consent_needs_both_answers() {
+        let partial = json!({ "automaticallyChecksForUpdates": true });
+        assert!(serde_json::from_value::<UpdateConsent>(partial).is_err());
+    }
+}
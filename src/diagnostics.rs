@@ -0,0 +1,175 @@
+use serde::Serialize;
+
+use crate::config::{Config, FeedPolicy, StartMode, UserDriverMode};
+
+/// Structured report on how the updater was set up, for support requests.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport {
+    pub plugin_version: String,
+    pub bundle: BundleStatus,
+    /// Sparkle keys in the app's Info.plist.
+    pub info_plist: Vec<PlistKeyStatus>,
+    /// Version of the loaded Sparkle.framework.
+    pub sparkle_version: Option<String>,
+    pub feed_url: Option<FeedUrlSource>,
+    pub updater: UpdaterStatus,
+    /// Only available once the updater has been created.
+    pub policies: Option<PolicyStatus>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleStatus {
+    pub detected: bool,
+    pub identifier: Option<String>,
+    pub path: Option<String>,
+    /// Why the bundle was not accepted, if it wasn't.
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlistKeyStatus {
+    pub key: String,
+    pub present: bool,
+    pub value: Option<String>,
+}
+
+/// Where the effective feed URL comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeedUrlOrigin {
+    /// `set_feed_url_override`
+    Override,
+    /// `set_feed_url`, kept in user defaults by Sparkle.
+    UserDefaults,
+    /// `feedUrl` in `tauri.conf.json`.
+    Config,
+    /// `SUFeedURL` in Info.plist.
+    InfoPlist,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedUrlSource {
+    pub url: String,
+    pub origin: FeedUrlOrigin,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdaterStatus {
+    /// Whether the Sparkle updater was created. `false` outside a valid bundle.
+    pub created: bool,
+    pub started: bool,
+    pub start_mode: StartMode,
+    /// Error from the last failed `start_updater` call.
+    pub startup_error: Option<String>,
+    pub custom_backend: bool,
+    pub user_driver: UserDriverMode,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyStatus {
+    pub may_check_for_updates: bool,
+    pub should_proceed_with_update: bool,
+    pub prompt_for_permission: bool,
+    pub automatically_checks_for_updates: bool,
+    pub automatically_downloads_updates: bool,
+    pub update_check_interval: f64,
+    pub allowed_channels: Option<Vec<String>>,
+    pub proceed_policy: bool,
+    pub may_check_policies: usize,
+    pub feed_url_policy: bool,
+    pub feed_policy: FeedPolicy,
+}
+
+/// Plugin configuration kept for [`DiagnosticsReport`], managed even when the updater
+/// could not be created.
+#[derive(Default)]
+pub(crate) struct StartupInfo {
+    pub config: Config,
+    pub start_mode: StartMode,
+    pub user_driver: UserDriverMode,
+}
+
+/// Resolves the effective feed URL in the order Sparkle and the delegate consult them.
+pub(crate) fn resolve_feed_url(
+    override_url: Option<String>,
+    user_defaults: Option<String>,
+    config: Option<String>,
+    info_plist: Option<String>,
+) -> Option<FeedUrlSource> {
+    [
+        (override_url, FeedUrlOrigin::Override),
+        (user_defaults, FeedUrlOrigin::UserDefaults),
+        (config, FeedUrlOrigin::Config),
+        (info_plist, FeedUrlOrigin::InfoPlist),
+    ]
+    .into_iter()
+    .find_map(|(url, origin)| url.map(|url| FeedUrlSource { url, origin }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(name: &str) -> Option<String> {
+        Some(format!("https://example.com/{}.xml", name))
+    }
+
+    fn source(name: &str, origin: FeedUrlOrigin) -> Option<FeedUrlSource> {
+        Some(FeedUrlSource {
+            url: url(name).unwrap(),
+            origin,
+        })
+    }
+
+    #[test]
+    fn override_wins() {
+        assert_eq!(
+            resolve_feed_url(
+                url("override"),
+                url("defaults"),
+                url("config"),
+                url("plist")
+            ),
+            source("override", FeedUrlOrigin::Override)
+        );
+    }
+
+    #[test]
+    fn user_defaults_win_over_config() {
+        assert_eq!(
+            resolve_feed_url(None, url("defaults"), url("config"), url("plist")),
+            source("defaults", FeedUrlOrigin::UserDefaults)
+        );
+    }
+
+    #[test]
+    fn config_wins_over_info_plist() {
+        assert_eq!(
+            resolve_feed_url(None, None, url("config"), url("plist")),
+            source("config", FeedUrlOrigin::Config)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_info_plist() {
+        assert_eq!(
+            resolve_feed_url(None, None, None, url("plist")),
+            source("plist", FeedUrlOrigin::InfoPlist)
+        );
+        assert_eq!(resolve_feed_url(None, None, None, None), None);
+    }
+
+    #[test]
+    fn override_wins_without_other_sources() {
+        assert_eq!(
+            resolve_feed_url(url("override"), None, None, None),
+            source("override", FeedUrlOrigin::Override)
+        );
+    }
+}
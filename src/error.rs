@@ -1,20 +1,136 @@
 use serde::{ser::Serializer, Serialize};
+use serde_json::{json, Value};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Error domain of errors reported by Sparkle.
+pub const SPARKLE_ERROR_DOMAIN: &str = "SUSparkleErrorDomain";
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+
     #[error("Invalid feed URL: {0}")]
     InvalidFeedUrl(String),
 
+    #[error("Feed URL rejected by feed policy: {0}")]
+    FeedUrlRejected(String),
+
+    #[error("Invalid sparkle-updater configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Invalid appcast: {0}")]
+    InvalidAppcast(String),
+
     #[error("Sparkle initialization failed: {0}")]
     SparkleInit(String),
 
+    /// An error reported by Sparkle in [`SPARKLE_ERROR_DOMAIN`].
+    #[error("{message}")]
+    Sparkle {
+        code: SparkleErrorCode,
+        raw_code: i64,
+        message: String,
+    },
+
     #[error("Updater not ready")]
     UpdaterNotReady,
+
+    #[error("Updater has not been started")]
+    UpdaterNotStarted,
+
+    #[error("Unknown update channel: {0}")]
+    UnknownChannel(String),
+
+    #[error("The headless user driver is not enabled")]
+    UserDriverNotHeadless,
+
+    #[error("No pending user driver request with id {0}")]
+    UnknownUserDriverRequest(u64),
+
+    #[error("Invalid user driver reply: {0}")]
+    InvalidUserDriverReply(String),
+
+    /// No update session is waiting for the requested action, e.g. cancelling when nothing
+    /// is downloading.
+    #[error("No update session to {0}")]
+    NoMatchingSession(String),
+
+    #[error("No install blocker with token {0}")]
+    UnknownInstallBlocker(u64),
+
+    #[error("Failed to fetch {0}")]
+    FetchFailed(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    /// The running build is below the minimum supported version, so the update cannot be
+    /// skipped or postponed.
+    #[error("An update to version {0} or later is required")]
+    UpdateRequired(String),
+}
+
+impl Error {
+    /// Stable identifier of the variant, used as `kind` when serialized.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "io",
+            Error::Json(_) => "json",
+            Error::Tauri(_) => "tauri",
+            Error::InvalidFeedUrl(_) => "invalidFeedUrl",
+            Error::FeedUrlRejected(_) => "feedUrlRejected",
+            Error::InvalidConfig(_) => "invalidConfig",
+            Error::InvalidAppcast(_) => "invalidAppcast",
+            Error::SparkleInit(_) => "sparkleInit",
+            Error::Sparkle { .. } => "sparkle",
+            Error::UpdaterNotReady => "updaterNotReady",
+            Error::UpdaterNotStarted => "updaterNotStarted",
+            Error::UnknownChannel(_) => "unknownChannel",
+            Error::UserDriverNotHeadless => "userDriverNotHeadless",
+            Error::UnknownUserDriverRequest(_) => "unknownUserDriverRequest",
+            Error::InvalidUserDriverReply(_) => "invalidUserDriverReply",
+            Error::NoMatchingSession(_) => "noMatchingSession",
+            Error::UnknownInstallBlocker(_) => "unknownInstallBlocker",
+            Error::FetchFailed(_) => "fetchFailed",
+            Error::InvalidSignature(_) => "invalidSignature",
+            Error::UpdateRequired(_) => "updateRequired",
+        }
+    }
+
+    /// Variant-specific data, serialized as `details`.
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            Error::InvalidFeedUrl(url) => Some(json!({ "url": url })),
+            Error::UnknownChannel(channel) => Some(json!({ "channel": channel })),
+            Error::UnknownUserDriverRequest(id) => Some(json!({ "requestId": id })),
+            Error::UnknownInstallBlocker(token) => Some(json!({ "token": token })),
+            Error::UpdateRequired(version) => Some(json!({ "minimumVersion": version })),
+            Error::Sparkle { code, raw_code, .. } => Some(json!({
+                "code": code,
+                "rawCode": raw_code,
+                "category": code.category(),
+                "retryable": code.is_retryable(),
+            })),
+            _ => None,
+        }
+    }
+
+    /// Whether retrying the same operation later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Io(_) | Error::FetchFailed(_) => true,
+            Error::Sparkle { code, .. } => code.is_retryable(),
+            _ => false,
+        }
+    }
 }
 
 impl Serialize for Error {
@@ -22,6 +138,307 @@ impl Serialize for Error {
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Error", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+/// Phase of the update process a [`SparkleErrorCode`] belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCategory {
+    Configuration,
+    Appcast,
+    Download,
+    Extraction,
+    Installation,
+    ApiMisuse,
+    Unknown,
+}
+
+/// Sparkle's `SUError` codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SparkleErrorCode {
+    NoPublicKey,
+    InsufficientSigning,
+    InsecureFeedUrl,
+    InvalidFeedUrl,
+    InvalidUpdater,
+    InvalidHostBundleIdentifier,
+    InvalidHostVersion,
+    AppcastParse,
+    NoUpdate,
+    Appcast,
+    RunningFromDiskImage,
+    ResumeAppcast,
+    RunningTranslocated,
+    WebKitTermination,
+    ReleaseNotes,
+    TemporaryDirectory,
+    Download,
+    Unarchiving,
+    SignatureVerification,
+    Validation,
+    FileCopy,
+    InstallationAuthorization,
+    MissingUpdate,
+    MissingInstallerTool,
+    Relaunch,
+    Installation,
+    Downgrade,
+    InstallationCanceled,
+    InstallationAuthorizeLater,
+    NotValidUpdate,
+    AgentInvalidation,
+    InstallationRootInteractive,
+    InstallationWriteNoPermission,
+    IncorrectApiUsage,
+    /// A code this version of the plugin doesn't know, or an error from another domain.
+    Unknown,
+}
+
+impl SparkleErrorCode {
+    pub fn from_code(code: i64) -> Self {
+        use SparkleErrorCode::*;
+
+        match code {
+            1 => NoPublicKey,
+            2 => InsufficientSigning,
+            3 => InsecureFeedUrl,
+            4 => InvalidFeedUrl,
+            5 => InvalidUpdater,
+            6 => InvalidHostBundleIdentifier,
+            7 => InvalidHostVersion,
+            1000 => AppcastParse,
+            1001 => NoUpdate,
+            1002 => Appcast,
+            1003 => RunningFromDiskImage,
+            1004 => ResumeAppcast,
+            1005 => RunningTranslocated,
+            1006 => WebKitTermination,
+            1007 => ReleaseNotes,
+            2000 => TemporaryDirectory,
+            2001 => Download,
+            3000 => Unarchiving,
+            3001 => SignatureVerification,
+            3002 => Validation,
+            4000 => FileCopy,
+            4001 => InstallationAuthorization,
+            4002 => MissingUpdate,
+            4003 => MissingInstallerTool,
+            4004 => Relaunch,
+            4005 => Installation,
+            4006 => Downgrade,
+            4007 => InstallationCanceled,
+            4008 => InstallationAuthorizeLater,
+            4009 => NotValidUpdate,
+            4010 => AgentInvalidation,
+            4011 => InstallationRootInteractive,
+            4012 => InstallationWriteNoPermission,
+            5000 => IncorrectApiUsage,
+            _ => Unknown,
+        }
+    }
+
+    /// Maps an `NSError` domain and code. Errors outside [`SPARKLE_ERROR_DOMAIN`] are
+    /// [`SparkleErrorCode::Unknown`].
+    pub fn from_domain(domain: &str, code: i64) -> Self {
+        if domain == SPARKLE_ERROR_DOMAIN {
+            Self::from_code(code)
+        } else {
+            SparkleErrorCode::Unknown
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        use SparkleErrorCode::*;
+
+        match self {
+            NoPublicKey
+            | InsufficientSigning
+            | InsecureFeedUrl
+            | InvalidFeedUrl
+            | InvalidUpdater
+            | InvalidHostBundleIdentifier
+            | InvalidHostVersion => ErrorCategory::Configuration,
+            AppcastParse | NoUpdate | Appcast | RunningFromDiskImage | ResumeAppcast
+            | RunningTranslocated | WebKitTermination | ReleaseNotes => ErrorCategory::Appcast,
+            TemporaryDirectory | Download => ErrorCategory::Download,
+            Unarchiving | SignatureVerification | Validation => ErrorCategory::Extraction,
+            FileCopy
+            | InstallationAuthorization
+            | MissingUpdate
+            | MissingInstallerTool
+            | Relaunch
+            | Installation
+            | Downgrade
+            | InstallationCanceled
+            | InstallationAuthorizeLater
+            | NotValidUpdate
+            | AgentInvalidation
+            | InstallationRootInteractive
+            | InstallationWriteNoPermission => ErrorCategory::Installation,
+            IncorrectApiUsage => ErrorCategory::ApiMisuse,
+            Unknown => ErrorCategory::Unknown,
+        }
+    }
+
+    /// Whether the failure is likely transient, e.g. a network error or a postponed
+    /// authorization, so retrying the check or download later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        use SparkleErrorCode::*;
+
+        matches!(
+            self,
+            Appcast
+                | ResumeAppcast
+                | ReleaseNotes
+                | TemporaryDirectory
+                | Download
+                | InstallationCanceled
+                | InstallationAuthorizeLater
+                | AgentInvalidation
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_sparkle_codes() {
+        assert_eq!(
+            SparkleErrorCode::from_code(1),
+            SparkleErrorCode::NoPublicKey
+        );
+        assert_eq!(
+            SparkleErrorCode::from_code(1001),
+            SparkleErrorCode::NoUpdate
+        );
+        assert_eq!(
+            SparkleErrorCode::from_code(2001),
+            SparkleErrorCode::Download
+        );
+        assert_eq!(
+            SparkleErrorCode::from_code(3001),
+            SparkleErrorCode::SignatureVerification
+        );
+        assert_eq!(
+            SparkleErrorCode::from_code(4012),
+            SparkleErrorCode::InstallationWriteNoPermission
+        );
+        assert_eq!(
+            SparkleErrorCode::from_code(5000),
+            SparkleErrorCode::IncorrectApiUsage
+        );
+        for unknown in [0, -1, 8, 1008, 4013, 9999] {
+            assert_eq!(
+                SparkleErrorCode::from_code(unknown),
+                SparkleErrorCode::Unknown
+            );
+        }
+    }
+
+    #[test]
+    fn maps_only_sparkle_domain() {
+        assert_eq!(
+            SparkleErrorCode::from_domain(SPARKLE_ERROR_DOMAIN, 2001),
+            SparkleErrorCode::Download
+        );
+        assert_eq!(
+            SparkleErrorCode::from_domain("NSURLErrorDomain", 2001),
+            SparkleErrorCode::Unknown
+        );
+    }
+
+    #[test]
+    fn categorizes_codes() {
+        for (code, category) in [
+            (4, ErrorCategory::Configuration),
+            (1000, ErrorCategory::Appcast),
+            (2000, ErrorCategory::Download),
+            (3002, ErrorCategory::Extraction),
+            (4006, ErrorCategory::Installation),
+            (5000, ErrorCategory::ApiMisuse),
+            (42, ErrorCategory::Unknown),
+        ] {
+            assert_eq!(SparkleErrorCode::from_code(code).category(), category);
+        }
+    }
+
+    #[test]
+    fn transient_codes_are_retryable() {
+        for code in [1002, 2001, 4007, 4008] {
+            assert!(SparkleErrorCode::from_code(code).is_retryable(), "{}", code);
+        }
+        for code in [1, 1000, 1001, 3001, 4006, 5000, 42] {
+            assert!(
+                !SparkleErrorCode::from_code(code).is_retryable(),
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn errors_know_whether_they_are_retryable() {
+        let sparkle = |raw_code| Error::Sparkle {
+            code: SparkleErrorCode::from_code(raw_code),
+            raw_code,
+            message: String::new(),
+        };
+        assert!(sparkle(2001).is_retryable());
+        assert!(!sparkle(3001).is_retryable());
+        assert!(Error::FetchFailed("https://example.com".to_string()).is_retryable());
+        assert!(!Error::UpdaterNotStarted.is_retryable());
+    }
+
+    #[test]
+    fn serializes_kind_message_and_details() {
+        assert_eq!(
+            serde_json::to_value(Error::UnknownChannel("nightly".to_string())).unwrap(),
+            json!({
+                "kind": "unknownChannel",
+                "message": "Unknown update channel: nightly",
+                "details": { "channel": "nightly" },
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(Error::UpdaterNotStarted).unwrap(),
+            json!({
+                "kind": "updaterNotStarted",
+                "message": "Updater has not been started",
+                "details": null,
+            })
+        );
+    }
+
+    #[test]
+    fn serializes_sparkle_details() {
+        let error = Error::Sparkle {
+            code: SparkleErrorCode::Download,
+            raw_code: 2001,
+            message: "The download failed.".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(error).unwrap(),
+            json!({
+                "kind": "sparkle",
+                "message": "The download failed.",
+                "details": {
+                    "code": "download",
+                    "rawCode": 2001,
+                    "category": "download",
+                    "retryable": true,
+                },
+            })
+        );
     }
 }
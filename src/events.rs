@@ -1,4 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{ErrorCategory, SparkleErrorCode};
 
 pub const EVENT_DID_FINISH_LOADING_APPCAST: &str = "sparkle://did-finish-loading-appcast";
 pub const EVENT_DID_FIND_VALID_UPDATE: &str = "sparkle://did-find-valid-update";
@@ -17,11 +20,51 @@ pub const EVENT_USER_DID_MAKE_CHOICE: &str = "sparkle://user-did-make-choice";
 pub const EVENT_WILL_SCHEDULE_UPDATE_CHECK: &str = "sparkle://will-schedule-update-check";
 pub const EVENT_WILL_NOT_SCHEDULE_UPDATE_CHECK: &str = "sparkle://will-not-schedule-update-check";
 pub const EVENT_WILL_INSTALL_UPDATE_ON_QUIT: &str = "sparkle://will-install-update-on-quit";
+pub const EVENT_DID_REJECT_UPDATE: &str = "sparkle://did-reject-update";
+pub const EVENT_DID_DENY_UPDATE_CHECK: &str = "sparkle://did-deny-update-check";
+pub const EVENT_CHANNEL_CHANGED: &str = "sparkle://channel-changed";
+pub const EVENT_INSTALL_DEFERRED: &str = "sparkle://install-deferred";
+pub const EVENT_BEFORE_INSTALL_QUIT: &str = "sparkle://before-install-quit";
+pub const EVENT_DID_UPDATE: &str = "sparkle://did-update";
+pub const EVENT_MIGRATION_FAILED: &str = "sparkle://migration-failed";
+pub const EVENT_ROLLBACK_RECOMMENDED: &str = "sparkle://rollback-recommended";
+pub const EVENT_PRE_INSTALL_ABORTED: &str = "sparkle://pre-install-aborted";
+pub const EVENT_UPDATE_REQUIRED: &str = "sparkle://update-required";
+pub const EVENT_CURRENT_VERSION_REVOKED: &str = "sparkle://current-version-revoked";
 
-#[derive(Clone, Debug, Serialize)]
+// Emitted by the headless user driver.
+pub const EVENT_SHOW_UPDATE_PERMISSION_REQUEST: &str = "sparkle://show-update-permission-request";
+pub const EVENT_SHOW_USER_INITIATED_UPDATE_CHECK: &str =
+    "sparkle://show-user-initiated-update-check";
+pub const EVENT_SHOW_UPDATE_FOUND: &str = "sparkle://show-update-found";
+pub const EVENT_SHOW_UPDATE_RELEASE_NOTES: &str = "sparkle://show-update-release-notes";
+pub const EVENT_SHOW_UPDATE_RELEASE_NOTES_FAILED: &str =
+    "sparkle://show-update-release-notes-failed";
+pub const EVENT_SHOW_UPDATE_NOT_FOUND: &str = "sparkle://show-update-not-found";
+pub const EVENT_SHOW_UPDATER_ERROR: &str = "sparkle://show-updater-error";
+pub const EVENT_SHOW_DOWNLOAD_INITIATED: &str = "sparkle://show-download-initiated";
+pub const EVENT_SHOW_EXTRACTING_UPDATE: &str = "sparkle://show-extracting-update";
+pub const EVENT_SHOW_EXTRACTION_PROGRESS: &str = "sparkle://show-extraction-progress";
+pub const EVENT_SHOW_READY_TO_INSTALL: &str = "sparkle://show-ready-to-install";
+pub const EVENT_SHOW_INSTALLING_UPDATE: &str = "sparkle://show-installing-update";
+pub const EVENT_SHOW_UPDATE_INSTALLED: &str = "sparkle://show-update-installed";
+pub const EVENT_SHOW_UPDATE_IN_FOCUS: &str = "sparkle://show-update-in-focus";
+pub const EVENT_DISMISS_UPDATE_INSTALLATION: &str = "sparkle://dismiss-update-installation";
+pub const EVENT_DOWNLOAD_PROGRESS: &str = "sparkle://download-progress";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateInfo {
     pub version: String,
+    /// Build version (`sparkle:version`), compared against `CFBundleVersion`.
+    pub build_version: String,
+    /// Enclosure URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_url: Option<String>,
+    /// Expected enclosure size in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+    pub is_delta: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub release_notes: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -56,11 +99,22 @@ pub struct UpdateInfo {
     pub date_string: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub item_description_format: Option<String>,
+    /// Custom appcast elements keyed by qualified name, e.g. `myapp:migrationRequired`.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
 
+/// Identifies the update a download, extraction or installation event refers to.
 #[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VersionInfo {
     pub version: String,
+    pub build_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+    pub is_delta: bool,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -68,6 +122,24 @@ pub struct ErrorPayload {
     pub message: String,
     pub code: i64,
     pub domain: String,
+    /// `code` mapped to Sparkle's `SUError` codes.
+    pub kind: SparkleErrorCode,
+    pub category: ErrorCategory,
+    pub retryable: bool,
+}
+
+impl ErrorPayload {
+    pub fn new(message: String, code: i64, domain: String) -> Self {
+        let kind = SparkleErrorCode::from_domain(&domain, code);
+        ErrorPayload {
+            message,
+            code,
+            domain,
+            kind,
+            category: kind.category(),
+            retryable: kind.is_retryable(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -84,7 +156,8 @@ pub struct UpdateCycleInfo {
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DownloadFailedInfo {
-    pub version: String,
+    #[serde(flatten)]
+    pub update: VersionInfo,
     pub error: ErrorPayload,
 }
 
@@ -101,3 +174,90 @@ pub struct UserChoiceInfo {
 pub struct ScheduleInfo {
     pub delay: f64,
 }
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRejectedInfo {
+    pub version: String,
+    pub update_check: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheckDeniedInfo {
+    pub update_check: String,
+    pub reason: String,
+}
+
+/// A headless user driver question, answered with `reply_user_driver(request_id, ...)`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDriverRequestInfo {
+    pub request_id: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFoundInfo {
+    pub request_id: u64,
+    #[serde(flatten)]
+    pub update: UpdateInfo,
+    pub stage: String,
+    pub user_initiated: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseNotesInfo {
+    pub notes: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Outcome the frontend has to acknowledge.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcknowledgementInfo {
+    pub request_id: u64,
+    pub error: ErrorPayload,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionProgressInfo {
+    /// Between 0 and 1.
+    pub progress: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeforeInstallQuitInfo {
+    /// Seconds the webview has to save before the save window ends anyway.
+    pub timeout: f64,
+    /// Install blockers that hold the quit back until they are released, past the timeout.
+    pub blocked_by: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgressInfo {
+    /// Size of the download in bytes, from the server or else the appcast enclosure.
+    /// `None` when neither knows it.
+    pub expected_content_length: Option<u64>,
+    pub bytes_received: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallingInfo {
+    pub request_id: u64,
+    pub application_terminated: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInstalledInfo {
+    pub request_id: u64,
+    pub relaunched: bool,
+}
@@ -0,0 +1,11 @@
+//! Downloads of small documents the plugin reads besides the appcast.
+
+use crate::Result;
+
+/// Fetches the body of a URL. Calls block and are made off the main thread.
+///
+/// The default implementation uses Foundation's URL loading on macOS. Set a custom one with
+/// [`Builder::fetcher`](crate::Builder::fetcher), e.g. to add authentication.
+pub trait Fetcher: Send + Sync {
+    fn fetch(&self, url: &str) -> Result<Vec<u8>>;
+}
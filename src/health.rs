@@ -0,0 +1,333 @@
+//! Verification of freshly installed updates.
+//!
+//! The first launch of an update starts a verification that the app ends by calling
+//! `mark_update_healthy`. Launches that never get there, because the app crashed or hung
+//! during startup, are counted. Too many of them, or no call within the timeout,
+//! recommend rolling back.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::whats_new::{DidUpdateInfo, LaunchedVersion};
+
+/// Launches without `mark_update_healthy` after which rolling back is recommended.
+pub const DEFAULT_MAX_UNHEALTHY_LAUNCHES: u32 = 3;
+
+/// Health check settings from `healthCheck` in the plugin configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct HealthCheckConfig {
+    /// Launches of an update that may end without `mark_update_healthy`.
+    pub max_launches: Option<u32>,
+    /// Seconds a launch of an update has to call `mark_update_healthy`.
+    pub timeout: Option<f64>,
+    /// Channel the feed is switched to when rolling back is recommended. The channel must
+    /// serve a build version higher than the broken one, as Sparkle never downgrades.
+    pub rollback_channel: Option<String>,
+}
+
+impl HealthCheckConfig {
+    pub fn max_launches(&self) -> u32 {
+        self.max_launches.unwrap_or(DEFAULT_MAX_UNHEALTHY_LAUNCHES)
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs_f64)
+    }
+}
+
+/// An installed update that has not been marked healthy yet.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingVerification {
+    pub from: LaunchedVersion,
+    pub to: LaunchedVersion,
+    /// Launches of `to` so far, including the running one.
+    pub launches: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RollbackReason {
+    /// Too many launches ended without `mark_update_healthy`.
+    FailedLaunches,
+    /// The running launch did not call `mark_update_healthy` in time.
+    Timeout,
+}
+
+/// Payload of `sparkle://rollback-recommended`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackInfo {
+    /// The version that worked before the update.
+    pub previous: LaunchedVersion,
+    pub current: LaunchedVersion,
+    pub reason: RollbackReason,
+    /// Launches that ended without `mark_update_healthy`.
+    pub failed_launches: u32,
+    /// Channel the feed was switched to, if configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollback_channel: Option<String>,
+}
+
+/// Result of `update_health`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateHealth {
+    /// The verification waiting for `mark_update_healthy`, if any.
+    pub pending: Option<PendingVerification>,
+    /// Set once rolling back was recommended during this launch.
+    pub rollback_recommended: Option<RollbackInfo>,
+}
+
+/// Verification state of the running launch.
+#[derive(Debug, Default)]
+pub struct HealthMonitor {
+    max_launches: u32,
+    rollback_channel: Option<String>,
+    pending: Option<PendingVerification>,
+    recommendation: Option<RollbackInfo>,
+}
+
+impl HealthMonitor {
+    pub fn new(config: &HealthCheckConfig) -> Self {
+        Self {
+            max_launches: config.max_launches(),
+            rollback_channel: config.rollback_channel.clone(),
+            pending: None,
+            recommendation: None,
+        }
+    }
+
+    /// Records a launch. `stored` is the persisted verification, `did_update` the update
+    /// this launch follows. Returns the recommendation when too many launches failed.
+    pub fn launch(
+        &mut self,
+        stored: Option<PendingVerification>,
+        did_update: Option<&DidUpdateInfo>,
+        current: &LaunchedVersion,
+    ) -> Option<RollbackInfo> {
+        self.pending = match did_update {
+            Some(update) => Some(PendingVerification {
+                from: update.from.clone(),
+                to: update.to.clone(),
+                launches: 1,
+            }),
+            // A verification of another build is stale, e.g. after a manual reinstall.
+            None => stored
+                .filter(|pending| pending.to == *current)
+                .map(|pending| PendingVerification {
+                    launches: pending.launches.saturating_add(1),
+                    ..pending
+                }),
+        };
+
+        let pending = self.pending.as_ref()?;
+        let failed_launches = pending.launches - 1;
+        if failed_launches < self.max_launches {
+            return None;
+        }
+        self.recommend(RollbackReason::FailedLaunches)
+    }
+
+    /// Called when the timeout of the running launch expires. Returns the recommendation
+    /// unless the update was marked healthy or rolling back is already recommended.
+    pub fn timed_out(&mut self) -> Option<RollbackInfo> {
+        if self.recommendation.is_some() {
+            return None;
+        }
+        self.recommend(RollbackReason::Timeout)
+    }
+
+    /// Ends the verification. Returns `false` when none was pending.
+    pub fn mark_healthy(&mut self) -> bool {
+        self.recommendation = None;
+        self.pending.take().is_some()
+    }
+
+    /// The verification to persist, `None` when nothing is pending.
+    pub fn pending(&self) -> Option<&PendingVerification> {
+        self.pending.as_ref()
+    }
+
+    pub fn status(&self) -> UpdateHealth {
+        UpdateHealth {
+            pending: self.pending.clone(),
+            rollback_recommended: self.recommendation.clone(),
+        }
+    }
+
+    fn recommend(&mut self, reason: RollbackReason) -> Option<RollbackInfo> {
+        let pending = self.pending.as_ref()?;
+        let info = RollbackInfo {
+            previous: pending.from.clone(),
+            current: pending.to.clone(),
+            reason,
+            failed_launches: match reason {
+                RollbackReason::FailedLaunches => pending.launches - 1,
+                RollbackReason::Timeout => pending.launches,
+            },
+            rollback_channel: self.rollback_channel.clone(),
+        };
+        self.recommendation = Some(info.clone());
+        Some(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(build: &str) -> LaunchedVersion {
+        LaunchedVersion {
+            version: format!("1.{}", build),
+            build_version: build.to_string(),
+        }
+    }
+
+    fn update(from: &str, to: &str) -> DidUpdateInfo {
+        DidUpdateInfo {
+            from: version(from),
+            to: version(to),
+            update: None,
+        }
+    }
+
+    fn monitor(max_launches: u32) -> HealthMonitor {
+        HealthMonitor::new(&HealthCheckConfig {
+            max_launches: Some(max_launches),
+            timeout: None,
+            rollback_channel: Some("stable-rollback".to_string()),
+        })
+    }
+
+    /// Launches `current` `count` times without marking it healthy, as a crash loop would,
+    /// and returns the recommendation of the last launch.
+    fn crash_loop(
+        monitor: &mut HealthMonitor,
+        did_update: &DidUpdateInfo,
+        count: u32,
+    ) -> Option<RollbackInfo> {
+        let mut recommendation = monitor.launch(None, Some(did_update), &did_update.to);
+        for _ in 1..count {
+            let stored = monitor.pending().cloned();
+            recommendation = monitor.launch(stored, None, &did_update.to);
+        }
+        recommendation
+    }
+
+    #[test]
+    fn first_launch_after_update_starts_verification() {
+        let mut monitor = monitor(3);
+        assert_eq!(
+            monitor.launch(None, Some(&update("1", "2")), &version("2")),
+            None
+        );
+        assert_eq!(
+            monitor.pending(),
+            Some(&PendingVerification {
+                from: version("1"),
+                to: version("2"),
+                launches: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn recommends_rollback_once_failed_launches_reach_the_threshold() {
+        let did_update = update("1", "2");
+
+        let mut below = monitor(3);
+        assert_eq!(crash_loop(&mut below, &did_update, 3), None);
+        assert_eq!(below.pending().unwrap().launches, 3);
+        assert_eq!(below.status().rollback_recommended, None);
+
+        let mut at = monitor(3);
+        let info = crash_loop(&mut at, &did_update, 4).unwrap();
+        assert_eq!(
+            info,
+            RollbackInfo {
+                previous: version("1"),
+                current: version("2"),
+                reason: RollbackReason::FailedLaunches,
+                failed_launches: 3,
+                rollback_channel: Some("stable-rollback".to_string()),
+            }
+        );
+        assert_eq!(at.status().rollback_recommended, Some(info));
+    }
+
+    #[test]
+    fn default_threshold_applies_without_max_launches() {
+        let mut monitor = HealthMonitor::new(&HealthCheckConfig::default());
+        let did_update = update("1", "2");
+        assert_eq!(
+            crash_loop(&mut monitor, &did_update, DEFAULT_MAX_UNHEALTHY_LAUNCHES),
+            None
+        );
+        assert!(crash_loop(
+            &mut monitor,
+            &did_update,
+            DEFAULT_MAX_UNHEALTHY_LAUNCHES + 1
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn healthy_launch_ends_the_crash_loop() {
+        let mut monitor = monitor(1);
+        let did_update = update("1", "2");
+        assert!(crash_loop(&mut monitor, &did_update, 2).is_some());
+
+        assert!(monitor.mark_healthy());
+        assert_eq!(monitor.status(), UpdateHealth::default());
+        assert!(!monitor.mark_healthy());
+        assert_eq!(monitor.launch(None, None, &version("2")), None);
+    }
+
+    #[test]
+    fn stale_verification_of_another_build_is_dropped() {
+        let mut monitor = monitor(1);
+        let stored = PendingVerification {
+            from: version("1"),
+            to: version("2"),
+            launches: 5,
+        };
+        assert_eq!(monitor.launch(Some(stored), None, &version("3")), None);
+        assert_eq!(monitor.pending(), None);
+    }
+
+    #[test]
+    fn new_update_restarts_the_count() {
+        let mut monitor = monitor(2);
+        let stored = PendingVerification {
+            from: version("1"),
+            to: version("2"),
+            launches: 2,
+        };
+        assert_eq!(
+            monitor.launch(Some(stored), Some(&update("2", "3")), &version("3")),
+            None
+        );
+        assert_eq!(monitor.pending().unwrap().launches, 1);
+    }
+
+    #[test]
+    fn timeout_recommends_rollback_once() {
+        let mut monitor = monitor(3);
+        monitor.launch(None, Some(&update("1", "2")), &version("2"));
+
+        let info = monitor.timed_out().unwrap();
+        assert_eq!(info.reason, RollbackReason::Timeout);
+        assert_eq!(info.failed_launches, 1);
+        assert_eq!(monitor.timed_out(), None);
+    }
+
+    #[test]
+    fn timeout_without_verification_does_nothing() {
+        let mut monitor = monitor(3);
+        monitor.launch(None, None, &version("2"));
+        assert_eq!(monitor.timed_out(), None);
+    }
+}
@@ -0,0 +1,312 @@
+//! Registry of tasks that must finish before an update may be installed.
+//!
+//! Any subsystem can hold a blocker while it has unsaved work, either as an
+//! [`InstallBlocker`] guard in Rust or as a token from the webview. Installing while
+//! blocked is deferred until the last blocker is released.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::{Error, Result};
+
+type Deferred = Box<dyn FnOnce() + Send>;
+
+/// A task holding off installation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockerInfo {
+    pub token: u64,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallBlockStatus {
+    /// Number of blockers held.
+    pub blocked_by: usize,
+    pub blockers: Vec<BlockerInfo>,
+    /// Whether an install is waiting for the last blocker to be released.
+    pub install_deferred: bool,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_token: u64,
+    blockers: BTreeMap<u64, String>,
+    deferred: Option<Deferred>,
+    /// Called once nothing blocks installation anymore, after the deferred install.
+    waiters: Vec<Deferred>,
+}
+
+/// Shared registry of install blockers.
+#[derive(Clone, Default)]
+pub struct InstallBlockers {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl InstallBlockers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Blocks installation until the returned guard is dropped.
+    pub fn block(&self, reason: impl Into<String>) -> InstallBlocker {
+        InstallBlocker {
+            blockers: self.clone(),
+            token: self.acquire(reason),
+        }
+    }
+
+    /// Blocks installation until [`release`](Self::release) is called with the returned
+    /// token.
+    pub fn acquire(&self, reason: impl Into<String>) -> u64 {
+        let mut registry = self.registry.lock().unwrap();
+        registry.next_token += 1;
+        let token = registry.next_token;
+        registry.blockers.insert(token, reason.into());
+        token
+    }
+
+    /// Releases a blocker. Runs the deferred install if it was the last one.
+    pub fn release(&self, token: u64) -> Result<()> {
+        let (deferred, waiters) = {
+            let mut registry = self.registry.lock().unwrap();
+            if registry.blockers.remove(&token).is_none() {
+                return Err(Error::UnknownInstallBlocker(token));
+            }
+            if registry.blockers.is_empty() {
+                (
+                    registry.deferred.take(),
+                    std::mem::take(&mut registry.waiters),
+                )
+            } else {
+                (None, Vec::new())
+            }
+        };
+        // These may call back into the registry, so they run without the lock.
+        deferred.into_iter().chain(waiters).for_each(|f| f());
+        Ok(())
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        !self.registry.lock().unwrap().blockers.is_empty()
+    }
+
+    pub fn status(&self) -> InstallBlockStatus {
+        let registry = self.registry.lock().unwrap();
+        InstallBlockStatus {
+            blocked_by: registry.blockers.len(),
+            blockers: registry
+                .blockers
+                .iter()
+                .map(|(token, reason)| BlockerInfo {
+                    token: *token,
+                    reason: reason.clone(),
+                })
+                .collect(),
+            install_deferred: registry.deferred.is_some(),
+        }
+    }
+
+    /// Runs `install` right away when nothing blocks it, otherwise once the last blocker is
+    /// released. A later deferred install replaces an earlier one. Returns whether it ran.
+    pub fn run_or_defer(&self, install: impl FnOnce() + Send + 'static) -> bool {
+        {
+            let mut registry = self.registry.lock().unwrap();
+            if !registry.blockers.is_empty() {
+                registry.deferred = Some(Box::new(install));
+                return false;
+            }
+        }
+        install();
+        true
+    }
+
+    /// Runs `f` right away when nothing blocks installation, otherwise once the last
+    /// blocker is released. Returns whether it ran.
+    pub fn when_unblocked(&self, f: impl FnOnce() + Send + 'static) -> bool {
+        {
+            let mut registry = self.registry.lock().unwrap();
+            if !registry.blockers.is_empty() {
+                registry.waiters.push(Box::new(f));
+                return false;
+            }
+        }
+        f();
+        true
+    }
+}
+
+/// Holds off installation until dropped.
+#[must_use = "installation is only blocked while the guard is held"]
+pub struct InstallBlocker {
+    blockers: InstallBlockers,
+    token: u64,
+}
+
+impl InstallBlocker {
+    pub fn token(&self) -> u64 {
+        self.token
+    }
+}
+
+impl Drop for InstallBlocker {
+    fn drop(&mut self) {
+        // The token can only be unknown if it was released by token, which is fine.
+        let _ = self.blockers.release(self.token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn counter() -> (Arc<AtomicUsize>, impl Fn() + Send + Clone + 'static) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let increment = {
+            let count = count.clone();
+            move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        (count, increment)
+    }
+
+    #[test]
+    fn blockers_are_counted_until_the_last_release() {
+        let blockers = InstallBlockers::new();
+        let guard = blockers.block("Saving");
+        let token = blockers.acquire("Syncing");
+        assert_ne!(guard.token(), token);
+        assert!(blockers.is_blocked());
+        assert_eq!(blockers.status().blocked_by, 2);
+
+        blockers.release(token).unwrap();
+        assert!(blockers.is_blocked());
+        assert_eq!(
+            blockers.status().blockers,
+            vec![BlockerInfo {
+                token: guard.token(),
+                reason: "Saving".to_string(),
+            }]
+        );
+
+        drop(guard);
+        assert!(!blockers.is_blocked());
+        assert_eq!(blockers.status().blocked_by, 0);
+    }
+
+    #[test]
+    fn releasing_an_unknown_token_fails() {
+        let blockers = InstallBlockers::new();
+        let token = blockers.acquire("Saving");
+        blockers.release(token).unwrap();
+        assert!(matches!(
+            blockers.release(token),
+            Err(Error::UnknownInstallBlocker(t)) if t == token
+        ));
+    }
+
+    #[test]
+    fn dropping_a_guard_released_by_token_is_harmless() {
+        let blockers = InstallBlockers::new();
+        let guard = blockers.block("Saving");
+        blockers.release(guard.token()).unwrap();
+        drop(guard);
+        assert!(!blockers.is_blocked());
+    }
+
+    #[test]
+    fn run_or_defer_runs_right_away_when_unblocked() {
+        let blockers = InstallBlockers::new();
+        let (count, install) = counter();
+        assert!(blockers.run_or_defer(install));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(!blockers.status().install_deferred);
+    }
+
+    #[test]
+    fn releasing_the_last_blocker_runs_the_deferred_install() {
+        let blockers = InstallBlockers::new();
+        let first = blockers.block("Saving");
+        let second = blockers.block("Syncing");
+        let (count, install) = counter();
+
+        assert!(!blockers.run_or_defer(install));
+        assert!(blockers.status().install_deferred);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        drop(first);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        drop(second);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(!blockers.status().install_deferred);
+
+        // Runs once only.
+        drop(blockers.block("Saving"));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn a_later_deferred_install_replaces_an_earlier_one() {
+        let blockers = InstallBlockers::new();
+        let guard = blockers.block("Saving");
+        let (earlier, install_earlier) = counter();
+        let (later, install_later) = counter();
+
+        blockers.run_or_defer(install_earlier);
+        blockers.run_or_defer(install_later);
+        drop(guard);
+        assert_eq!(earlier.load(Ordering::SeqCst), 0);
+        assert_eq!(later.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn when_unblocked_waits_for_the_last_release() {
+        let blockers = InstallBlockers::new();
+        let (count, waiter) = counter();
+        assert!(blockers.when_unblocked(waiter.clone()));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        let guard = blockers.block("Saving");
+        assert!(!blockers.when_unblocked(waiter.clone()));
+        assert!(!blockers.when_unblocked(waiter));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        drop(guard);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn deferred_install_runs_before_waiters() {
+        let blockers = InstallBlockers::new();
+        let guard = blockers.block("Saving");
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let record = |name: &'static str| {
+            let order = order.clone();
+            move || order.lock().unwrap().push(name)
+        };
+
+        blockers.when_unblocked(record("waiter"));
+        blockers.run_or_defer(record("install"));
+        drop(guard);
+        assert_eq!(*order.lock().unwrap(), vec!["install", "waiter"]);
+    }
+
+    #[test]
+    fn callbacks_may_block_again() {
+        let blockers = InstallBlockers::new();
+        let guard = blockers.block("Saving");
+        let reentrant = blockers.clone();
+        blockers.when_unblocked(move || {
+            let token = reentrant.acquire("Follow-up");
+            reentrant.release(token).unwrap();
+        });
+        drop(guard);
+        assert!(!blockers.is_blocked());
+    }
+}
@@ -1,16 +1,86 @@
+use std::sync::Arc;
+
+use serde_json::Value;
 use tauri::{
-    plugin::{Builder, TauriPlugin},
-    Manager, Runtime,
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    Manager, RunEvent, Runtime,
 };
 
+pub mod appcast;
+mod backend;
+mod channels;
 mod commands;
+mod config;
+mod consent;
+mod diagnostics;
 mod error;
 mod events;
+mod fetch;
+mod health;
+mod install_blockers;
+mod migrations;
+mod minimum_version;
+mod policy;
+mod pre_install;
+mod progress;
+mod quit;
+mod release_notes;
+mod revocation;
+mod signature;
 mod sparkle;
+mod store;
+#[cfg(test)]
+mod test_support;
+mod user_driver;
+mod version;
+mod whats_new;
 
-pub use error::{Error, Result};
+pub use backend::UpdaterBackend;
+pub use channels::{Channel, ChannelChange};
+pub use config::{Config, FeedPolicy, StartMode, UserDriverMode};
+pub use consent::UpdateConsent;
+pub use diagnostics::{
+    BundleStatus, DiagnosticsReport, FeedUrlOrigin, FeedUrlSource, PlistKeyStatus, PolicyStatus,
+    UpdaterStatus,
+};
+pub use error::{Error, ErrorCategory, Result, SparkleErrorCode, SPARKLE_ERROR_DOMAIN};
+pub use events::UpdateInfo;
+pub use fetch::Fetcher;
+pub use health::{
+    HealthCheckConfig, PendingVerification, RollbackInfo, RollbackReason, UpdateHealth,
+};
+pub use install_blockers::{BlockerInfo, InstallBlockStatus, InstallBlocker};
+pub use migrations::{
+    JsonFileMigrationStore, MemoryMigrationStore, MigrationContext, MigrationError,
+    MigrationFailure, MigrationReport, MigrationState, MigrationStore, Migrations,
+};
+pub use minimum_version::{
+    MinimumVersionConfig, MinimumVersionDocument, MinimumVersionSource, UpdateRequiredInfo,
+};
+pub use policy::{
+    CheckDecision, FeedUrlPolicy, FlagPolicy, MayCheckPolicies, MayCheckPolicy, ProceedDecision,
+    ProceedPolicy, QuietHoursPolicy, UpdateCheck,
+};
+pub use pre_install::{
+    BackupDirectoryHook, HookError, HookResult, PreInstallAbort, PreInstallContext,
+    PreInstallHooks, PreInstallReport,
+};
+pub use release_notes::{
+    CumulativeNotesOptions, CumulativeReleaseNotes, ReleaseNotes, ReleaseNotesFormat,
+    ReleaseNotesService,
+};
+pub use revocation::{
+    CurrentVersionRevoked, RecommendedRelease, RevocationSource, RevokedRelease,
+    RevokedReleasesConfig, RevokedReleasesDocument,
+};
+pub use signature::SignedDocumentConfig;
+pub use store::{JsonFileStore, MemoryStore, PersistedSettings, SettingsStore};
+pub use user_driver::{PendingRequest, PromptKind, UserDriverReply};
+pub use version::{StandardVersionComparator, VersionComparator};
+pub use whats_new::{DidUpdateInfo, LaunchedVersion};
 
-use sparkle::SparkleUpdater;
+use diagnostics::StartupInfo;
+use sparkle::{Options, SparkleUpdater};
 
 /// Extensions to [`tauri::App`], [`tauri::AppHandle`] and [`tauri::Window`] to access the sparkle-updater APIs.
 pub trait SparkleUpdaterExt<R: Runtime> {
@@ -18,74 +88,320 @@ pub trait SparkleUpdaterExt<R: Runtime> {
     ///
     /// Returns `None` when running outside a valid macOS bundle (e.g., during `tauri dev`).
     fn sparkle_updater(&self) -> Option<tauri::State<'_, SparkleUpdater<R>>>;
+
+    /// Returns a report on how the updater was set up, including why it is unavailable.
+    fn sparkle_diagnostics(&self) -> DiagnosticsReport;
 }
 
 impl<R: Runtime, T: Manager<R>> crate::SparkleUpdaterExt<R> for T {
     fn sparkle_updater(&self) -> Option<tauri::State<'_, SparkleUpdater<R>>> {
         self.try_state::<SparkleUpdater<R>>()
     }
+
+    fn sparkle_diagnostics(&self) -> DiagnosticsReport {
+        let updater = self.sparkle_updater();
+        match self.try_state::<StartupInfo>() {
+            Some(startup) => sparkle::diagnostics(&startup, updater.as_deref()),
+            None => sparkle::diagnostics(&StartupInfo::default(), updater.as_deref()),
+        }
+    }
 }
 
 /// Initializes the plugin.
 ///
-/// Sparkle configuration is read from the app's Info.plist:
+/// Settings come from `plugins.sparkle-updater` in `tauri.conf.json` (see [`Config`]) and
+/// from the app's Info.plist. Values changed at runtime win over `tauri.conf.json`, which wins
+/// over Info.plist:
 /// - `SUFeedURL` - Appcast feed URL
 /// - `SUPublicEDKey` - Ed25519 public key for signature verification
 /// - `SUEnableAutomaticChecks` - Enable automatic update checks (default: true)
 /// - `SUAutomaticallyUpdate` - Automatically download and install updates (default: false)
 /// - `SUScheduledCheckInterval` - Check interval in seconds (default: 86400)
+///
+/// Use [`Builder`] to install hooks and policies before Sparkle starts.
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    Builder::new("sparkle-updater")
-        .invoke_handler(tauri::generate_handler![
-            commands::check_for_updates,
-            commands::check_for_updates_in_background,
-            commands::can_check_for_updates,
-            commands::current_version,
-            commands::feed_url,
-            commands::set_feed_url,
-            commands::automatically_checks_for_updates,
-            commands::set_automatically_checks_for_updates,
-            commands::automatically_downloads_updates,
-            commands::set_automatically_downloads_updates,
-            commands::last_update_check_date,
-            commands::reset_update_cycle,
-            commands::update_check_interval,
-            commands::set_update_check_interval,
-            commands::check_for_update_information,
-            commands::session_in_progress,
-            commands::http_headers,
-            commands::set_http_headers,
-            commands::user_agent_string,
-            commands::set_user_agent_string,
-            commands::sends_system_profile,
-            commands::set_sends_system_profile,
-            commands::clear_feed_url_from_user_defaults,
-            commands::reset_update_cycle_after_short_delay,
-            commands::allowed_channels,
-            commands::set_allowed_channels,
-            commands::feed_url_override,
-            commands::set_feed_url_override,
-            commands::feed_parameters,
-            commands::set_feed_parameters,
-            commands::should_download_release_notes,
-            commands::set_should_download_release_notes,
-            commands::should_relaunch_application,
-            commands::set_should_relaunch_application,
-            commands::may_check_for_updates_config,
-            commands::set_may_check_for_updates_config,
-            commands::should_proceed_with_update,
-            commands::set_should_proceed_with_update,
-            commands::decryption_password,
-            commands::set_decryption_password,
-            commands::download_request_headers,
-            commands::set_download_request_headers,
-            commands::last_found_update,
-        ])
-        .setup(|app, _api| {
-            if let Some(sparkle_updater) = sparkle::init(app)? {
-                app.manage(sparkle_updater);
-            }
-            Ok(())
-        })
-        .build()
+    Builder::new().build()
+}
+
+enum StoreSource {
+    AppConfigDir,
+    Custom(Arc<dyn SettingsStore>),
+}
+
+/// Builds the plugin with hooks and policies that are installed before Sparkle starts its
+/// first scheduled check.
+#[derive(Default)]
+pub struct Builder {
+    store: Option<StoreSource>,
+    event_listeners: Vec<sparkle::EventCallback>,
+    proceed_policy: Option<ProceedPolicy>,
+    may_check_policies: MayCheckPolicies,
+    feed_url_policy: Option<Arc<dyn FeedUrlPolicy>>,
+    version_comparator: Option<Arc<dyn VersionComparator>>,
+    backend: Option<Arc<dyn UpdaterBackend>>,
+    start_mode: Option<StartMode>,
+    prompt_for_permission: Option<bool>,
+    user_driver: Option<UserDriverMode>,
+    migrations: Migrations,
+    migration_store: Option<Arc<dyn MigrationStore>>,
+    pre_install_hooks: PreInstallHooks,
+    fetcher: Option<Arc<dyn Fetcher>>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Persists delegate-side settings in `sparkle-updater.json` inside the app config
+    /// directory.
+    pub fn persist_settings(mut self) -> Self {
+        self.store = Some(StoreSource::AppConfigDir);
+        self
+    }
+
+    /// Persists delegate-side settings in a custom [`SettingsStore`].
+    pub fn settings_store(mut self, store: impl SettingsStore + 'static) -> Self {
+        self.store = Some(StoreSource::Custom(Arc::new(store)));
+        self
+    }
+
+    /// Adds a listener called with the name and payload of every `sparkle://` event.
+    pub fn on_event(mut self, listener: impl Fn(&str, &Value) + Send + Sync + 'static) -> Self {
+        self.event_listeners.push(Arc::new(listener));
+        self
+    }
+
+    /// Sets the policy consulted for every update Sparkle is about to proceed with.
+    pub fn proceed_policy(
+        mut self,
+        policy: impl Fn(&UpdateInfo, UpdateCheck) -> ProceedDecision + Send + Sync + 'static,
+    ) -> Self {
+        self.proceed_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Appends a policy to the chain consulted before every update check.
+    pub fn may_check_policy(mut self, policy: impl MayCheckPolicy + 'static) -> Self {
+        self.may_check_policies.push(Arc::new(policy));
+        self
+    }
+
+    /// Sets a policy every feed URL must pass, on top of `feedPolicy` from `tauri.conf.json`.
+    pub fn feed_url_policy(mut self, policy: impl FeedUrlPolicy + 'static) -> Self {
+        self.feed_url_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Replaces the version ordering used by Sparkle and by the downgrade checks.
+    pub fn version_comparator(mut self, comparator: impl VersionComparator + 'static) -> Self {
+        self.version_comparator = Some(Arc::new(comparator));
+        self
+    }
+
+    /// Routes update checks to a custom [`UpdaterBackend`] instead of Sparkle's updater.
+    pub fn backend(mut self, backend: impl UpdaterBackend + 'static) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    /// Overrides `startMode` from `tauri.conf.json`.
+    pub fn start_mode(mut self, mode: StartMode) -> Self {
+        self.start_mode = Some(mode);
+        self
+    }
+
+    /// Overrides `promptForPermission` from `tauri.conf.json`.
+    pub fn prompt_for_permission(mut self, prompt: bool) -> Self {
+        self.prompt_for_permission = Some(prompt);
+        self
+    }
+
+    /// Overrides `userDriver` from `tauri.conf.json`.
+    pub fn user_driver(mut self, mode: UserDriverMode) -> Self {
+        self.user_driver = Some(mode);
+        self
+    }
+
+    /// Sets the migrations run on the first launch after an update.
+    pub fn migrations(mut self, migrations: Migrations) -> Self {
+        self.migrations = migrations;
+        self
+    }
+
+    /// Records completed migrations in a custom [`MigrationStore`] instead of
+    /// `sparkle-migrations.json` inside the app config directory.
+    pub fn migration_store(mut self, store: impl MigrationStore + 'static) -> Self {
+        self.migration_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Sets the hooks run right before Sparkle installs an update.
+    pub fn pre_install_hooks(mut self, hooks: PreInstallHooks) -> Self {
+        self.pre_install_hooks = hooks;
+        self
+    }
+
+    /// Replaces how the plugin downloads documents besides the appcast, such as the signed
+    /// minimum version and revoked releases documents and remote release notes.
+    pub fn fetcher(mut self, fetcher: impl Fetcher + 'static) -> Self {
+        self.fetcher = Some(Arc::new(fetcher));
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        let Builder {
+            store,
+            event_listeners,
+            proceed_policy,
+            may_check_policies,
+            feed_url_policy,
+            version_comparator,
+            backend,
+            start_mode,
+            prompt_for_permission,
+            user_driver,
+            migrations,
+            migration_store,
+            pre_install_hooks,
+            fetcher,
+        } = self;
+        let mut options = Options {
+            store: None,
+            event_listeners,
+            proceed_policy,
+            may_check_policies,
+            feed_url_policy,
+            version_comparator,
+            backend,
+            start_mode,
+            prompt_for_permission,
+            user_driver,
+            migrations,
+            migration_store,
+            pre_install_hooks,
+            fetcher,
+        };
+
+        PluginBuilder::<R, Option<Config>>::new("sparkle-updater")
+            .invoke_handler(tauri::generate_handler![
+                commands::check_for_updates,
+                commands::check_for_updates_in_background,
+                commands::can_check_for_updates,
+                commands::current_version,
+                commands::feed_url,
+                commands::set_feed_url,
+                commands::automatically_checks_for_updates,
+                commands::set_automatically_checks_for_updates,
+                commands::automatically_downloads_updates,
+                commands::set_automatically_downloads_updates,
+                commands::last_update_check_date,
+                commands::reset_update_cycle,
+                commands::update_check_interval,
+                commands::set_update_check_interval,
+                commands::check_for_update_information,
+                commands::session_in_progress,
+                commands::http_headers,
+                commands::set_http_headers,
+                commands::user_agent_string,
+                commands::set_user_agent_string,
+                commands::sends_system_profile,
+                commands::set_sends_system_profile,
+                commands::clear_feed_url_from_user_defaults,
+                commands::reset_update_cycle_after_short_delay,
+                commands::allowed_channels,
+                commands::set_allowed_channels,
+                commands::feed_url_override,
+                commands::set_feed_url_override,
+                commands::feed_parameters,
+                commands::set_feed_parameters,
+                commands::should_download_release_notes,
+                commands::set_should_download_release_notes,
+                commands::should_relaunch_application,
+                commands::set_should_relaunch_application,
+                commands::may_check_for_updates_config,
+                commands::set_may_check_for_updates_config,
+                commands::should_proceed_with_update,
+                commands::set_should_proceed_with_update,
+                commands::decryption_password,
+                commands::set_decryption_password,
+                commands::download_request_headers,
+                commands::set_download_request_headers,
+                commands::last_found_update,
+                commands::channels,
+                commands::current_enrollment,
+                commands::enroll_channel,
+                commands::leave_channel,
+                commands::start_updater,
+                commands::is_started,
+                commands::record_update_consent,
+                commands::should_prompt_for_permission,
+                commands::set_should_prompt_for_permission,
+                commands::diagnostics,
+                commands::reply_user_driver,
+                commands::pending_user_driver_requests,
+                commands::cancel_update,
+                commands::skip_update,
+                commands::remind_later,
+                commands::install_update_now,
+                commands::block_install,
+                commands::unblock_install,
+                commands::install_block_status,
+                commands::ready_to_quit,
+                commands::consume_whats_new,
+                commands::mark_update_healthy,
+                commands::update_health,
+                commands::update_required,
+                commands::check_minimum_version,
+                commands::install_required_update,
+                commands::current_version_revoked,
+                commands::check_revoked_releases,
+                commands::release_notes,
+                commands::cumulative_release_notes,
+            ])
+            .on_event(|app, event| {
+                if let RunEvent::ExitRequested { code, api, .. } = event {
+                    if let Some(updater) = app.sparkle_updater() {
+                        if updater.handle_exit_requested(*code) {
+                            api.prevent_exit();
+                        }
+                    }
+                }
+            })
+            .setup(move |app, api| {
+                let config = api.config().clone().unwrap_or_default();
+                config.validate()?;
+                app.manage(StartupInfo {
+                    config: config.clone(),
+                    start_mode: options.start_mode.or(config.start_mode).unwrap_or_default(),
+                    user_driver: options
+                        .user_driver
+                        .or(config.user_driver)
+                        .unwrap_or_default(),
+                });
+                options.store = match store {
+                    Some(StoreSource::AppConfigDir) => {
+                        let path = app.path().app_config_dir()?.join(store::SETTINGS_FILE_NAME);
+                        let store: Arc<dyn SettingsStore> = Arc::new(JsonFileStore::new(path));
+                        Some(store)
+                    }
+                    Some(StoreSource::Custom(store)) => Some(store),
+                    None => None,
+                };
+                if options.migration_store.is_none() && !options.migrations.is_empty() {
+                    let path = app
+                        .path()
+                        .app_config_dir()?
+                        .join(migrations::MIGRATIONS_FILE_NAME);
+                    let store: Arc<dyn MigrationStore> =
+                        Arc::new(JsonFileMigrationStore::new(path));
+                    options.migration_store = Some(store);
+                }
+                if let Some(sparkle_updater) = sparkle::init(app, &config, options)? {
+                    app.manage(sparkle_updater);
+                }
+                Ok(())
+            })
+            .build()
+    }
 }
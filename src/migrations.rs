@@ -0,0 +1,413 @@
+//! Data migrations run on the first launch after an update.
+//!
+//! Every step is declared against the build version that introduced it and runs when an
+//! update crosses that version, including when the user skipped several releases in
+//! between. Steps run in version order, each at most once. When a step fails, the
+//! remaining ones are retried on the next launch.
+
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::events::EVENT_MIGRATION_FAILED;
+use crate::store::{read_json, write_json};
+use crate::version::VersionComparator;
+use crate::Result;
+
+/// File name used for the migration state in the app config directory.
+pub const MIGRATIONS_FILE_NAME: &str = "sparkle-migrations.json";
+
+pub type MigrationError = Box<dyn std::error::Error + Send + Sync>;
+
+type MigrationFn =
+    Arc<dyn Fn(&MigrationContext) -> std::result::Result<(), MigrationError> + Send + Sync>;
+
+/// The update a migration runs for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationContext {
+    /// Build version the app was updated from.
+    pub from: String,
+    /// Build version running now.
+    pub to: String,
+}
+
+#[derive(Clone)]
+struct Migration {
+    id: String,
+    /// Build version that introduced the migration. Updates from an older build to this
+    /// one or a newer one run it.
+    version: String,
+    run: MigrationFn,
+}
+
+/// Which migrations have run, and from which build failed ones are retried.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MigrationState {
+    /// Ids of the migrations that completed.
+    pub completed: Vec<String>,
+    /// Build version the interrupted update started from, while migrations are left.
+    pub pending_from: Option<String>,
+}
+
+/// Storage backend for [`MigrationState`].
+pub trait MigrationStore: Send + Sync {
+    fn load(&self) -> Result<MigrationState>;
+
+    fn save(&self, state: &MigrationState) -> Result<()>;
+}
+
+/// Stores the migration state as a JSON file.
+#[derive(Clone, Debug)]
+pub struct JsonFileMigrationStore {
+    path: PathBuf,
+}
+
+impl JsonFileMigrationStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl MigrationStore for JsonFileMigrationStore {
+    fn load(&self) -> Result<MigrationState> {
+        Ok(read_json(&self.path)?.unwrap_or_default())
+    }
+
+    fn save(&self, state: &MigrationState) -> Result<()> {
+        write_json(&self.path, state)
+    }
+}
+
+/// Keeps the migration state in memory only. Useful for tests.
+#[derive(Debug, Default)]
+pub struct MemoryMigrationStore {
+    state: Mutex<MigrationState>,
+}
+
+impl MigrationStore for MemoryMigrationStore {
+    fn load(&self) -> Result<MigrationState> {
+        Ok(self.state.lock().unwrap().clone())
+    }
+
+    fn save(&self, state: &MigrationState) -> Result<()> {
+        *self.state.lock().unwrap() = state.clone();
+        Ok(())
+    }
+}
+
+/// A migration that returned an error.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationFailure {
+    pub id: String,
+    pub version: String,
+    pub error: String,
+}
+
+/// Outcome of [`Migrations::run`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    /// Ids of the migrations that completed during this run, in order.
+    pub completed: Vec<String>,
+    /// The migration that failed. Migrations after it did not run.
+    pub failed: Option<MigrationFailure>,
+}
+
+/// Ordered registry of migration steps.
+#[derive(Clone, Default)]
+pub struct Migrations {
+    steps: Vec<Migration>,
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a step introduced in build `version`. Steps for the same version run in the
+    /// order they were added.
+    pub fn add(
+        mut self,
+        id: impl Into<String>,
+        version: impl Into<String>,
+        run: impl Fn(&MigrationContext) -> std::result::Result<(), MigrationError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.steps.push(Migration {
+            id: id.into(),
+            version: version.into(),
+            run: Arc::new(run),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Runs the migrations due on this launch. `previous` is the build of the previous
+    /// launch when this launch follows an update, `None` otherwise. Steps left by a failed
+    /// run are retried either way.
+    pub fn run(
+        &self,
+        store: &dyn MigrationStore,
+        comparator: &dyn VersionComparator,
+        previous: Option<&str>,
+        current: &str,
+    ) -> Result<MigrationReport> {
+        let mut state = store.load()?;
+        let mut report = MigrationReport::default();
+        let Some(from) = state.pending_from.clone().or(previous.map(str::to_string)) else {
+            return Ok(report);
+        };
+        let context = MigrationContext {
+            from: from.clone(),
+            to: current.to_string(),
+        };
+
+        for step in self.due(comparator, &from, current) {
+            if state.completed.contains(&step.id) {
+                continue;
+            }
+            if let Err(error) = (step.run)(&context) {
+                report.failed = Some(MigrationFailure {
+                    id: step.id.clone(),
+                    version: step.version.clone(),
+                    error: error.to_string(),
+                });
+                state.pending_from = Some(from);
+                store.save(&state)?;
+                return Ok(report);
+            }
+            state.completed.push(step.id.clone());
+            report.completed.push(step.id.clone());
+            store.save(&state)?;
+        }
+
+        if state.pending_from.take().is_some() {
+            store.save(&state)?;
+        }
+        Ok(report)
+    }
+
+    /// Runs the migrations like [`run`](Self::run) and hands a failure to `emit` as
+    /// `sparkle://migration-failed`. Errors of the store are only logged, so a broken store
+    /// never keeps the app from launching.
+    pub(crate) fn run_on_launch(
+        &self,
+        store: &dyn MigrationStore,
+        comparator: &dyn VersionComparator,
+        previous: Option<&str>,
+        current: &str,
+        emit: impl FnOnce(&str, &MigrationFailure),
+    ) {
+        match self.run(store, comparator, previous, current) {
+            Ok(report) => {
+                if let Some(failure) = &report.failed {
+                    error!("Migration {} failed: {}", failure.id, failure.error);
+                    emit(EVENT_MIGRATION_FAILED, failure);
+                }
+            }
+            Err(e) => error!("Failed to run migrations: {}", e),
+        }
+    }
+
+    /// Steps introduced after `from` and up to `to`, in version order.
+    fn due(&self, comparator: &dyn VersionComparator, from: &str, to: &str) -> Vec<&Migration> {
+        let mut due: Vec<&Migration> = self
+            .steps
+            .iter()
+            .filter(|step| {
+                comparator.compare(&step.version, from) == Ordering::Greater
+                    && comparator.compare(&step.version, to) != Ordering::Greater
+            })
+            .collect();
+        // Stable, so steps of the same version keep their declaration order.
+        due.sort_by(|a, b| comparator.compare(&a.version, &b.version));
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+
+    use crate::version::StandardVersionComparator;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    fn step(
+        log: &Log,
+        id: &'static str,
+    ) -> impl Fn(&MigrationContext) -> std::result::Result<(), MigrationError> {
+        let log = log.clone();
+        move |context: &MigrationContext| {
+            log.lock()
+                .unwrap()
+                .push(format!("{} {}->{}", id, context.from, context.to));
+            Ok(())
+        }
+    }
+
+    fn run(
+        migrations: &Migrations,
+        store: &MemoryMigrationStore,
+        previous: Option<&str>,
+        current: &str,
+    ) -> MigrationReport {
+        migrations
+            .run(store, &StandardVersionComparator, previous, current)
+            .unwrap()
+    }
+
+    #[test]
+    fn runs_steps_introduced_after_the_previous_build_up_to_the_current_one() {
+        let log = Log::default();
+        let migrations = Migrations::new()
+            .add("v10", "10", step(&log, "v10"))
+            .add("v20", "20", step(&log, "v20"))
+            .add("v30", "30", step(&log, "v30"))
+            .add("v40", "40", step(&log, "v40"));
+        let store = MemoryMigrationStore::default();
+
+        let report = run(&migrations, &store, Some("10"), "30");
+        assert_eq!(report.completed, vec!["v20", "v30"]);
+        assert_eq!(report.failed, None);
+        assert_eq!(*log.lock().unwrap(), vec!["v20 10->30", "v30 10->30"]);
+    }
+
+    #[test]
+    fn runs_nothing_without_an_update() {
+        let log = Log::default();
+        let migrations = Migrations::new().add("v20", "20", step(&log, "v20"));
+        let store = MemoryMigrationStore::default();
+
+        assert_eq!(
+            run(&migrations, &store, None, "20"),
+            MigrationReport::default()
+        );
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn runs_each_step_once() {
+        let log = Log::default();
+        let migrations = Migrations::new().add("v20", "20", step(&log, "v20")).add(
+            "v30",
+            "30",
+            step(&log, "v30"),
+        );
+        let store = MemoryMigrationStore::default();
+
+        run(&migrations, &store, Some("10"), "20");
+        let report = run(&migrations, &store, Some("10"), "30");
+        assert_eq!(report.completed, vec!["v30"]);
+        assert_eq!(*log.lock().unwrap(), vec!["v20 10->20", "v30 10->30"]);
+        assert_eq!(
+            store.load().unwrap(),
+            MigrationState {
+                completed: vec!["v20".to_string(), "v30".to_string()],
+                pending_from: None,
+            }
+        );
+    }
+
+    #[test]
+    fn runs_in_version_order_then_declaration_order() {
+        let log = Log::default();
+        let migrations = Migrations::new()
+            .add("c", "30", step(&log, "c"))
+            .add("a", "20", step(&log, "a"))
+            .add("b", "20", step(&log, "b"))
+            .add("beta", "20b1", step(&log, "beta"));
+        let store = MemoryMigrationStore::default();
+
+        let report = run(&migrations, &store, Some("10"), "30");
+        assert_eq!(report.completed, vec!["beta", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn failed_step_is_reported_and_retried_on_the_next_launch() {
+        let log = Log::default();
+        let healthy = Arc::new(AtomicBool::new(false));
+        let flaky = {
+            let healthy = healthy.clone();
+            let log = log.clone();
+            move |_: &MigrationContext| -> std::result::Result<(), MigrationError> {
+                if !healthy.load(AtomicOrdering::SeqCst) {
+                    return Err("disk full".into());
+                }
+                log.lock().unwrap().push("v20".to_string());
+                Ok(())
+            }
+        };
+        let migrations = Migrations::new()
+            .add("v15", "15", step(&log, "v15"))
+            .add("v20", "20", flaky)
+            .add("v30", "30", step(&log, "v30"));
+        let store = MemoryMigrationStore::default();
+
+        let mut emitted = Vec::new();
+        migrations.run_on_launch(
+            &store,
+            &StandardVersionComparator,
+            Some("10"),
+            "30",
+            |event, failure| emitted.push((event.to_string(), failure.clone())),
+        );
+        let failure = MigrationFailure {
+            id: "v20".to_string(),
+            version: "20".to_string(),
+            error: "disk full".to_string(),
+        };
+        assert_eq!(emitted, vec![(EVENT_MIGRATION_FAILED.to_string(), failure)]);
+        assert_eq!(
+            store.load().unwrap(),
+            MigrationState {
+                completed: vec!["v15".to_string()],
+                pending_from: Some("10".to_string()),
+            }
+        );
+        assert_eq!(*log.lock().unwrap(), vec!["v15 10->30"]);
+
+        // The next launch is no update, but the steps left are due from the original build.
+        healthy.store(true, AtomicOrdering::SeqCst);
+        let report = run(&migrations, &store, None, "30");
+        assert_eq!(report.completed, vec!["v20", "v30"]);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["v15 10->30", "v20", "v30 10->30"]
+        );
+        assert_eq!(store.load().unwrap().pending_from, None);
+    }
+
+    #[test]
+    fn success_emits_nothing() {
+        let log = Log::default();
+        let migrations = Migrations::new().add("v20", "20", step(&log, "v20"));
+        let store = MemoryMigrationStore::default();
+
+        let mut emitted = false;
+        migrations.run_on_launch(
+            &store,
+            &StandardVersionComparator,
+            Some("10"),
+            "20",
+            |_, _| emitted = true,
+        );
+        assert!(!emitted);
+    }
+}
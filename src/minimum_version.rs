@@ -0,0 +1,212 @@
+//! Minimum supported version, below which the running build must update.
+//!
+//! The minimum is declared in a custom appcast element, or in a small JSON document signed
+//! with the app's EdDSA update key. Once the running build is below it, the plugin reports
+//! `update-required` and refuses to skip or postpone the update.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::signature::SignedDocumentConfig;
+use crate::version::VersionComparator;
+
+/// Minimum version settings from `minimumVersion` in the plugin configuration. The appcast
+/// element holds the minimum build version, e.g. `myapp:minimumSupportedVersion`, and the
+/// document is a [`MinimumVersionDocument`].
+pub type MinimumVersionConfig = SignedDocumentConfig;
+
+/// Content of the signed minimum version document.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinimumVersionDocument {
+    /// Lowest build version (`CFBundleVersion`) still allowed to run.
+    pub minimum_version: String,
+    /// Shown to the user, e.g. why the update is required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Where a minimum version was declared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MinimumVersionSource {
+    Appcast,
+    Document,
+}
+
+/// Payload of `sparkle://update-required`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRequiredInfo {
+    pub minimum_version: String,
+    pub current_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub source: MinimumVersionSource,
+}
+
+/// Whether the running build must update, from the highest minimum declared so far.
+#[derive(Clone, Debug, Default)]
+pub struct MinimumVersionGate {
+    required: Option<UpdateRequiredInfo>,
+}
+
+impl MinimumVersionGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a declared minimum. Returns the new state when it makes an update required
+    /// or raises the minimum of a required one.
+    pub fn declare(
+        &mut self,
+        comparator: &dyn VersionComparator,
+        current: &str,
+        minimum: &str,
+        message: Option<String>,
+        source: MinimumVersionSource,
+    ) -> Option<UpdateRequiredInfo> {
+        if comparator.compare(current, minimum) != Ordering::Less {
+            return None;
+        }
+        if let Some(required) = &self.required {
+            if comparator.compare(minimum, &required.minimum_version) != Ordering::Greater {
+                return None;
+            }
+        }
+        let required = UpdateRequiredInfo {
+            minimum_version: minimum.to_string(),
+            current_version: current.to_string(),
+            message,
+            source,
+        };
+        self.required = Some(required.clone());
+        Some(required)
+    }
+
+    pub fn required(&self) -> Option<&UpdateRequiredInfo> {
+        self.required.as_ref()
+    }
+}
+
+/// The highest minimum among the values an appcast declares, ignoring empty ones.
+pub fn highest_minimum<'a>(
+    comparator: &dyn VersionComparator,
+    values: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    values
+        .into_iter()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .max_by(|a, b| comparator.compare(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::verify_document;
+    use crate::test_support::{public_key, sign};
+    use crate::version::StandardVersionComparator;
+    use crate::Error;
+
+    #[test]
+    fn verifies_signed_document() {
+        let document = br#"{"minimumVersion":"120","message":"Security fix"}"#;
+        let parsed =
+            verify_document::<MinimumVersionDocument>(document, &sign(document), &public_key());
+        assert_eq!(
+            parsed.unwrap(),
+            MinimumVersionDocument {
+                minimum_version: "120".to_string(),
+                message: Some("Security fix".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_document() {
+        let document = br#"{"minimumVersion":"120"}"#;
+        let tampered = br#"{"minimumVersion":"100"}"#;
+        assert!(matches!(
+            verify_document::<MinimumVersionDocument>(tampered, &sign(document), &public_key()),
+            Err(Error::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_document_without_minimum() {
+        for document in [&br#"{"message":"Update"}"#[..], b"", b"120"] {
+            let result =
+                verify_document::<MinimumVersionDocument>(document, &sign(document), &public_key());
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn below_minimum_requires_update() {
+        let mut gate = MinimumVersionGate::new();
+        let required = gate.declare(
+            &StandardVersionComparator,
+            "100",
+            "120",
+            Some("Security fix".to_string()),
+            MinimumVersionSource::Document,
+        );
+        let expected = UpdateRequiredInfo {
+            minimum_version: "120".to_string(),
+            current_version: "100".to_string(),
+            message: Some("Security fix".to_string()),
+            source: MinimumVersionSource::Document,
+        };
+        assert_eq!(required, Some(expected.clone()));
+        assert_eq!(gate.required(), Some(&expected));
+    }
+
+    #[test]
+    fn at_or_above_minimum_does_not_require_update() {
+        let mut gate = MinimumVersionGate::new();
+        for minimum in ["100", "99", "1.0"] {
+            let required = gate.declare(
+                &StandardVersionComparator,
+                "100",
+                minimum,
+                None,
+                MinimumVersionSource::Appcast,
+            );
+            assert_eq!(required, None);
+        }
+        assert_eq!(gate.required(), None);
+    }
+
+    #[test]
+    fn only_a_higher_minimum_is_reported_again() {
+        let comparator = StandardVersionComparator;
+        let mut gate = MinimumVersionGate::new();
+        let declare = |gate: &mut MinimumVersionGate, minimum| {
+            gate.declare(
+                &comparator,
+                "100",
+                minimum,
+                None,
+                MinimumVersionSource::Appcast,
+            )
+        };
+
+        assert!(declare(&mut gate, "110").is_some());
+        assert!(declare(&mut gate, "110").is_none());
+        assert!(declare(&mut gate, "105").is_none());
+        assert_eq!(declare(&mut gate, "120").unwrap().minimum_version, "120");
+        assert_eq!(gate.required().unwrap().minimum_version, "120");
+    }
+
+    #[test]
+    fn highest_minimum_ignores_empty_values() {
+        let comparator = StandardVersionComparator;
+        assert_eq!(
+            highest_minimum(&comparator, ["", " 110 ", "99", "1.0"]),
+            Some("110")
+        );
+        assert_eq!(highest_minimum(&comparator, ["", "  "]), None);
+    }
+}
@@ -0,0 +1,375 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::events::UpdateInfo;
+
+/// The kind of update check Sparkle is performing (`SPUUpdateCheck`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateCheck {
+    /// Started by the user, e.g. via "Check for Updates…".
+    UserInitiated,
+    /// Scheduled or started with `check_for_updates_in_background`.
+    Background,
+    /// Probing check started with `check_for_update_information`.
+    Information,
+}
+
+impl UpdateCheck {
+    pub(crate) fn from_raw(value: isize) -> Self {
+        match value {
+            0 => UpdateCheck::UserInitiated,
+            1 => UpdateCheck::Background,
+            _ => UpdateCheck::Information,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateCheck::UserInitiated => "userInitiated",
+            UpdateCheck::Background => "background",
+            UpdateCheck::Information => "information",
+        }
+    }
+}
+
+/// Result of a [`ProceedPolicy`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProceedDecision {
+    Proceed,
+    /// Refuses the update. The reason is reported to Sparkle and emitted as
+    /// `sparkle://did-reject-update`.
+    Reject(String),
+}
+
+/// Decides per update whether Sparkle may proceed with it.
+pub type ProceedPolicy = Arc<dyn Fn(&UpdateInfo, UpdateCheck) -> ProceedDecision + Send + Sync>;
+
+/// Result of a [`MayCheckPolicy`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckDecision {
+    Allow,
+    /// Skips the check. The reason is reported to Sparkle and emitted as
+    /// `sparkle://did-deny-update-check`.
+    Deny(String),
+}
+
+/// Decides whether Sparkle may perform an update check of a given kind.
+///
+/// Implemented for closures taking an [`UpdateCheck`] and returning a [`CheckDecision`].
+pub trait MayCheckPolicy: Send + Sync {
+    fn evaluate(&self, check: UpdateCheck) -> CheckDecision;
+}
+
+impl<F> MayCheckPolicy for F
+where
+    F: Fn(UpdateCheck) -> CheckDecision + Send + Sync,
+{
+    fn evaluate(&self, check: UpdateCheck) -> CheckDecision {
+        self(check)
+    }
+}
+
+/// Ordered chain of [`MayCheckPolicy`]s. The first denial wins.
+#[derive(Clone, Default)]
+pub struct MayCheckPolicies {
+    policies: Vec<Arc<dyn MayCheckPolicy>>,
+}
+
+impl MayCheckPolicies {
+    pub fn push(&mut self, policy: Arc<dyn MayCheckPolicy>) {
+        self.policies.push(policy);
+    }
+
+    pub fn clear(&mut self) {
+        self.policies.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.policies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    pub fn evaluate(&self, check: UpdateCheck) -> CheckDecision {
+        self.policies
+            .iter()
+            .map(|policy| policy.evaluate(check))
+            .find(|decision| matches!(decision, CheckDecision::Deny(_)))
+            .unwrap_or(CheckDecision::Allow)
+    }
+}
+
+/// Decides whether a feed URL may be used. Consulted for the configured feed URL, `set_feed_url`
+/// and `set_feed_url_override`, after `feedPolicy` from `tauri.conf.json`.
+///
+/// Implemented for closures taking the URL and returning a [`CheckDecision`].
+pub trait FeedUrlPolicy: Send + Sync {
+    fn evaluate(&self, url: &str) -> CheckDecision;
+}
+
+impl<F> FeedUrlPolicy for F
+where
+    F: Fn(&str) -> CheckDecision + Send + Sync,
+{
+    fn evaluate(&self, url: &str) -> CheckDecision {
+        self(url)
+    }
+}
+
+/// Denies checks while a shared flag is set, e.g. a presentation mode or a metered connection.
+///
+/// Applies to background checks unless configured otherwise with [`FlagPolicy::for_checks`].
+pub struct FlagPolicy {
+    flag: Arc<AtomicBool>,
+    reason: String,
+    checks: Vec<UpdateCheck>,
+}
+
+impl FlagPolicy {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self::with_flag(Arc::new(AtomicBool::new(false)), reason)
+    }
+
+    pub fn with_flag(flag: Arc<AtomicBool>, reason: impl Into<String>) -> Self {
+        Self {
+            flag,
+            reason: reason.into(),
+            checks: vec![UpdateCheck::Background],
+        }
+    }
+
+    pub fn for_checks(mut self, checks: &[UpdateCheck]) -> Self {
+        self.checks = checks.to_vec();
+        self
+    }
+
+    /// Returns the flag so the app can toggle it after registering the policy.
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.flag.clone()
+    }
+}
+
+impl MayCheckPolicy for FlagPolicy {
+    fn evaluate(&self, check: UpdateCheck) -> CheckDecision {
+        if self.checks.contains(&check) && self.flag.load(Ordering::SeqCst) {
+            CheckDecision::Deny(self.reason.clone())
+        } else {
+            CheckDecision::Allow
+        }
+    }
+}
+
+/// Denies checks during a daily window of hours, which may wrap around midnight.
+///
+/// The clock returns the current local hour (0-23). Applies to background checks unless
+/// configured otherwise with [`QuietHoursPolicy::for_checks`].
+pub struct QuietHoursPolicy {
+    start_hour: u8,
+    end_hour: u8,
+    clock: Box<dyn Fn() -> u8 + Send + Sync>,
+    checks: Vec<UpdateCheck>,
+}
+
+impl QuietHoursPolicy {
+    pub fn new(
+        start_hour: u8,
+        end_hour: u8,
+        clock: impl Fn() -> u8 + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            start_hour,
+            end_hour,
+            clock: Box::new(clock),
+            checks: vec![UpdateCheck::Background],
+        }
+    }
+
+    pub fn for_checks(mut self, checks: &[UpdateCheck]) -> Self {
+        self.checks = checks.to_vec();
+        self
+    }
+
+    fn is_quiet(&self, hour: u8) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+impl MayCheckPolicy for QuietHoursPolicy {
+    fn evaluate(&self, check: UpdateCheck) -> CheckDecision {
+        if self.checks.contains(&check) && self.is_quiet((self.clock)()) {
+            CheckDecision::Deny(format!(
+                "Quiet hours ({:02}:00-{:02}:00)",
+                self.start_hour, self.end_hour
+            ))
+        } else {
+            CheckDecision::Allow
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU8;
+
+    use super::*;
+
+    fn deny(reason: &'static str) -> Arc<dyn MayCheckPolicy> {
+        Arc::new(move |_: UpdateCheck| CheckDecision::Deny(reason.to_string()))
+    }
+
+    fn quiet_hours(start: u8, end: u8) -> (QuietHoursPolicy, Arc<AtomicU8>) {
+        let hour = Arc::new(AtomicU8::new(0));
+        let clock = hour.clone();
+        let policy = QuietHoursPolicy::new(start, end, move || clock.load(Ordering::SeqCst));
+        (policy, hour)
+    }
+
+    #[test]
+    fn empty_chain_allows() {
+        let policies = MayCheckPolicies::default();
+        assert!(policies.is_empty());
+        assert_eq!(
+            policies.evaluate(UpdateCheck::Background),
+            CheckDecision::Allow
+        );
+    }
+
+    #[test]
+    fn first_denial_wins() {
+        let mut policies = MayCheckPolicies::default();
+        policies.push(Arc::new(|_: UpdateCheck| CheckDecision::Allow));
+        policies.push(deny("first"));
+        policies.push(deny("second"));
+        assert_eq!(policies.len(), 3);
+        assert_eq!(
+            policies.evaluate(UpdateCheck::UserInitiated),
+            CheckDecision::Deny("first".to_string())
+        );
+
+        policies.clear();
+        assert_eq!(
+            policies.evaluate(UpdateCheck::UserInitiated),
+            CheckDecision::Allow
+        );
+    }
+
+    #[test]
+    fn policies_see_the_check_kind() {
+        let mut policies = MayCheckPolicies::default();
+        policies.push(Arc::new(|check: UpdateCheck| match check {
+            UpdateCheck::Information => CheckDecision::Deny("no probing".to_string()),
+            _ => CheckDecision::Allow,
+        }));
+        assert_eq!(
+            policies.evaluate(UpdateCheck::Background),
+            CheckDecision::Allow
+        );
+        assert_eq!(
+            policies.evaluate(UpdateCheck::Information),
+            CheckDecision::Deny("no probing".to_string())
+        );
+    }
+
+    #[test]
+    fn flag_policy_denies_background_checks_while_set() {
+        let policy = FlagPolicy::new("Metered connection");
+        assert_eq!(
+            policy.evaluate(UpdateCheck::Background),
+            CheckDecision::Allow
+        );
+
+        policy.flag().store(true, Ordering::SeqCst);
+        assert_eq!(
+            policy.evaluate(UpdateCheck::Background),
+            CheckDecision::Deny("Metered connection".to_string())
+        );
+        assert_eq!(
+            policy.evaluate(UpdateCheck::UserInitiated),
+            CheckDecision::Allow
+        );
+    }
+
+    #[test]
+    fn flag_policy_for_other_checks() {
+        let flag = Arc::new(AtomicBool::new(true));
+        let policy = FlagPolicy::with_flag(flag.clone(), "Presenting")
+            .for_checks(&[UpdateCheck::UserInitiated, UpdateCheck::Information]);
+        assert_eq!(
+            policy.evaluate(UpdateCheck::Background),
+            CheckDecision::Allow
+        );
+        assert_eq!(
+            policy.evaluate(UpdateCheck::UserInitiated),
+            CheckDecision::Deny("Presenting".to_string())
+        );
+
+        flag.store(false, Ordering::SeqCst);
+        assert_eq!(
+            policy.evaluate(UpdateCheck::UserInitiated),
+            CheckDecision::Allow
+        );
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let (policy, _) = quiet_hours(9, 17);
+        assert!(!policy.is_quiet(8));
+        assert!(policy.is_quiet(9));
+        assert!(policy.is_quiet(16));
+        assert!(!policy.is_quiet(17));
+    }
+
+    #[test]
+    fn quiet_hours_wrapping_past_midnight() {
+        let (policy, _) = quiet_hours(22, 6);
+        assert!(policy.is_quiet(22));
+        assert!(policy.is_quiet(23));
+        assert!(policy.is_quiet(0));
+        assert!(policy.is_quiet(5));
+        assert!(!policy.is_quiet(6));
+        assert!(!policy.is_quiet(21));
+    }
+
+    #[test]
+    fn empty_quiet_window_is_never_quiet() {
+        let (policy, _) = quiet_hours(3, 3);
+        assert!((0..24).all(|hour| !policy.is_quiet(hour)));
+    }
+
+    #[test]
+    fn quiet_hours_deny_with_the_window() {
+        let (policy, hour) = quiet_hours(22, 6);
+        hour.store(23, Ordering::SeqCst);
+        assert_eq!(
+            policy.evaluate(UpdateCheck::Background),
+            CheckDecision::Deny("Quiet hours (22:00-06:00)".to_string())
+        );
+        assert_eq!(
+            policy.evaluate(UpdateCheck::UserInitiated),
+            CheckDecision::Allow
+        );
+
+        hour.store(12, Ordering::SeqCst);
+        assert_eq!(
+            policy.evaluate(UpdateCheck::Background),
+            CheckDecision::Allow
+        );
+    }
+
+    #[test]
+    fn raw_update_checks() {
+        assert_eq!(UpdateCheck::from_raw(0), UpdateCheck::UserInitiated);
+        assert_eq!(UpdateCheck::from_raw(1), UpdateCheck::Background);
+        assert_eq!(UpdateCheck::from_raw(2), UpdateCheck::Information);
+        assert_eq!(UpdateCheck::Background.as_str(), "background");
+    }
+}
@@ -0,0 +1,450 @@
+//! Hooks run right before Sparkle installs an update.
+//!
+//! Hooks run in registration order, each on its own thread so a hung hook cannot hold the
+//! install longer than its timeout. The plugin runs the pipeline off the main thread when
+//! Sparkle hands it the install, and installs once it passes. A hook that fails or times
+//! out aborts the pipeline and the install waits to be retried. [`BackupDirectoryHook`]
+//! snapshots a directory before the update applies.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::events::UpdateInfo;
+
+pub type HookError = Box<dyn std::error::Error + Send + Sync>;
+
+pub type HookResult = std::result::Result<(), HookError>;
+
+type HookFn = Arc<dyn Fn(&PreInstallContext) -> HookResult + Send + Sync>;
+
+/// The install a hook runs for.
+#[derive(Clone, Debug)]
+pub struct PreInstallContext {
+    /// Build version being replaced.
+    pub from: String,
+    pub update: UpdateInfo,
+    /// Whether the update was staged to install when the app quits.
+    pub on_quit: bool,
+}
+
+#[derive(Clone)]
+struct Hook {
+    name: String,
+    timeout: Duration,
+    run: HookFn,
+}
+
+/// Payload of `sparkle://pre-install-aborted`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreInstallAbort {
+    pub hook: String,
+    pub reason: String,
+    /// Build version of the update that was held back.
+    pub version: String,
+    pub on_quit: bool,
+}
+
+/// Outcome of [`PreInstallHooks::run`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PreInstallReport {
+    /// Names of the hooks that completed, in order.
+    pub completed: Vec<String>,
+    /// The hook that aborted the install. Hooks after it did not run.
+    pub aborted: Option<PreInstallAbort>,
+}
+
+/// Ordered pipeline of pre-install hooks.
+#[derive(Clone, Default)]
+pub struct PreInstallHooks {
+    hooks: Vec<Hook>,
+}
+
+impl PreInstallHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hook that must finish within `timeout`. Returning an error aborts the
+    /// install with the error as reason.
+    pub fn add(
+        mut self,
+        name: impl Into<String>,
+        timeout: Duration,
+        run: impl Fn(&PreInstallContext) -> HookResult + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.push(Hook {
+            name: name.into(),
+            timeout,
+            run: Arc::new(run),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Runs the hooks in order and stops at the first one that fails or times out. Blocks
+    /// until the pipeline is done.
+    pub fn run(&self, context: &PreInstallContext) -> PreInstallReport {
+        let mut report = PreInstallReport::default();
+        for hook in &self.hooks {
+            if let Err(reason) = run_hook(hook, context) {
+                report.aborted = Some(PreInstallAbort {
+                    hook: hook.name.clone(),
+                    reason,
+                    version: context.update.build_version.clone(),
+                    on_quit: context.on_quit,
+                });
+                break;
+            }
+            report.completed.push(hook.name.clone());
+        }
+        report
+    }
+
+    /// Runs the hooks on a new thread and hands the report to `done` there. Fails only when
+    /// the thread cannot be started, and `done` is not called then.
+    pub fn run_in_background(
+        &self,
+        context: PreInstallContext,
+        done: impl FnOnce(PreInstallReport) + Send + 'static,
+    ) -> io::Result<()> {
+        let hooks = self.clone();
+        thread::Builder::new()
+            .name("pre-install".to_string())
+            .spawn(move || done(hooks.run(&context)))
+            .map(|_| ())
+    }
+}
+
+fn run_hook(hook: &Hook, context: &PreInstallContext) -> std::result::Result<(), String> {
+    let (sender, receiver) = mpsc::channel();
+    let run = hook.run.clone();
+    let context = context.clone();
+    thread::Builder::new()
+        .name(format!("pre-install-{}", hook.name))
+        .spawn(move || {
+            let _ = sender.send(run(&context).map_err(|e| e.to_string()));
+        })
+        .map_err(|e| format!("failed to start: {}", e))?;
+
+    // A timed out hook keeps running detached; the install just stops waiting for it.
+    match receiver.recv_timeout(hook.timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(format!(
+            "timed out after {} seconds",
+            hook.timeout.as_secs_f64()
+        )),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err("panicked".to_string()),
+    }
+}
+
+/// Prefix of the backup directories [`BackupDirectoryHook`] creates and prunes.
+const BACKUP_PREFIX: &str = "backup-";
+
+/// Copies a directory into a versioned backup before every install, keeping the newest
+/// `keep` backups.
+///
+/// Backups are named `backup-<milliseconds since epoch>-<replaced build version>`, so they
+/// sort by age on any filesystem. Other entries of the backup directory are left alone.
+#[derive(Clone, Debug)]
+pub struct BackupDirectoryHook {
+    source: PathBuf,
+    destination: PathBuf,
+    keep: usize,
+}
+
+impl BackupDirectoryHook {
+    /// Backs up `source` into subdirectories of `destination`, keeping the last 3.
+    pub fn new(source: impl Into<PathBuf>, destination: impl Into<PathBuf>) -> Self {
+        Self {
+            source: source.into(),
+            destination: destination.into(),
+            keep: 3,
+        }
+    }
+
+    /// Sets how many backups are kept. At least the new one is always kept.
+    pub fn keep(mut self, keep: usize) -> Self {
+        self.keep = keep.max(1);
+        self
+    }
+
+    /// Runs the backup for a pre-install hook.
+    pub fn run(&self, context: &PreInstallContext) -> HookResult {
+        self.backup(&context.from)?;
+        Ok(())
+    }
+
+    /// Copies the source directory into a new backup labelled `version`, prunes old
+    /// backups and returns the path of the new one.
+    pub fn backup(&self, version: &str) -> io::Result<PathBuf> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let name = format!("{}{:013}-{}", BACKUP_PREFIX, millis, sanitize(version));
+        let target = self.destination.join(&name);
+        // Copied under a temporary name first, so a partial copy is never taken for a backup.
+        let partial = self.destination.join(format!(".{}.partial", name));
+
+        fs::create_dir_all(&self.destination)?;
+        if partial.exists() {
+            fs::remove_dir_all(&partial)?;
+        }
+        if let Err(e) = copy_dir(&self.source, &partial) {
+            let _ = fs::remove_dir_all(&partial);
+            return Err(e);
+        }
+        fs::rename(&partial, &target)?;
+        self.prune()?;
+        Ok(target)
+    }
+
+    /// Backups in the destination directory, oldest first.
+    pub fn backups(&self) -> io::Result<Vec<PathBuf>> {
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(&self.destination)? {
+            let entry = entry?;
+            let is_backup = entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(BACKUP_PREFIX);
+            if is_backup && entry.file_type()?.is_dir() {
+                backups.push(entry.path());
+            }
+        }
+        backups.sort();
+        Ok(backups)
+    }
+
+    fn prune(&self) -> io::Result<()> {
+        let backups = self.backups()?;
+        let excess = backups.len().saturating_sub(self.keep);
+        for backup in &backups[..excess] {
+            fs::remove_dir_all(backup)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps a version usable as part of a file name.
+fn sanitize(version: &str) -> String {
+    version
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' | '-' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn copy_dir(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let to = target.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &to)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &to)?;
+        } else {
+            fs::copy(entry.path(), &to)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    fs::copy(source, target).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn context(from: &str, to: &str) -> PreInstallContext {
+        let update = serde_json::from_value(serde_json::json!({
+            "version": to,
+            "buildVersion": to,
+            "isDelta": false,
+            "isCritical": false,
+            "isMajorUpgrade": false,
+            "isInformationOnly": false,
+            "minimumOsVersionOk": true,
+            "maximumOsVersionOk": true,
+            "installationType": "application",
+        }))
+        .unwrap();
+        PreInstallContext {
+            from: from.to_string(),
+            update,
+            on_quit: false,
+        }
+    }
+
+    fn source(dir: &TempDir) -> PathBuf {
+        let source = dir.path().join("data");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("settings.json"), "{}").unwrap();
+        fs::write(source.join("nested/db.sqlite"), "rows").unwrap();
+        source
+    }
+
+    #[test]
+    fn backup_copies_the_source() {
+        let dir = TempDir::new("copy");
+        let hook = BackupDirectoryHook::new(source(&dir), dir.path().join("backups"));
+
+        let backup = hook.backup("1.2/3").unwrap();
+        let name = backup.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with(BACKUP_PREFIX));
+        assert!(name.ends_with("-1.2_3"));
+        assert_eq!(
+            fs::read_to_string(backup.join("settings.json")).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            fs::read_to_string(backup.join("nested/db.sqlite")).unwrap(),
+            "rows"
+        );
+        assert_eq!(hook.backups().unwrap(), vec![backup]);
+    }
+
+    #[test]
+    fn backup_prunes_the_oldest() {
+        let dir = TempDir::new("prune");
+        let backups = dir.path().join("backups");
+        fs::create_dir_all(backups.join("unrelated")).unwrap();
+        let hook = BackupDirectoryHook::new(source(&dir), &backups).keep(2);
+
+        let mut created = Vec::new();
+        for version in ["1", "2", "3"] {
+            created.push(hook.backup(version).unwrap());
+            // Backup names have millisecond resolution.
+            thread::sleep(Duration::from_millis(2));
+        }
+
+        assert_eq!(hook.backups().unwrap(), created[1..]);
+        assert!(!created[0].exists());
+        assert!(backups.join("unrelated").exists());
+    }
+
+    #[test]
+    fn backup_of_missing_source_fails_without_leftovers() {
+        let dir = TempDir::new("missing");
+        let backups = dir.path().join("backups");
+        let hook = BackupDirectoryHook::new(dir.path().join("absent"), &backups);
+
+        assert!(hook.run(&context("1", "2")).is_err());
+        assert_eq!(fs::read_dir(&backups).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn hooks_run_in_order() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut hooks = PreInstallHooks::new();
+        for name in ["first", "second", "third"] {
+            let order = order.clone();
+            hooks = hooks.add(name, Duration::from_secs(5), move |_| {
+                order.lock().unwrap().push(name);
+                Ok(())
+            });
+        }
+
+        let report = hooks.run(&context("1", "2"));
+        assert_eq!(report.completed, ["first", "second", "third"]);
+        assert_eq!(report.aborted, None);
+        assert_eq!(*order.lock().unwrap(), ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn failing_backup_aborts_the_pipeline() {
+        let dir = TempDir::new("abort");
+        let backup =
+            BackupDirectoryHook::new(dir.path().join("absent"), dir.path().join("backups"));
+        let ran = Arc::new(AtomicUsize::new(0));
+        let later = ran.clone();
+        let hooks = PreInstallHooks::new()
+            .add("flush", Duration::from_secs(5), |_| Ok(()))
+            .add("backup-data", Duration::from_secs(5), move |ctx| {
+                backup.run(ctx)
+            })
+            .add("close-database", Duration::from_secs(5), move |_| {
+                later.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            });
+
+        let mut context = context("1", "2");
+        context.on_quit = true;
+        let report = hooks.run(&context);
+
+        assert_eq!(report.completed, ["flush"]);
+        let aborted = report.aborted.unwrap();
+        assert_eq!(aborted.hook, "backup-data");
+        assert!(!aborted.reason.is_empty());
+        assert_eq!(aborted.version, "2");
+        assert!(aborted.on_quit);
+        assert_eq!(ran.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn hook_errors_become_the_reason() {
+        let hooks = PreInstallHooks::new()
+            .add("check", Duration::from_secs(5), |_| Err("disk full".into()));
+        let aborted = hooks.run(&context("1", "2")).aborted.unwrap();
+        assert_eq!(aborted.reason, "disk full");
+    }
+
+    #[test]
+    fn hung_hook_times_out() {
+        let hooks = PreInstallHooks::new().add("hang", Duration::from_millis(20), |_| {
+            thread::sleep(Duration::from_secs(1));
+            Ok(())
+        });
+        let aborted = hooks.run(&context("1", "2")).aborted.unwrap();
+        assert_eq!(aborted.hook, "hang");
+        assert!(aborted.reason.starts_with("timed out"));
+    }
+
+    #[test]
+    fn runs_in_background_and_reports_back() {
+        let hooks = PreInstallHooks::new()
+            .add("check", Duration::from_secs(5), |_| Err("disk full".into()));
+        let (sender, receiver) = mpsc::channel();
+        hooks
+            .run_in_background(context("1", "2"), move |report| {
+                sender.send(report).unwrap();
+            })
+            .unwrap();
+        let report = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(report.aborted.unwrap().hook, "check");
+    }
+
+    #[test]
+    fn panicking_hook_aborts() {
+        let hooks = PreInstallHooks::new().add("panic", Duration::from_secs(5), |_| panic!());
+        let aborted = hooks.run(&context("1", "2")).aborted.unwrap();
+        assert_eq!(aborted.reason, "panicked");
+    }
+}
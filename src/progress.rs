@@ -0,0 +1,206 @@
+//! Throttling of `sparkle://download-progress` events.
+//!
+//! Sparkle reports every chunk it receives, which is far more often than a progress bar
+//! needs to redraw. Events are emitted at most once per interval, except for the first
+//! and the last one of a download.
+
+use std::time::{Duration, Instant};
+
+use crate::events::DownloadProgressInfo;
+
+/// Minimum time between two progress events.
+pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Download progress of the current update session.
+#[derive(Debug)]
+pub struct DownloadProgress {
+    interval: Duration,
+    /// Enclosure length from the appcast, used when the server omits `Content-Length`.
+    fallback_length: Option<u64>,
+    /// Length the server reported.
+    server_length: Option<u64>,
+    bytes_received: u64,
+    last_emitted: Option<Instant>,
+}
+
+impl DownloadProgress {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            fallback_length: None,
+            server_length: None,
+            bytes_received: 0,
+            last_emitted: None,
+        }
+    }
+
+    /// Sets the enclosure length of the update about to be downloaded. `0` means unknown.
+    pub fn set_fallback_length(&mut self, length: u64) {
+        self.fallback_length = Some(length).filter(|&length| length > 0);
+    }
+
+    /// Starts a download whose server reported `expected_length` bytes, `0` if it did not.
+    /// Always returns an event, so the frontend can show the expected size right away.
+    pub fn start(&mut self, expected_length: u64, now: Instant) -> DownloadProgressInfo {
+        self.server_length = Some(expected_length).filter(|&length| length > 0);
+        self.bytes_received = 0;
+        self.last_emitted = Some(now);
+        self.info()
+    }
+
+    /// Records `length` more bytes. Returns an event when the interval has passed since the
+    /// last one or the download is complete.
+    pub fn receive(&mut self, length: u64, now: Instant) -> Option<DownloadProgressInfo> {
+        let was_complete = self.is_complete();
+        self.bytes_received = self.bytes_received.saturating_add(length);
+        let completed = !was_complete && self.is_complete();
+        let due = match self.last_emitted {
+            Some(last) => now.saturating_duration_since(last) >= self.interval,
+            None => true,
+        };
+        if !completed && !due {
+            return None;
+        }
+        self.last_emitted = Some(now);
+        Some(self.info())
+    }
+
+    /// Forgets the current download and the fallback length when the update session ends.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.interval);
+    }
+
+    fn expected_length(&self) -> Option<u64> {
+        self.server_length.or(self.fallback_length)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.expected_length()
+            .is_some_and(|expected| self.bytes_received >= expected)
+    }
+
+    pub fn info(&self) -> DownloadProgressInfo {
+        DownloadProgressInfo {
+            expected_content_length: self.expected_length(),
+            bytes_received: self.bytes_received,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(250);
+
+    fn info(expected: Option<u64>, received: u64) -> DownloadProgressInfo {
+        DownloadProgressInfo {
+            expected_content_length: expected,
+            bytes_received: received,
+        }
+    }
+
+    fn ms(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn start_always_emits() {
+        let mut progress = DownloadProgress::new(INTERVAL);
+        assert_eq!(progress.start(1000, Instant::now()), info(Some(1000), 0));
+    }
+
+    #[test]
+    fn throttles_to_the_interval() {
+        let t0 = Instant::now();
+        let mut progress = DownloadProgress::new(INTERVAL);
+        progress.start(1000, t0);
+
+        assert_eq!(progress.receive(100, ms(t0, 100)), None);
+        assert_eq!(progress.receive(100, ms(t0, 249)), None);
+        assert_eq!(
+            progress.receive(100, ms(t0, 250)),
+            Some(info(Some(1000), 300))
+        );
+        // The interval counts from the last event, not from the start.
+        assert_eq!(progress.receive(100, ms(t0, 400)), None);
+        assert_eq!(
+            progress.receive(100, ms(t0, 500)),
+            Some(info(Some(1000), 500))
+        );
+    }
+
+    #[test]
+    fn final_chunk_is_always_emitted() {
+        let t0 = Instant::now();
+        let mut progress = DownloadProgress::new(INTERVAL);
+        progress.start(1000, t0);
+
+        assert_eq!(progress.receive(900, ms(t0, 10)), None);
+        assert_eq!(
+            progress.receive(100, ms(t0, 20)),
+            Some(info(Some(1000), 1000))
+        );
+        // Bytes past the expected length are only reported when the interval passed.
+        assert_eq!(progress.receive(10, ms(t0, 30)), None);
+    }
+
+    #[test]
+    fn zero_interval_emits_every_chunk() {
+        let t0 = Instant::now();
+        let mut progress = DownloadProgress::new(Duration::ZERO);
+        progress.start(0, t0);
+        assert_eq!(progress.receive(1, t0), Some(info(None, 1)));
+        assert_eq!(progress.receive(1, t0), Some(info(None, 2)));
+    }
+
+    #[test]
+    fn falls_back_to_enclosure_length() {
+        let t0 = Instant::now();
+        let mut progress = DownloadProgress::new(INTERVAL);
+        progress.set_fallback_length(500);
+
+        assert_eq!(progress.start(0, t0), info(Some(500), 0));
+        assert_eq!(
+            progress.receive(500, ms(t0, 10)),
+            Some(info(Some(500), 500))
+        );
+    }
+
+    #[test]
+    fn server_length_wins_over_enclosure_length() {
+        let mut progress = DownloadProgress::new(INTERVAL);
+        progress.set_fallback_length(500);
+        assert_eq!(progress.start(800, Instant::now()), info(Some(800), 0));
+    }
+
+    #[test]
+    fn unknown_length_without_either() {
+        let t0 = Instant::now();
+        let mut progress = DownloadProgress::new(INTERVAL);
+        progress.set_fallback_length(0);
+
+        assert_eq!(progress.start(0, t0), info(None, 0));
+        // Nothing is known to be complete, so only the interval applies.
+        assert_eq!(progress.receive(100, ms(t0, 10)), None);
+        assert_eq!(progress.receive(100, ms(t0, 300)), Some(info(None, 200)));
+    }
+
+    #[test]
+    fn restart_resets_received_bytes() {
+        let t0 = Instant::now();
+        let mut progress = DownloadProgress::new(INTERVAL);
+        progress.start(1000, t0);
+        progress.receive(400, ms(t0, 300));
+
+        assert_eq!(progress.start(1000, ms(t0, 400)), info(Some(1000), 0));
+    }
+
+    #[test]
+    fn reset_forgets_fallback_length() {
+        let mut progress = DownloadProgress::new(INTERVAL);
+        progress.set_fallback_length(500);
+        progress.reset();
+        assert_eq!(progress.start(0, Instant::now()), info(None, 0));
+    }
+}
@@ -0,0 +1,293 @@
+//! Ordering of Tauri's exit with an update Sparkle installs on quit.
+//!
+//! When the app is about to exit with an update staged, the first exit request is held
+//! back and `sparkle://before-install-quit` is emitted. The save window ends when the
+//! webview is ready or its timeout runs out. The exit goes ahead once the save window has
+//! ended and no install blockers are held; the timeout does not cut blockers short.
+//! Without a save window, the exit is only held while install blockers are held.
+
+use std::time::Duration;
+
+/// Time the webview has to save its state before the save window ends anyway.
+pub const DEFAULT_QUIT_SAVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What to do with an exit request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitDecision {
+    /// Let the exit happen.
+    Proceed,
+    /// Prevent the exit for now. `notify` is set for the request that started the save
+    /// window, so the webview is told only once.
+    Hold { notify: bool },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Phase {
+    #[default]
+    Idle,
+    /// An exit request is held. Holds its code and whether the save window is still open
+    /// or, after it, install blockers are still held.
+    Holding {
+        code: Option<i32>,
+        saving: bool,
+        blocked: bool,
+    },
+    /// The exit was let through and must not be held again.
+    Exiting,
+}
+
+#[derive(Debug)]
+pub struct QuitCoordinator {
+    phase: Phase,
+    save_window: bool,
+}
+
+impl QuitCoordinator {
+    /// `save_window` is whether the webview gets time to save before the app exits.
+    pub fn new(save_window: bool) -> Self {
+        Self {
+            phase: Phase::Idle,
+            save_window,
+        }
+    }
+
+    /// Decides an exit request. `install_pending` is whether an update is staged for
+    /// install on quit, `blocked` whether install blockers are held.
+    pub fn exit_requested(
+        &mut self,
+        code: Option<i32>,
+        install_pending: bool,
+        blocked: bool,
+    ) -> ExitDecision {
+        match self.phase {
+            Phase::Exiting => ExitDecision::Proceed,
+            Phase::Holding { .. } => ExitDecision::Hold { notify: false },
+            Phase::Idle if install_pending && (self.save_window || blocked) => {
+                // With a save window, blockers are checked when it ends, so the ones the
+                // webview takes while saving count as well.
+                self.phase = Phase::Holding {
+                    code,
+                    saving: self.save_window,
+                    blocked: !self.save_window,
+                };
+                ExitDecision::Hold { notify: true }
+            }
+            Phase::Idle => ExitDecision::Proceed,
+        }
+    }
+
+    pub fn is_saving(&self) -> bool {
+        matches!(self.phase, Phase::Holding { saving: true, .. })
+    }
+
+    /// Whether the save window has ended and the exit waits for install blockers.
+    pub fn is_waiting_for_blockers(&self) -> bool {
+        matches!(
+            self.phase,
+            Phase::Holding {
+                saving: false,
+                blocked: true,
+                ..
+            }
+        )
+    }
+
+    /// Ends the save window, because the webview is ready or the timeout ran out.
+    /// `blocked` is whether install blockers are held. Returns the exit code to exit with,
+    /// or `None` while blockers hold the exit or when no exit is held, e.g. because the
+    /// save window already ended.
+    pub fn save_finished(&mut self, blocked: bool) -> Option<i32> {
+        match self.phase {
+            Phase::Holding {
+                code, saving: true, ..
+            } => {
+                self.phase = Phase::Holding {
+                    code,
+                    saving: false,
+                    blocked,
+                };
+                self.try_exit()
+            }
+            _ => None,
+        }
+    }
+
+    /// Records that the last install blocker was released. Returns the exit code to exit
+    /// with once the save window has ended as well.
+    pub fn unblocked(&mut self) -> Option<i32> {
+        match self.phase {
+            Phase::Holding {
+                code,
+                saving,
+                blocked: true,
+            } => {
+                self.phase = Phase::Holding {
+                    code,
+                    saving,
+                    blocked: false,
+                };
+                self.try_exit()
+            }
+            _ => None,
+        }
+    }
+
+    fn try_exit(&mut self) -> Option<i32> {
+        match self.phase {
+            Phase::Holding {
+                code,
+                saving: false,
+                blocked: false,
+            } => {
+                self.phase = Phase::Exiting;
+                Some(code.unwrap_or(0))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proceeds_without_staged_update() {
+        let mut quit = QuitCoordinator::new(true);
+        assert_eq!(
+            quit.exit_requested(Some(0), false, false),
+            ExitDecision::Proceed
+        );
+        assert!(!quit.is_saving());
+    }
+
+    #[test]
+    fn exits_after_save() {
+        let mut quit = QuitCoordinator::new(true);
+        assert_eq!(
+            quit.exit_requested(Some(3), true, false),
+            ExitDecision::Hold { notify: true }
+        );
+        assert!(quit.is_saving());
+
+        assert_eq!(quit.save_finished(false), Some(3));
+        assert!(!quit.is_saving());
+        assert_eq!(
+            quit.exit_requested(Some(3), true, false),
+            ExitDecision::Proceed
+        );
+    }
+
+    #[test]
+    fn exits_with_code_0_without_one() {
+        let mut quit = QuitCoordinator::new(true);
+        quit.exit_requested(None, true, false);
+        assert_eq!(quit.save_finished(false), Some(0));
+    }
+
+    #[test]
+    fn timeout_after_save_does_nothing() {
+        let mut quit = QuitCoordinator::new(true);
+        quit.exit_requested(Some(0), true, false);
+        assert_eq!(quit.save_finished(false), Some(0));
+
+        // The timer still fires after the webview was ready.
+        assert_eq!(quit.save_finished(false), None);
+        assert_eq!(quit.unblocked(), None);
+    }
+
+    #[test]
+    fn timeout_does_not_cut_blockers_short() {
+        let mut quit = QuitCoordinator::new(true);
+        quit.exit_requested(Some(1), true, false);
+
+        // The save window times out while a blocker is held.
+        assert_eq!(quit.save_finished(true), None);
+        assert!(!quit.is_saving());
+        assert!(quit.is_waiting_for_blockers());
+        assert_eq!(
+            quit.exit_requested(Some(1), true, false),
+            ExitDecision::Hold { notify: false }
+        );
+
+        // A late readyToQuit does not end the wait either.
+        assert_eq!(quit.save_finished(false), None);
+        assert_eq!(quit.unblocked(), Some(1));
+        assert!(!quit.is_waiting_for_blockers());
+    }
+
+    #[test]
+    fn release_during_save_window_waits_for_save() {
+        let mut quit = QuitCoordinator::new(true);
+        quit.exit_requested(Some(0), true, false);
+
+        assert_eq!(quit.unblocked(), None);
+        assert!(quit.is_saving());
+        assert_eq!(quit.save_finished(false), Some(0));
+    }
+
+    #[test]
+    fn second_exit_request_is_held_without_notifying() {
+        let mut quit = QuitCoordinator::new(true);
+        assert_eq!(
+            quit.exit_requested(Some(0), true, false),
+            ExitDecision::Hold { notify: true }
+        );
+        assert_eq!(
+            quit.exit_requested(Some(2), true, false),
+            ExitDecision::Hold { notify: false }
+        );
+        assert_eq!(
+            quit.exit_requested(Some(2), false, false),
+            ExitDecision::Hold { notify: false }
+        );
+
+        // The code of the request that started the save window wins.
+        assert_eq!(quit.save_finished(false), Some(0));
+    }
+
+    #[test]
+    fn without_save_window_holds_only_while_blocked() {
+        let mut quit = QuitCoordinator::new(false);
+        assert_eq!(
+            quit.exit_requested(Some(0), true, false),
+            ExitDecision::Proceed
+        );
+
+        let mut quit = QuitCoordinator::new(false);
+        assert_eq!(
+            quit.exit_requested(Some(4), true, true),
+            ExitDecision::Hold { notify: true }
+        );
+        assert!(!quit.is_saving());
+        assert!(quit.is_waiting_for_blockers());
+        assert_eq!(quit.save_finished(false), None);
+        assert_eq!(quit.unblocked(), Some(4));
+    }
+
+    #[test]
+    fn blockers_at_exit_request_are_checked_after_save() {
+        let mut quit = QuitCoordinator::new(true);
+        quit.exit_requested(Some(0), true, true);
+        assert!(!quit.is_waiting_for_blockers());
+        assert_eq!(quit.save_finished(false), Some(0));
+    }
+
+    #[test]
+    fn exit_after_finishing_is_not_held_again() {
+        let mut quit = QuitCoordinator::new(true);
+        quit.exit_requested(Some(0), true, false);
+        quit.save_finished(false);
+
+        // `AppHandle::exit` raises ExitRequested again, which must go through.
+        assert_eq!(
+            quit.exit_requested(Some(0), true, false),
+            ExitDecision::Proceed
+        );
+        assert_eq!(
+            quit.exit_requested(None, true, false),
+            ExitDecision::Proceed
+        );
+        assert_eq!(quit.save_finished(false), None);
+    }
+}
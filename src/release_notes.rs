@@ -0,0 +1,626 @@
+//! Release notes rendered to sanitized HTML.
+//!
+//! Notes come inline from the appcast item or from `sparkle:releaseNotesLink`, as HTML,
+//! markdown or plain text. Whatever the source, they end up as HTML cleaned against an
+//! allowlist of tags, attributes and URL schemes, safe to insert into the webview.
+//!
+//! [`ReleaseNotesService::cumulative`] collects the notes of every release an update
+//! skips, so a user jumping several versions sees everything they missed.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use ammonia::UrlRelative;
+use log::warn;
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::appcast::AppcastRelease;
+use crate::events::UpdateInfo;
+use crate::fetch::Fetcher;
+use crate::version::VersionComparator;
+use crate::{Error, Result};
+
+/// URL schemes links and images in release notes may use.
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// How release notes are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReleaseNotesFormat {
+    Html,
+    Markdown,
+    PlainText,
+}
+
+impl ReleaseNotesFormat {
+    /// Format of inline notes from the `sparkle:format` attribute of the description,
+    /// which defaults to HTML.
+    pub fn from_description_format(format: Option<&str>) -> Self {
+        match format
+            .map(|format| format.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("markdown") => ReleaseNotesFormat::Markdown,
+            Some("plain-text" | "plaintext" | "text") => ReleaseNotesFormat::PlainText,
+            _ => ReleaseNotesFormat::Html,
+        }
+    }
+
+    /// Format of remote notes from the file extension of their URL, defaulting to HTML.
+    pub fn from_url(url: &str) -> Self {
+        let path = Url::parse(url)
+            .map(|url| url.path().to_ascii_lowercase())
+            .unwrap_or_default();
+        if path.ends_with(".md") || path.ends_with(".markdown") {
+            ReleaseNotesFormat::Markdown
+        } else if path.ends_with(".txt") {
+            ReleaseNotesFormat::PlainText
+        } else {
+            ReleaseNotesFormat::Html
+        }
+    }
+}
+
+/// Release notes of one update as sanitized HTML.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub build_version: String,
+    /// How the notes were written before rendering.
+    pub format: ReleaseNotesFormat,
+    /// URL the notes were downloaded from, when they were not inline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Sanitized HTML. Empty when the update has no notes.
+    pub html: String,
+}
+
+/// Renders notes to HTML and sanitizes it. Relative URLs are resolved against `base`, or
+/// removed without one.
+pub fn render(text: &str, format: ReleaseNotesFormat, base: Option<&Url>) -> String {
+    let html = match format {
+        ReleaseNotesFormat::Html => text.to_string(),
+        ReleaseNotesFormat::Markdown => markdown_to_html(text),
+        ReleaseNotesFormat::PlainText => plain_text_to_html(text),
+    };
+    sanitize(&html, base)
+}
+
+/// Cleans HTML against the allowlist. Scripts, styles, event handlers and URLs with other
+/// schemes than `http`, `https` and `mailto` are removed, and links get
+/// `rel="noopener noreferrer"`.
+pub fn sanitize(html: &str, base: Option<&Url>) -> String {
+    let url_relative = match base {
+        Some(base) => UrlRelative::RewriteWithBase(base.clone()),
+        None => UrlRelative::Deny,
+    };
+    ammonia::Builder::default()
+        .url_schemes(URL_SCHEMES.iter().copied().collect::<HashSet<_>>())
+        .url_relative(url_relative)
+        .link_rel(Some("noopener noreferrer"))
+        .clean(html)
+        .to_string()
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut output = String::new();
+    html::push_html(&mut output, Parser::new_ext(markdown, options));
+    output
+}
+
+/// Blank lines separate paragraphs, other line breaks are kept.
+fn plain_text_to_html(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| format!("<p>{}</p>", escape(paragraph).replace('\n', "<br>")))
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Release notes of every release an update skips.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CumulativeReleaseNotes {
+    /// Build version the update starts from.
+    pub from: String,
+    /// Build version the update ends at.
+    pub to: String,
+    /// Newest first.
+    pub releases: Vec<ReleaseNotes>,
+    /// `releases` merged into one document, one `<section>` with a version heading each.
+    pub html: String,
+}
+
+/// Options of the `cumulative_release_notes` command.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CumulativeNotesOptions {
+    /// Build version to start from. Defaults to the running build.
+    pub current_version: Option<String>,
+    /// Build version to end at. Defaults to the newest release the appcast offers.
+    pub target_version: Option<String>,
+    /// Appcast to read the releases from.
+    pub appcast_xml: Option<String>,
+    /// URL to download the appcast from when `appcast_xml` is not set. Without either, the
+    /// appcast Sparkle loaded last is used.
+    pub appcast_url: Option<String>,
+    /// Whether to include the notes of the target release itself.
+    #[serde(default)]
+    pub include_target: bool,
+}
+
+/// Whether a release is on the default channel or one of `channels`.
+fn on_channels(release: &AppcastRelease, channels: &[String]) -> bool {
+    match &release.channel {
+        Some(channel) => channels.contains(channel),
+        None => true,
+    }
+}
+
+/// The newest release on the default channel or one of `channels`.
+pub fn latest_release<'a>(
+    comparator: &dyn VersionComparator,
+    releases: &'a [AppcastRelease],
+    channels: &[String],
+) -> Option<&'a AppcastRelease> {
+    releases
+        .iter()
+        .filter(|release| on_channels(release, channels))
+        .max_by(|a, b| comparator.compare(&a.build_version, &b.build_version))
+}
+
+/// Releases on the default channel or one of `channels` newer than `current` and older
+/// than `target`, or up to `target` with `include_target`. Newest first, one per build
+/// version.
+pub fn releases_between<'a>(
+    comparator: &dyn VersionComparator,
+    releases: &'a [AppcastRelease],
+    channels: &[String],
+    current: &str,
+    target: &str,
+    include_target: bool,
+) -> Vec<&'a AppcastRelease> {
+    let mut between: Vec<&AppcastRelease> = releases
+        .iter()
+        .filter(|release| on_channels(release, channels))
+        .filter(|release| comparator.compare(&release.build_version, current) == Ordering::Greater)
+        .filter(
+            |release| match comparator.compare(&release.build_version, target) {
+                Ordering::Less => true,
+                Ordering::Equal => include_target,
+                Ordering::Greater => false,
+            },
+        )
+        .collect();
+    between.sort_by(|a, b| comparator.compare(&b.build_version, &a.build_version));
+    between
+        .dedup_by(|a, b| comparator.compare(&a.build_version, &b.build_version) == Ordering::Equal);
+    between
+}
+
+/// Merges release notes into one document with a heading per version.
+pub fn merge(notes: &[ReleaseNotes]) -> String {
+    notes
+        .iter()
+        .map(|notes| {
+            format!(
+                "<section>\n<h2>{}</h2>\n{}</section>\n",
+                escape(&notes.version),
+                notes.html
+            )
+        })
+        .collect()
+}
+
+/// Fetches and renders release notes, caching them per build version.
+pub struct ReleaseNotesService {
+    fetcher: Arc<dyn Fetcher>,
+    cache: Mutex<HashMap<String, ReleaseNotes>>,
+}
+
+impl ReleaseNotesService {
+    pub fn new(fetcher: Arc<dyn Fetcher>) -> Self {
+        Self {
+            fetcher,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The rendered notes of `update`. Notes at `release_notes_url` are preferred, as in
+    /// Sparkle, falling back to the inline notes when the download fails.
+    pub fn notes(&self, update: &UpdateInfo) -> Result<ReleaseNotes> {
+        self.release_notes(&AppcastRelease::from(update))
+    }
+
+    /// The rendered notes of an appcast release, as with [`notes`](Self::notes).
+    pub fn release_notes(&self, release: &AppcastRelease) -> Result<ReleaseNotes> {
+        if let Some(notes) = self.cache.lock().unwrap().get(&release.build_version) {
+            return Ok(notes.clone());
+        }
+        let notes = self.render(release)?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(release.build_version.clone(), notes.clone());
+        Ok(notes)
+    }
+
+    /// The notes of the releases between `current` and `target`, newest first. `target`
+    /// defaults to the newest release on the default channel or one of `channels`.
+    pub fn cumulative(
+        &self,
+        comparator: &dyn VersionComparator,
+        releases: &[AppcastRelease],
+        channels: &[String],
+        current: &str,
+        target: Option<&str>,
+        include_target: bool,
+    ) -> Result<CumulativeReleaseNotes> {
+        let target = match target {
+            Some(target) => target.to_string(),
+            None => latest_release(comparator, releases, channels)
+                .map(|release| release.build_version.clone())
+                .unwrap_or_else(|| current.to_string()),
+        };
+        let releases = releases_between(
+            comparator,
+            releases,
+            channels,
+            current,
+            &target,
+            include_target,
+        )
+        .into_iter()
+        .map(|release| self.release_notes(release))
+        .collect::<Result<Vec<_>>>()?;
+        Ok(CumulativeReleaseNotes {
+            from: current.to_string(),
+            html: merge(&releases),
+            to: target,
+            releases,
+        })
+    }
+
+    /// Forgets the rendered notes, e.g. after the release notes were edited.
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn render(&self, update: &AppcastRelease) -> Result<ReleaseNotes> {
+        let inline = update
+            .release_notes
+            .as_deref()
+            .filter(|notes| !notes.trim().is_empty());
+        let notes = |format, url: Option<&str>, html| ReleaseNotes {
+            version: update.version.clone(),
+            build_version: update.build_version.clone(),
+            format,
+            url: url.map(str::to_string),
+            html,
+        };
+
+        if let Some(url) = &update.release_notes_url {
+            match self.fetch(url) {
+                Ok((text, base)) => {
+                    let format = ReleaseNotesFormat::from_url(url);
+                    return Ok(notes(format, Some(url), render(&text, format, Some(&base))));
+                }
+                Err(e) if inline.is_some() => {
+                    warn!(
+                        "Failed to download the release notes, using the inline ones: {}",
+                        e
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let format =
+            ReleaseNotesFormat::from_description_format(update.item_description_format.as_deref());
+        let html = inline
+            .map(|text| render(text, format, None))
+            .unwrap_or_default();
+        Ok(notes(format, None, html))
+    }
+
+    fn fetch(&self, url: &str) -> Result<(String, Url)> {
+        let base =
+            Url::parse(url).map_err(|_| Error::FetchFailed(format!("{}: invalid URL", url)))?;
+        let body = self.fetcher.fetch(url)?;
+        Ok((String::from_utf8_lossy(&body).into_owned(), base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::StandardVersionComparator;
+
+    fn base() -> Url {
+        Url::parse("https://example.com/notes/2.0.html").unwrap()
+    }
+
+    #[test]
+    fn strips_scripts_and_event_handlers() {
+        let html = sanitize(
+            concat!(
+                r#"<p onclick="steal()">Fixes<script>alert(1)</script></p>"#,
+                r#"<img src="https://example.com/a.png" onerror="steal()"><style>p{}</style>"#,
+            ),
+            None,
+        );
+        assert!(!html.contains("script"));
+        assert!(!html.contains("alert"));
+        assert!(!html.contains("onclick"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("style"));
+        assert!(html.contains("<p>Fixes</p>"));
+        assert!(html.contains(r#"<img src="https://example.com/a.png">"#));
+    }
+
+    #[test]
+    fn removes_javascript_urls() {
+        let html = sanitize(
+            r#"<a href="javascript:alert(1)">Click</a><a href="JavaScript:alert(1)">Too</a>"#,
+            Some(&base()),
+        );
+        assert!(!html.to_ascii_lowercase().contains("javascript"));
+        assert!(html.contains("Click"));
+    }
+
+    #[test]
+    fn keeps_allowed_schemes() {
+        let html = sanitize(
+            concat!(
+                r#"<a href="https://example.com">Site</a>"#,
+                r#"<a href="mailto:help@example.com">Mail</a><a href="ftp://example.com">FTP</a>"#,
+            ),
+            None,
+        );
+        assert!(html.contains(r#"href="https://example.com""#));
+        assert!(html.contains(r#"href="mailto:help@example.com""#));
+        assert!(!html.contains("ftp:"));
+        assert!(html.contains(r#"rel="noopener noreferrer""#));
+    }
+
+    #[test]
+    fn rewrites_relative_urls_against_base() {
+        let html = sanitize(
+            r#"<a href="changes.html">More</a><img src="/img/shot.png">"#,
+            Some(&base()),
+        );
+        assert!(html.contains(r#"href="https://example.com/notes/changes.html""#));
+        assert!(html.contains(r#"src="https://example.com/img/shot.png""#));
+    }
+
+    #[test]
+    fn denies_relative_urls_without_base() {
+        let html = sanitize(
+            r#"<a href="changes.html">More</a><img src="/img/shot.png">"#,
+            None,
+        );
+        assert!(!html.contains("changes.html"));
+        assert!(!html.contains("shot.png"));
+        assert!(html.contains("More"));
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let html = render(
+            "# 2.0\n\n- **Faster** sync\n- [Docs](docs.html)\n\n<script>alert(1)</script>",
+            ReleaseNotesFormat::Markdown,
+            Some(&base()),
+        );
+        assert!(html.contains("<h1>2.0</h1>"));
+        assert!(html.contains("<li><strong>Faster</strong> sync</li>"));
+        assert!(html.contains(r#"href="https://example.com/notes/docs.html""#));
+        assert!(!html.contains("script"));
+    }
+
+    #[test]
+    fn renders_plain_text() {
+        let html = render(
+            "Fixes <crashes> & hangs\r\nin sync.\r\n\r\n\r\nSecond paragraph\n",
+            ReleaseNotesFormat::PlainText,
+            None,
+        );
+        assert_eq!(
+            html,
+            "<p>Fixes &lt;crashes&gt; &amp; hangs<br>in sync.</p><p>Second paragraph</p>"
+        );
+    }
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(
+            ReleaseNotesFormat::from_description_format(Some(" Markdown ")),
+            ReleaseNotesFormat::Markdown
+        );
+        assert_eq!(
+            ReleaseNotesFormat::from_description_format(Some("plain-text")),
+            ReleaseNotesFormat::PlainText
+        );
+        assert_eq!(
+            ReleaseNotesFormat::from_description_format(None),
+            ReleaseNotesFormat::Html
+        );
+        assert_eq!(
+            ReleaseNotesFormat::from_url("https://example.com/notes/2.0.MD?lang=en"),
+            ReleaseNotesFormat::Markdown
+        );
+        assert_eq!(
+            ReleaseNotesFormat::from_url("https://example.com/notes.txt"),
+            ReleaseNotesFormat::PlainText
+        );
+        assert_eq!(
+            ReleaseNotesFormat::from_url("https://example.com/notes"),
+            ReleaseNotesFormat::Html
+        );
+    }
+
+    fn release(build_version: &str, channel: Option<&str>) -> AppcastRelease {
+        AppcastRelease {
+            version: format!("v{}", build_version),
+            build_version: build_version.to_string(),
+            channel: channel.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn releases() -> Vec<AppcastRelease> {
+        vec![
+            release("100", None),
+            release("103", None),
+            release("101", None),
+            release("102", Some("beta")),
+            release("104", None),
+            release("103", None),
+            release("105", Some("beta")),
+        ]
+    }
+
+    fn build_versions(releases: &[&AppcastRelease]) -> Vec<String> {
+        releases
+            .iter()
+            .map(|release| release.build_version.clone())
+            .collect()
+    }
+
+    #[test]
+    fn releases_between_excludes_bounds() {
+        let releases = releases();
+        let between = releases_between(
+            &StandardVersionComparator,
+            &releases,
+            &[],
+            "100",
+            "104",
+            false,
+        );
+        assert_eq!(build_versions(&between), ["103", "101"]);
+    }
+
+    #[test]
+    fn releases_between_includes_target() {
+        let releases = releases();
+        let between = releases_between(
+            &StandardVersionComparator,
+            &releases,
+            &[],
+            "100",
+            "104",
+            true,
+        );
+        assert_eq!(build_versions(&between), ["104", "103", "101"]);
+    }
+
+    #[test]
+    fn releases_between_filters_channels() {
+        let releases = releases();
+        let channels = ["beta".to_string()];
+        let between = releases_between(
+            &StandardVersionComparator,
+            &releases,
+            &channels,
+            "100",
+            "105",
+            true,
+        );
+        assert_eq!(
+            build_versions(&between),
+            ["105", "104", "103", "102", "101"]
+        );
+
+        let channels = ["nightly".to_string()];
+        let between = releases_between(
+            &StandardVersionComparator,
+            &releases,
+            &channels,
+            "100",
+            "105",
+            true,
+        );
+        assert_eq!(build_versions(&between), ["104", "103", "101"]);
+    }
+
+    #[test]
+    fn releases_between_dedups_build_versions() {
+        let releases = releases();
+        let between = releases_between(
+            &StandardVersionComparator,
+            &releases,
+            &[],
+            "102",
+            "103",
+            true,
+        );
+        assert_eq!(between.len(), 1);
+        assert_eq!(between[0].build_version, "103");
+    }
+
+    #[test]
+    fn releases_between_empty_ranges() {
+        let releases = releases();
+        let comparator = StandardVersionComparator;
+        assert!(releases_between(&comparator, &releases, &[], "104", "104", true).is_empty());
+        assert!(releases_between(&comparator, &releases, &[], "104", "101", true).is_empty());
+        assert!(releases_between(&comparator, &[], &[], "100", "104", true).is_empty());
+    }
+
+    #[test]
+    fn latest_release_respects_channels() {
+        let releases = releases();
+        let comparator = StandardVersionComparator;
+        assert_eq!(
+            latest_release(&comparator, &releases, &[]).map(|r| r.build_version.as_str()),
+            Some("104")
+        );
+        assert_eq!(
+            latest_release(&comparator, &releases, &["beta".to_string()])
+                .map(|r| r.build_version.as_str()),
+            Some("105")
+        );
+        assert_eq!(latest_release(&comparator, &[], &[]), None);
+        assert_eq!(
+            latest_release(&comparator, &[release("9", Some("beta"))], &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn merge_adds_escaped_headings() {
+        let notes = ReleaseNotes {
+            version: "2.0 <beta>".to_string(),
+            build_version: "200".to_string(),
+            format: ReleaseNotesFormat::Html,
+            url: None,
+            html: "<p>Faster</p>".to_string(),
+        };
+        assert_eq!(
+            merge(&[notes]),
+            "<section>\n<h2>2.0 &lt;beta&gt;</h2>\n<p>Faster</p></section>\n"
+        );
+    }
+}
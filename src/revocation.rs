@@ -0,0 +1,405 @@
+//! Releases pulled after users already installed them.
+//!
+//! Revoked build versions are listed in a custom appcast element or in a sidecar JSON
+//! document signed with the app's EdDSA update key. When the running build is on the list,
+//! the plugin reports `current-version-revoked` with the release it should move to.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::policy::UpdateCheck;
+use crate::signature::SignedDocumentConfig;
+use crate::version::VersionComparator;
+
+/// Revoked release settings from `revokedReleases` in the plugin configuration. The appcast
+/// element lists revoked build versions, separated by commas or whitespace, e.g.
+/// `myapp:revokedVersions`, and the document is a [`RevokedReleasesDocument`].
+pub type RevokedReleasesConfig = SignedDocumentConfig;
+
+/// A release that must not be run any more.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokedRelease {
+    /// Build version (`CFBundleVersion`) of the release.
+    pub version: String,
+    /// Why the release was pulled, shown to the user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Content of the signed revoked releases document.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokedReleasesDocument {
+    pub revoked: Vec<RevokedRelease>,
+}
+
+/// Build versions listed in an appcast element value.
+pub fn parse_revoked_versions(value: &str) -> Vec<RevokedRelease> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|version| !version.is_empty())
+        .map(|version| RevokedRelease {
+            version: version.to_string(),
+            reason: None,
+        })
+        .collect()
+}
+
+/// Where a revoked release was listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevocationSource {
+    Appcast,
+    Document,
+}
+
+/// A release in the appcast the running build could move to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseCandidate {
+    /// Display version (`sparkle:shortVersionString`).
+    pub version: String,
+    pub build_version: String,
+}
+
+/// The release a revoked build should move to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedRelease {
+    pub version: String,
+    pub build_version: String,
+    /// Whether the release is older than the running build. Sparkle never downgrades, so
+    /// such a release has to be installed by other means.
+    pub downgrade: bool,
+}
+
+/// Payload of `sparkle://current-version-revoked`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentVersionRevoked {
+    pub current_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub source: RevocationSource,
+    /// Highest release in the last loaded appcast that is not revoked. `None` until an
+    /// appcast has been loaded.
+    pub recommended: Option<RecommendedRelease>,
+}
+
+/// Revoked releases as last seen, kept across launches so a revoked build is reported
+/// before the next appcast loads.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokedReleases {
+    #[serde(default)]
+    pub appcast: Vec<RevokedRelease>,
+    #[serde(default)]
+    pub document: Vec<RevokedRelease>,
+}
+
+/// Tracks whether the running build is revoked.
+#[derive(Clone, Debug, Default)]
+pub struct RevocationMonitor {
+    releases: RevokedReleases,
+    candidates: Vec<ReleaseCandidate>,
+    revoked: Option<CurrentVersionRevoked>,
+}
+
+impl RevocationMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restores the lists persisted by an earlier launch.
+    pub fn restore(&mut self, releases: RevokedReleases) {
+        self.releases = releases;
+    }
+
+    /// Replaces the list of one source.
+    pub fn set_revoked(&mut self, source: RevocationSource, revoked: Vec<RevokedRelease>) {
+        match source {
+            RevocationSource::Appcast => self.releases.appcast = revoked,
+            RevocationSource::Document => self.releases.document = revoked,
+        }
+    }
+
+    /// Replaces the releases the running build could move to.
+    pub fn set_candidates(&mut self, candidates: Vec<ReleaseCandidate>) {
+        self.candidates = candidates;
+    }
+
+    /// Compares the lists against the running build. Returns the new state when the build
+    /// became revoked or what is reported about it changed.
+    pub fn evaluate(
+        &mut self,
+        comparator: &dyn VersionComparator,
+        current: &str,
+    ) -> Option<CurrentVersionRevoked> {
+        let listed = [
+            (RevocationSource::Appcast, &self.releases.appcast),
+            (RevocationSource::Document, &self.releases.document),
+        ]
+        .into_iter()
+        .find_map(|(source, releases)| {
+            releases
+                .iter()
+                .find(|release| comparator.compare(&release.version, current) == Ordering::Equal)
+                .map(|release| (source, release))
+        });
+        let Some((source, release)) = listed else {
+            self.revoked = None;
+            return None;
+        };
+
+        let revoked = CurrentVersionRevoked {
+            current_version: current.to_string(),
+            reason: release.reason.clone(),
+            source,
+            recommended: self.recommend(comparator, current),
+        };
+        if self.revoked.as_ref() == Some(&revoked) {
+            return None;
+        }
+        self.revoked = Some(revoked.clone());
+        Some(revoked)
+    }
+
+    fn recommend(
+        &self,
+        comparator: &dyn VersionComparator,
+        current: &str,
+    ) -> Option<RecommendedRelease> {
+        self.candidates
+            .iter()
+            .filter(|candidate| !self.is_revoked(comparator, &candidate.build_version))
+            .filter(|candidate| {
+                comparator.compare(&candidate.build_version, current) != Ordering::Equal
+            })
+            .max_by(|a, b| comparator.compare(&a.build_version, &b.build_version))
+            .map(|candidate| RecommendedRelease {
+                version: candidate.version.clone(),
+                build_version: candidate.build_version.clone(),
+                downgrade: comparator.compare(&candidate.build_version, current) == Ordering::Less,
+            })
+    }
+
+    fn is_revoked(&self, comparator: &dyn VersionComparator, version: &str) -> bool {
+        self.releases
+            .appcast
+            .iter()
+            .chain(&self.releases.document)
+            .any(|release| comparator.compare(&release.version, version) == Ordering::Equal)
+    }
+
+    pub fn revoked(&self) -> Option<&CurrentVersionRevoked> {
+        self.revoked.as_ref()
+    }
+
+    /// Whether an update check of kind `check` should be made user-initiated, so the user
+    /// sees the update. Only background checks are, while the running build is revoked,
+    /// and not when the only way out is a downgrade Sparkle cannot offer.
+    pub fn should_escalate(&self, check: UpdateCheck) -> bool {
+        if check != UpdateCheck::Background {
+            return false;
+        }
+        match &self.revoked {
+            Some(revoked) => !revoked
+                .recommended
+                .as_ref()
+                .is_some_and(|recommended| recommended.downgrade),
+            None => false,
+        }
+    }
+
+    /// The lists to persist for the next launch.
+    pub fn releases(&self) -> &RevokedReleases {
+        &self.releases
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::verify_document;
+    use crate::test_support::{public_key, sign};
+    use crate::version::StandardVersionComparator;
+    use crate::Error;
+
+    fn revoked(version: &str, reason: Option<&str>) -> RevokedRelease {
+        RevokedRelease {
+            version: version.to_string(),
+            reason: reason.map(str::to_string),
+        }
+    }
+
+    fn candidate(build_version: &str) -> ReleaseCandidate {
+        ReleaseCandidate {
+            version: format!("v{}", build_version),
+            build_version: build_version.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_appcast_element_values() {
+        assert_eq!(
+            parse_revoked_versions(" 101, 102\n103,,104 "),
+            ["101", "102", "103", "104"].map(|version| revoked(version, None))
+        );
+        assert!(parse_revoked_versions(" , \n").is_empty());
+    }
+
+    #[test]
+    fn verifies_signed_document() {
+        let document = br#"{"revoked":[{"version":"101","reason":"Data loss"},{"version":"102"}]}"#;
+        let parsed =
+            verify_document::<RevokedReleasesDocument>(document, &sign(document), &public_key());
+        assert_eq!(
+            parsed.unwrap().revoked,
+            [revoked("101", Some("Data loss")), revoked("102", None)]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_document() {
+        for document in [
+            &b"101,102"[..],
+            br#"{"revoked":"101"}"#,
+            br#"{"revoked":[{"reason":"no version"}]}"#,
+            br#"{}"#,
+        ] {
+            let result = verify_document::<RevokedReleasesDocument>(
+                document,
+                &sign(document),
+                &public_key(),
+            );
+            assert!(matches!(result, Err(Error::Json(_))), "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn rejects_unsigned_document() {
+        let document = br#"{"revoked":[]}"#;
+        assert!(matches!(
+            verify_document::<RevokedReleasesDocument>(document, "", &public_key()),
+            Err(Error::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn reports_revoked_build() {
+        let comparator = StandardVersionComparator;
+        let mut monitor = RevocationMonitor::new();
+        monitor.set_revoked(
+            RevocationSource::Document,
+            vec![revoked("101", Some("Data loss"))],
+        );
+        monitor.set_candidates(vec![candidate("100"), candidate("101"), candidate("102")]);
+
+        let reported = monitor.evaluate(&comparator, "101").unwrap();
+        assert_eq!(
+            reported,
+            CurrentVersionRevoked {
+                current_version: "101".to_string(),
+                reason: Some("Data loss".to_string()),
+                source: RevocationSource::Document,
+                recommended: Some(RecommendedRelease {
+                    version: "v102".to_string(),
+                    build_version: "102".to_string(),
+                    downgrade: false,
+                }),
+            }
+        );
+        assert_eq!(monitor.revoked(), Some(&reported));
+
+        // Nothing changed, so nothing is reported again.
+        assert_eq!(monitor.evaluate(&comparator, "101"), None);
+        assert!(monitor.revoked().is_some());
+    }
+
+    #[test]
+    fn appcast_listing_wins_over_document() {
+        let mut monitor = RevocationMonitor::new();
+        monitor.set_revoked(
+            RevocationSource::Document,
+            vec![revoked("101", Some("doc"))],
+        );
+        monitor.set_revoked(RevocationSource::Appcast, vec![revoked("101", None)]);
+
+        let reported = monitor.evaluate(&StandardVersionComparator, "101").unwrap();
+        assert_eq!(reported.source, RevocationSource::Appcast);
+        assert_eq!(reported.reason, None);
+    }
+
+    #[test]
+    fn ignores_build_that_is_not_revoked() {
+        let comparator = StandardVersionComparator;
+        let mut monitor = RevocationMonitor::new();
+        monitor.set_revoked(RevocationSource::Appcast, parse_revoked_versions("101 103"));
+
+        assert_eq!(monitor.evaluate(&comparator, "102"), None);
+        assert_eq!(monitor.revoked(), None);
+        assert!(!monitor.should_escalate(UpdateCheck::Background));
+    }
+
+    #[test]
+    fn lifting_revocation_clears_state() {
+        let comparator = StandardVersionComparator;
+        let mut monitor = RevocationMonitor::new();
+        monitor.restore(RevokedReleases {
+            appcast: vec![revoked("101", None)],
+            document: Vec::new(),
+        });
+        assert!(monitor.evaluate(&comparator, "101").is_some());
+
+        monitor.set_revoked(RevocationSource::Appcast, Vec::new());
+        assert_eq!(monitor.evaluate(&comparator, "101"), None);
+        assert_eq!(monitor.revoked(), None);
+        assert!(monitor.releases().appcast.is_empty());
+    }
+
+    #[test]
+    fn recommends_highest_release_that_is_not_revoked() {
+        let mut monitor = RevocationMonitor::new();
+        monitor.set_revoked(RevocationSource::Appcast, parse_revoked_versions("101 102"));
+        monitor.set_candidates(vec![candidate("100"), candidate("101"), candidate("102")]);
+
+        let reported = monitor.evaluate(&StandardVersionComparator, "101").unwrap();
+        let recommended = reported.recommended.unwrap();
+        assert_eq!(recommended.build_version, "100");
+        assert!(recommended.downgrade);
+    }
+
+    #[test]
+    fn escalates_background_checks_of_revoked_build() {
+        let mut monitor = RevocationMonitor::new();
+        monitor.set_revoked(RevocationSource::Appcast, parse_revoked_versions("101"));
+        monitor.set_candidates(vec![candidate("102")]);
+        monitor.evaluate(&StandardVersionComparator, "101");
+
+        assert!(monitor.should_escalate(UpdateCheck::Background));
+        assert!(!monitor.should_escalate(UpdateCheck::UserInitiated));
+        assert!(!monitor.should_escalate(UpdateCheck::Information));
+    }
+
+    #[test]
+    fn escalates_without_loaded_appcast() {
+        let mut monitor = RevocationMonitor::new();
+        monitor.set_revoked(RevocationSource::Appcast, parse_revoked_versions("101"));
+        monitor.evaluate(&StandardVersionComparator, "101");
+
+        assert!(monitor.should_escalate(UpdateCheck::Background));
+    }
+
+    #[test]
+    fn does_not_escalate_when_only_a_downgrade_helps() {
+        let mut monitor = RevocationMonitor::new();
+        monitor.set_revoked(RevocationSource::Appcast, parse_revoked_versions("101"));
+        monitor.set_candidates(vec![candidate("100"), candidate("101")]);
+        monitor.evaluate(&StandardVersionComparator, "101");
+
+        assert!(monitor.revoked().is_some());
+        assert!(!monitor.should_escalate(UpdateCheck::Background));
+    }
+}
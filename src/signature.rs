@@ -0,0 +1,315 @@
+//! Verification of documents signed with the app's EdDSA update key.
+//!
+//! Signatures are the base64 Ed25519 signatures Sparkle's `sign_update` tool prints, made
+//! over the raw bytes of the document. The public key is the one in `SUPublicEDKey`.
+//! [`SignedDocumentConfig`] holds the settings `minimumVersion` and `revokedReleases`
+//! share, and [`SignedDocumentSource`] downloads and verifies their JSON documents.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+use crate::fetch::Fetcher;
+use crate::{Error, Result};
+
+/// Settings of values declared in an appcast element or in a signed JSON document, such as
+/// `minimumVersion` and `revokedReleases` in the plugin configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SignedDocumentConfig {
+    /// Qualified name of the appcast item element holding the values, e.g.
+    /// `myapp:minimumSupportedVersion`.
+    pub appcast_element: Option<String>,
+    /// URL of the signed JSON document.
+    pub url: Option<String>,
+    /// URL of the document's signature. Defaults to `url` with `.sig` appended.
+    pub signature_url: Option<String>,
+    /// Base64 Ed25519 public key the document is signed with. Defaults to `SUPublicEDKey`.
+    pub public_key: Option<String>,
+}
+
+impl SignedDocumentConfig {
+    /// `setting` names the configuration section in errors.
+    pub fn validate(&self, setting: &str) -> Result<()> {
+        let invalid = |message: &str| Error::InvalidConfig(format!("{} {}", setting, message));
+        if self.appcast_element.is_none() && self.url.is_none() {
+            return Err(invalid("needs appcastElement or url"));
+        }
+        if self.appcast_element.as_deref() == Some("") {
+            return Err(invalid("appcastElement must not be empty"));
+        }
+        for url in [&self.url, &self.signature_url].into_iter().flatten() {
+            Url::parse(url).map_err(|_| invalid(&format!("{} is not a valid URL", url)))?;
+        }
+        if self.signature_url.is_some() && self.url.is_none() {
+            return Err(invalid("signatureUrl needs url"));
+        }
+        Ok(())
+    }
+
+    /// The signature URL of the document, if a document is configured.
+    pub fn signature_url(&self) -> Option<String> {
+        let url = self.url.as_ref()?;
+        Some(
+            self.signature_url
+                .clone()
+                .unwrap_or_else(|| format!("{}.sig", url)),
+        )
+    }
+}
+
+/// Where a signed document is downloaded from.
+#[derive(Clone, Debug)]
+pub struct SignedDocumentSource {
+    url: String,
+    signature_url: String,
+    public_key: String,
+}
+
+impl SignedDocumentSource {
+    /// Returns `None` when `config` has no document. `setting` names the configuration
+    /// section in errors, and `default_public_key` is asked when `config` has no key.
+    pub fn new(
+        setting: &str,
+        config: &SignedDocumentConfig,
+        default_public_key: impl FnOnce() -> Option<String>,
+    ) -> Result<Option<Self>> {
+        let (Some(url), Some(signature_url)) = (&config.url, config.signature_url()) else {
+            return Ok(None);
+        };
+        let public_key = config
+            .public_key
+            .clone()
+            .or_else(default_public_key)
+            .ok_or_else(|| {
+                Error::InvalidConfig(format!(
+                    "{0}.url needs {0}.publicKey or SUPublicEDKey",
+                    setting
+                ))
+            })?;
+        Ok(Some(Self {
+            url: url.clone(),
+            signature_url,
+            public_key,
+        }))
+    }
+
+    /// Downloads the document and its signature and verifies them.
+    pub fn fetch<T: DeserializeOwned>(&self, fetcher: &dyn Fetcher) -> Result<T> {
+        let document = fetcher.fetch(&self.url)?;
+        let signature = fetcher.fetch(&self.signature_url)?;
+        verify_document(
+            &document,
+            &String::from_utf8_lossy(&signature),
+            &self.public_key,
+        )
+    }
+}
+
+/// Parses a JSON document after checking its signature.
+pub fn verify_document<T: DeserializeOwned>(
+    document: &[u8],
+    signature: &str,
+    public_key: &str,
+) -> Result<T> {
+    verify_ed25519(document, signature, public_key)?;
+    Ok(serde_json::from_slice(document)?)
+}
+
+/// Checks `signature` over `data` against the base64 Ed25519 `public_key`.
+///
+/// `signature` may be the bare base64 signature or `sign_update`'s full output, such as
+/// `sparkle:edSignature="..." length="..."`.
+pub fn verify_ed25519(data: &[u8], signature: &str, public_key: &str) -> Result<()> {
+    let key: [u8; 32] = decode(public_key)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("the public key is not a base64 Ed25519 key"))?;
+    let key = VerifyingKey::from_bytes(&key).map_err(|_| invalid("the public key is not valid"))?;
+    let signature: [u8; 64] = decode(extract_signature(signature))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("the signature is not a base64 Ed25519 signature"))?;
+    key.verify(data, &Signature::from_bytes(&signature))
+        .map_err(|_| invalid("the signature does not match"))
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    STANDARD.decode(text.trim()).ok()
+}
+
+fn extract_signature(text: &str) -> &str {
+    const ATTRIBUTE: &str = "edSignature=\"";
+    match text.find(ATTRIBUTE) {
+        Some(start) => {
+            let rest = &text[start + ATTRIBUTE.len()..];
+            rest.split('"').next().unwrap_or(rest)
+        }
+        None => text,
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidSignature(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use super::*;
+    use crate::test_support::{public_key, sign};
+
+    #[test]
+    fn accepts_good_signature() {
+        assert!(verify_ed25519(b"document", &sign(b"document"), &public_key()).is_ok());
+    }
+
+    #[test]
+    fn accepts_sign_update_output() {
+        let output = format!(
+            "sparkle:edSignature=\"{}\" length=\"8\"\n",
+            sign(b"document")
+        );
+        assert!(verify_ed25519(b"document", &output, &public_key()).is_ok());
+    }
+
+    #[test]
+    fn rejects_signature_over_other_data() {
+        assert!(matches!(
+            verify_ed25519(b"tampered", &sign(b"document"), &public_key()),
+            Err(Error::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_signature_by_other_key() {
+        let other = STANDARD.encode(SigningKey::from_bytes(&[8; 32]).verifying_key().as_bytes());
+        assert!(matches!(
+            verify_ed25519(b"document", &sign(b"document"), &other),
+            Err(Error::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for (signature, public_key) in [
+            (String::new(), public_key()),
+            ("not base64!".to_string(), public_key()),
+            (STANDARD.encode([0; 12]), public_key()),
+            (sign(b"document"), String::new()),
+            (sign(b"document"), STANDARD.encode([0; 16])),
+        ] {
+            assert!(matches!(
+                verify_ed25519(b"document", &signature, &public_key),
+                Err(Error::InvalidSignature(_))
+            ));
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Document {
+        value: u32,
+    }
+
+    struct Files(Vec<(&'static str, Vec<u8>)>);
+
+    impl Fetcher for Files {
+        fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+            self.0
+                .iter()
+                .find(|(path, _)| *path == url)
+                .map(|(_, body)| body.clone())
+                .ok_or_else(|| Error::FetchFailed(url.to_string()))
+        }
+    }
+
+    fn config(url: &str) -> SignedDocumentConfig {
+        SignedDocumentConfig {
+            url: Some(url.to_string()),
+            public_key: Some(public_key()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn config_needs_a_source() {
+        assert!(matches!(
+            SignedDocumentConfig::default().validate("minimumVersion"),
+            Err(Error::InvalidConfig(message))
+                if message == "minimumVersion needs appcastElement or url"
+        ));
+        let orphan_signature = SignedDocumentConfig {
+            signature_url: Some("https://example.com/doc.sig".to_string()),
+            appcast_element: Some("myapp:values".to_string()),
+            ..Default::default()
+        };
+        assert!(orphan_signature.validate("revokedReleases").is_err());
+        assert!(config("https://example.com/doc.json").validate("x").is_ok());
+        assert!(config("not a url").validate("x").is_err());
+    }
+
+    #[test]
+    fn signature_url_defaults_to_sig_suffix() {
+        let mut config = config("https://example.com/doc.json");
+        assert_eq!(
+            config.signature_url().as_deref(),
+            Some("https://example.com/doc.json.sig")
+        );
+        config.signature_url = Some("https://example.com/signature".to_string());
+        assert_eq!(
+            config.signature_url().as_deref(),
+            Some("https://example.com/signature")
+        );
+        assert_eq!(SignedDocumentConfig::default().signature_url(), None);
+    }
+
+    #[test]
+    fn source_needs_a_public_key() {
+        let mut config = config("https://example.com/doc.json");
+        config.public_key = None;
+        assert!(matches!(
+            SignedDocumentSource::new("minimumVersion", &config, || None),
+            Err(Error::InvalidConfig(_))
+        ));
+        let source = SignedDocumentSource::new("minimumVersion", &config, || Some(public_key()));
+        assert!(source.unwrap().is_some());
+        let appcast_only = SignedDocumentConfig {
+            appcast_element: Some("myapp:values".to_string()),
+            ..Default::default()
+        };
+        let source = SignedDocumentSource::new("minimumVersion", &appcast_only, || None);
+        assert!(source.unwrap().is_none());
+    }
+
+    #[test]
+    fn fetch_verifies_the_document() {
+        let document = br#"{"value":7}"#.to_vec();
+        let source = SignedDocumentSource::new("x", &config("https://a.test/doc"), || None)
+            .unwrap()
+            .unwrap();
+
+        let signed = Files(vec![
+            ("https://a.test/doc", document.clone()),
+            ("https://a.test/doc.sig", sign(&document).into_bytes()),
+        ]);
+        assert_eq!(
+            source.fetch::<Document>(&signed).unwrap(),
+            Document { value: 7 }
+        );
+
+        let tampered = Files(vec![
+            ("https://a.test/doc", document),
+            ("https://a.test/doc.sig", sign(b"other").into_bytes()),
+        ]);
+        assert!(matches!(
+            source.fetch::<Document>(&tampered),
+            Err(Error::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            source.fetch::<Document>(&Files(Vec::new())),
+            Err(Error::FetchFailed(_))
+        ));
+    }
+}
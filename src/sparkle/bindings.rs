@@ -1,7 +1,9 @@
 use objc2::rc::Retained;
 use objc2::runtime::NSObject;
 use objc2::{extern_class, extern_methods, MainThreadOnly};
-use objc2_foundation::{NSDate, NSDictionary, NSError, NSNumber, NSString, NSURL};
+use objc2_foundation::{
+    NSArray, NSBundle, NSDate, NSDictionary, NSError, NSNumber, NSString, NSURL,
+};
 
 extern_class!(
     #[unsafe(super(NSObject))]
@@ -23,9 +25,6 @@ impl SPUStandardUpdaterController {
 
         #[unsafe(method(updater))]
         pub fn updater(&self) -> Retained<SPUUpdater>;
-
-        #[unsafe(method(checkForUpdates:))]
-        pub fn check_for_updates(&self, sender: Option<&NSObject>);
     );
 }
 
@@ -39,6 +38,15 @@ extern_class!(
 
 impl SPUUpdater {
     extern_methods!(
+        #[unsafe(method(initWithHostBundle:applicationBundle:userDriver:delegate:))]
+        pub fn init_with_host_bundle(
+            this: objc2::rc::Allocated<Self>,
+            host_bundle: &NSBundle,
+            application_bundle: &NSBundle,
+            user_driver: &NSObject,
+            delegate: Option<&NSObject>,
+        ) -> Retained<Self>;
+
         #[unsafe(method(canCheckForUpdates))]
         pub fn can_check_for_updates(&self) -> bool;
 
@@ -141,6 +149,9 @@ impl SPUAppcastItem {
         #[unsafe(method(contentLength))]
         pub fn content_length(&self) -> u64;
 
+        #[unsafe(method(isDeltaUpdate))]
+        pub fn is_delta_update(&self) -> bool;
+
         #[unsafe(method(title))]
         pub fn title(&self) -> Option<Retained<NSString>>;
 
@@ -197,5 +208,42 @@ impl SPUAppcastItem {
 
         #[unsafe(method(itemDescriptionFormat))]
         pub fn item_description_format(&self) -> Option<Retained<NSString>>;
+
+        #[unsafe(method(propertiesDictionary))]
+        pub fn properties_dictionary(&self) -> Retained<NSDictionary<NSString, NSObject>>;
+    );
+}
+
+extern_class!(
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "SPUAppcast"]
+    #[derive(Debug)]
+    pub struct SPUAppcast;
+);
+
+impl SPUAppcast {
+    extern_methods!(
+        #[unsafe(method(items))]
+        pub fn items(&self) -> Retained<NSArray<SPUAppcastItem>>;
+    );
+}
+
+extern_class!(
+    #[unsafe(super(NSObject))]
+    #[name = "SUUpdatePermissionResponse"]
+    #[derive(Debug)]
+    pub struct SUUpdatePermissionResponse;
+);
+
+impl SUUpdatePermissionResponse {
+    extern_methods!(
+        #[unsafe(method(initWithAutomaticUpdateChecks:automaticUpdateDownloading:sendSystemProfile:))]
+        pub fn init_with_automatic_update_checks(
+            this: objc2::rc::Allocated<Self>,
+            automatic_update_checks: bool,
+            automatic_update_downloading: Option<&NSNumber>,
+            send_system_profile: bool,
+        ) -> Retained<Self>;
     );
 }
@@ -0,0 +1,43 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use objc2::rc::Retained;
+use objc2::runtime::NSObject;
+use objc2::{define_class, msg_send, AllocAnyThread, DeclaredClass};
+use objc2_foundation::NSString;
+
+use crate::version::VersionComparator;
+
+pub struct ComparatorIvars {
+    comparator: Arc<dyn VersionComparator>,
+}
+
+define_class!(
+    /// Exposes a [`VersionComparator`] to Sparkle as an `SUVersionComparison` object.
+    #[unsafe(super(NSObject))]
+    #[name = "TauriSparkleVersionComparator"]
+    #[ivars = ComparatorIvars]
+    pub struct SparkleVersionComparator;
+
+    impl SparkleVersionComparator {
+        #[unsafe(method(compareVersion:toVersion:))]
+        fn compare_version(&self, version_a: &NSString, version_b: &NSString) -> isize {
+            let ordering = self
+                .ivars()
+                .comparator
+                .compare(&version_a.to_string(), &version_b.to_string());
+            match ordering {
+                Ordering::Less => -1,
+                Ordering::Equal => 0,
+                Ordering::Greater => 1,
+            }
+        }
+    }
+);
+
+impl SparkleVersionComparator {
+    pub fn new(comparator: Arc<dyn VersionComparator>) -> Retained<Self> {
+        let this = Self::alloc().set_ivars(ComparatorIvars { comparator });
+        unsafe { msg_send![super(this), init] }
+    }
+}
@@ -0,0 +1,39 @@
+use objc2::rc::Retained;
+use objc2::{msg_send, ClassType};
+use objc2_foundation::{NSString, NSUserDefaults};
+
+/// User defaults key holding the highest build version ever installed.
+pub const HIGHEST_INSTALLED_VERSION_KEY: &str = "TauriSparkleHighestInstalledVersion";
+
+/// User defaults key holding the version of the previous launch.
+pub const LAST_LAUNCHED_VERSION_KEY: &str = "TauriSparkleLastLaunchedVersion";
+
+/// User defaults key holding the update Sparkle last started to install.
+pub const INSTALLING_UPDATE_KEY: &str = "TauriSparkleInstallingUpdate";
+
+/// User defaults key holding the update waiting for `mark_update_healthy`.
+pub const UPDATE_VERIFICATION_KEY: &str = "TauriSparkleUpdateVerification";
+
+/// User defaults key holding the revoked releases last seen.
+pub const REVOKED_RELEASES_KEY: &str = "TauriSparkleRevokedReleases";
+
+fn standard() -> Retained<NSUserDefaults> {
+    unsafe { msg_send![NSUserDefaults::class(), standardUserDefaults] }
+}
+
+pub fn string(key: &str) -> Option<String> {
+    let key = NSString::from_str(key);
+    let value: Option<Retained<NSString>> = unsafe { msg_send![&standard(), stringForKey: &*key] };
+    value.map(|value| value.to_string())
+}
+
+pub fn set_string(key: &str, value: &str) {
+    let key = NSString::from_str(key);
+    let value = NSString::from_str(value);
+    let _: () = unsafe { msg_send![&standard(), setObject: &*value, forKey: &*key] };
+}
+
+pub fn remove(key: &str) {
+    let key = NSString::from_str(key);
+    let _: () = unsafe { msg_send![&standard(), removeObjectForKey: &*key] };
+}
@@ -2,43 +2,121 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use log::error;
+use block2::{Block, RcBlock};
+use log::{error, warn};
 use objc2::rc::Retained;
-use objc2::runtime::NSObject;
-use objc2::{define_class, msg_send, DeclaredClass, MainThreadMarker, MainThreadOnly};
-use objc2_foundation::{NSArray, NSDictionary, NSMutableSet, NSNumber, NSSet, NSString, NSURL};
+use objc2::runtime::{AnyObject, NSObject};
+use objc2::{define_class, msg_send, ClassType, DeclaredClass, MainThreadMarker, MainThreadOnly};
+use objc2_foundation::{
+    NSArray, NSDictionary, NSError, NSMutableSet, NSNumber, NSSet, NSString, NSURL,
+};
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-use super::bindings::SPUAppcastItem;
+use super::bindings::{SPUAppcast, SPUAppcastItem};
+use super::comparator::SparkleVersionComparator;
+use super::defaults::{self, INSTALLING_UPDATE_KEY, REVOKED_RELEASES_KEY};
+use crate::appcast::{is_standard_element, AppcastRelease};
 use crate::events::UpdateInfo;
 use crate::events::{
     DownloadFailedInfo, EmptyPayload, ErrorPayload, ScheduleInfo, UpdateCycleInfo, UserChoiceInfo,
     VersionInfo, EVENT_DID_ABORT_WITH_ERROR, EVENT_DID_DOWNLOAD_UPDATE, EVENT_DID_EXTRACT_UPDATE,
     EVENT_DID_FIND_VALID_UPDATE, EVENT_DID_FINISH_LOADING_APPCAST, EVENT_DID_FINISH_UPDATE_CYCLE,
-    EVENT_DID_NOT_FIND_UPDATE, EVENT_FAILED_TO_DOWNLOAD_UPDATE, EVENT_USER_DID_CANCEL_DOWNLOAD,
-    EVENT_USER_DID_MAKE_CHOICE, EVENT_WILL_DOWNLOAD_UPDATE, EVENT_WILL_EXTRACT_UPDATE,
-    EVENT_WILL_INSTALL_UPDATE, EVENT_WILL_INSTALL_UPDATE_ON_QUIT,
-    EVENT_WILL_NOT_SCHEDULE_UPDATE_CHECK, EVENT_WILL_RELAUNCH_APPLICATION,
-    EVENT_WILL_SCHEDULE_UPDATE_CHECK,
+    EVENT_DID_NOT_FIND_UPDATE, EVENT_FAILED_TO_DOWNLOAD_UPDATE, EVENT_PRE_INSTALL_ABORTED,
+    EVENT_UPDATE_REQUIRED, EVENT_USER_DID_CANCEL_DOWNLOAD, EVENT_USER_DID_MAKE_CHOICE,
+    EVENT_WILL_DOWNLOAD_UPDATE, EVENT_WILL_EXTRACT_UPDATE, EVENT_WILL_INSTALL_UPDATE,
+    EVENT_WILL_INSTALL_UPDATE_ON_QUIT, EVENT_WILL_NOT_SCHEDULE_UPDATE_CHECK,
+    EVENT_WILL_RELAUNCH_APPLICATION, EVENT_WILL_SCHEDULE_UPDATE_CHECK,
+};
+use crate::events::{
+    UpdateCheckDeniedInfo, UpdateRejectedInfo, EVENT_CURRENT_VERSION_REVOKED,
+    EVENT_DID_DENY_UPDATE_CHECK, EVENT_DID_REJECT_UPDATE,
+};
+use crate::minimum_version::{
+    highest_minimum, MinimumVersionGate, MinimumVersionSource, UpdateRequiredInfo,
+};
+use crate::policy::{
+    CheckDecision, MayCheckPolicies, MayCheckPolicy, ProceedDecision, ProceedPolicy, UpdateCheck,
+};
+use crate::pre_install::{PreInstallContext, PreInstallHooks, PreInstallReport};
+use crate::revocation::{
+    parse_revoked_versions, CurrentVersionRevoked, ReleaseCandidate, RevocationMonitor,
+    RevocationSource, RevokedRelease, RevokedReleases,
+};
+use crate::store::PersistedSettings;
+use crate::version::{
+    check_candidate_version, newer_version, StandardVersionComparator, VersionComparator,
 };
 
+/// Error domain for errors the plugin hands back to Sparkle.
+const PLUGIN_ERROR_DOMAIN: &str = "TauriSparkleUpdaterErrorDomain";
+
+/// Error code used when a [`ProceedPolicy`] rejects an update.
+const POLICY_REJECTION_CODE: i64 = 3;
+
+/// Error code used when an update check is denied.
+const CHECK_DENIED_CODE: i64 = 4;
+
+/// Error code used when a background check is replaced by a user-initiated one.
+const CHECK_ESCALATED_CODE: i64 = 5;
+
 pub type EventEmitter = Arc<dyn Fn(&str, Value) + Send + Sync>;
 pub type EventCallback = Arc<dyn Fn(&str, &Value) + Send + Sync>;
+/// Hands the report of hooks run off the main thread back to the delegate.
+pub type PreInstallCompletion = Arc<dyn Fn(PreInstallReport) + Send + Sync>;
+
+/// An install Sparkle handed over to the plugin.
+struct PendingInstall {
+    update: UpdateInfo,
+    /// Whether Sparkle staged the update to install on quit, rather than postponing the
+    /// relaunch.
+    on_quit: bool,
+    install: RcBlock<dyn Fn()>,
+}
 
 pub struct DelegateIvars {
     emitter: RefCell<Option<EventEmitter>>,
     event_callback: RefCell<Option<EventCallback>>,
+    event_listeners: RefCell<Vec<EventCallback>>,
     allowed_channels: RefCell<Option<Vec<String>>>,
     feed_url_override: RefCell<Option<String>>,
     feed_parameters: RefCell<Option<HashMap<String, String>>>,
     should_download_release_notes: RefCell<bool>,
     should_relaunch: RefCell<bool>,
+    /// Relaunch behaviour chosen at runtime, which is persisted and wins over the config.
+    relaunch_override: RefCell<Option<bool>>,
+    should_prompt_for_permission: RefCell<bool>,
     may_check_for_updates: RefCell<bool>,
     should_proceed_with_update: RefCell<bool>,
     decryption_password: RefCell<Option<String>>,
     last_found_update: RefCell<Option<UpdateInfo>>,
     download_request_headers: RefCell<Option<HashMap<String, String>>>,
+    installed_version: RefCell<Option<String>>,
+    highest_installed_version: RefCell<Option<String>>,
+    proceed_policy: RefCell<Option<ProceedPolicy>>,
+    may_check_policies: RefCell<MayCheckPolicies>,
+    latest_stable_version: RefCell<Option<String>>,
+    /// Releases of the last loaded appcast, in appcast order.
+    appcast_releases: RefCell<Option<Vec<AppcastRelease>>>,
+    version_comparator: RefCell<Arc<dyn VersionComparator>>,
+    sparkle_comparator: RefCell<Option<Retained<SparkleVersionComparator>>>,
+    /// Installs the update right away: the immediate installation block of an update staged
+    /// on quit, or the handler of a postponed relaunch.
+    pending_install: RefCell<Option<PendingInstall>>,
+    pre_install_hooks: RefCell<PreInstallHooks>,
+    pre_install_completion: RefCell<Option<PreInstallCompletion>>,
+    pre_install_running: RefCell<bool>,
+    /// Build version the pre-install hooks last passed for, so they run once per install.
+    pre_install_passed: RefCell<Option<String>>,
+    /// Appcast item element declaring the minimum supported version.
+    minimum_version_element: RefCell<Option<String>>,
+    minimum_version: RefCell<MinimumVersionGate>,
+    /// Appcast item element listing revoked build versions.
+    revoked_releases_element: RefCell<Option<String>>,
+    revocation: RefCell<RevocationMonitor>,
+    /// Starts a user-initiated check in place of a background one while the running build
+    /// is revoked.
+    check_escalation: RefCell<Option<Arc<dyn Fn()>>>,
 }
 
 define_class!(
@@ -53,8 +131,27 @@ define_class!(
         fn updater_did_finish_loading_appcast(
             &self,
             _updater: &NSObject,
-            _appcast: &NSObject,
+            appcast: &SPUAppcast,
         ) {
+            let comparator = self.version_comparator();
+            let stable = appcast
+                .items()
+                .iter()
+                .filter(|item| item.channel().is_none())
+                .map(|item| item.version_string().to_string())
+                .reduce(|a, b| newer_version(&*comparator, &a, &b).to_string());
+            if stable.is_some() {
+                *self.ivars().latest_stable_version.borrow_mut() = stable;
+            }
+            let releases = appcast
+                .items()
+                .iter()
+                .map(|item| AppcastRelease::from(&update_info_from_item(&item)))
+                .collect();
+            *self.ivars().appcast_releases.borrow_mut() = Some(releases);
+            self.declare_appcast_minimum_version(appcast);
+            self.update_appcast_revocations(appcast);
+
             self.emit(EVENT_DID_FINISH_LOADING_APPCAST, &EmptyPayload {});
         }
 
@@ -64,41 +161,7 @@ define_class!(
             _updater: &NSObject,
             item: &SPUAppcastItem,
         ) {
-            let url_to_string = |url: &NSURL| -> String {
-                let abs: Option<Retained<NSString>> = unsafe { msg_send![url, absoluteString] };
-                abs.map(|s| s.to_string()).unwrap_or_default()
-            };
-
-            let number_to_f64 = |num: &NSNumber| -> f64 {
-                unsafe { msg_send![num, doubleValue] }
-            };
-
-            let update_info = UpdateInfo {
-                version: item.display_version_string().to_string(),
-                release_notes: item.item_description().map(|s| s.to_string()),
-                title: item.title().map(|s| s.to_string()),
-                release_notes_url: item.release_notes_url().map(|u| url_to_string(&u)),
-                info_url: item.info_url().map(|u| url_to_string(&u)),
-                minimum_system_version: item.minimum_system_version().map(|s| s.to_string()),
-                channel: item.channel().map(|s| s.to_string()),
-                date: item.date().map(|d| {
-                    let seconds: f64 = unsafe { msg_send![&d, timeIntervalSince1970] };
-                    seconds * 1000.0
-                }),
-                is_critical: item.is_critical_update(),
-                is_major_upgrade: item.is_major_upgrade(),
-                is_information_only: item.is_information_only_update(),
-                maximum_system_version: item.maximum_system_version().map(|s| s.to_string()),
-                minimum_os_version_ok: item.minimum_operating_system_version_is_ok(),
-                maximum_os_version_ok: item.maximum_operating_system_version_is_ok(),
-                installation_type: item.installation_type().to_string(),
-                phased_rollout_interval: item.phased_rollout_interval().map(|n| number_to_f64(&n)),
-                full_release_notes_url: item.full_release_notes_url().map(|u| url_to_string(&u)),
-                minimum_autoupdate_version: item.minimum_autoupdate_version().map(|s| s.to_string()),
-                ignore_skipped_upgrades_below_version: item.ignore_skipped_upgrades_below_version().map(|s| s.to_string()),
-                date_string: item.date_string().map(|s| s.to_string()),
-                item_description_format: item.item_description_format().map(|s| s.to_string()),
-            };
+            let update_info = update_info_from_item(item);
 
             *self.ivars().last_found_update.borrow_mut() = Some(update_info.clone());
             self.emit(EVENT_DID_FIND_VALID_UPDATE, &update_info);
@@ -125,9 +188,7 @@ define_class!(
                 }
             }
 
-            self.emit(EVENT_WILL_DOWNLOAD_UPDATE, &VersionInfo {
-                version: item.display_version_string().to_string(),
-            });
+            self.emit(EVENT_WILL_DOWNLOAD_UPDATE, &version_info_from_item(item));
         }
 
         #[unsafe(method(updater:didDownloadUpdate:))]
@@ -136,9 +197,7 @@ define_class!(
             _updater: &NSObject,
             item: &SPUAppcastItem,
         ) {
-            self.emit(EVENT_DID_DOWNLOAD_UPDATE, &VersionInfo {
-                version: item.display_version_string().to_string(),
-            });
+            self.emit(EVENT_DID_DOWNLOAD_UPDATE, &version_info_from_item(item));
         }
 
         #[unsafe(method(updater:willInstallUpdate:))]
@@ -147,9 +206,10 @@ define_class!(
             _updater: &NSObject,
             item: &SPUAppcastItem,
         ) {
-            self.emit(EVENT_WILL_INSTALL_UPDATE, &VersionInfo {
-                version: item.display_version_string().to_string(),
-            });
+            self.ivars().pending_install.borrow_mut().take();
+            self.ivars().pre_install_passed.borrow_mut().take();
+            remember_installing_update(item);
+            self.emit(EVENT_WILL_INSTALL_UPDATE, &version_info_from_item(item));
         }
 
         #[unsafe(method(updater:didAbortWithError:))]
@@ -158,11 +218,7 @@ define_class!(
             _updater: &NSObject,
             ns_error: &NSObject,
         ) {
-            self.emit(EVENT_DID_ABORT_WITH_ERROR, &ErrorPayload {
-                message: nserror_description(ns_error),
-                code: unsafe { msg_send![ns_error, code] },
-                domain: nserror_domain(ns_error),
-            });
+            self.emit(EVENT_DID_ABORT_WITH_ERROR, &error_payload(ns_error));
         }
 
         #[unsafe(method(updater:didFinishUpdateCycleForUpdateCheck:error:))]
@@ -172,18 +228,9 @@ define_class!(
             update_check: isize,
             error: Option<&NSObject>,
         ) {
-            let update_check_str = match update_check {
-                0 => "userInitiated",
-                1 => "background",
-                _ => "information",
-            };
             self.emit(EVENT_DID_FINISH_UPDATE_CYCLE, &UpdateCycleInfo {
-                update_check: update_check_str.to_string(),
-                error: error.map(|e| ErrorPayload {
-                    message: nserror_description(e),
-                    code: unsafe { msg_send![e, code] },
-                    domain: nserror_domain(e),
-                }),
+                update_check: UpdateCheck::from_raw(update_check).as_str().to_string(),
+                error: error.map(error_payload),
             });
         }
 
@@ -195,12 +242,8 @@ define_class!(
             ns_error: &NSObject,
         ) {
             self.emit(EVENT_FAILED_TO_DOWNLOAD_UPDATE, &DownloadFailedInfo {
-                version: item.display_version_string().to_string(),
-                error: ErrorPayload {
-                    message: nserror_description(ns_error),
-                    code: unsafe { msg_send![ns_error, code] },
-                    domain: nserror_domain(ns_error),
-                },
+                update: version_info_from_item(item),
+                error: error_payload(ns_error),
             });
         }
 
@@ -211,16 +254,12 @@ define_class!(
 
         #[unsafe(method(updater:willExtractUpdate:))]
         fn updater_will_extract_update(&self, _updater: &NSObject, item: &SPUAppcastItem) {
-            self.emit(EVENT_WILL_EXTRACT_UPDATE, &VersionInfo {
-                version: item.display_version_string().to_string(),
-            });
+            self.emit(EVENT_WILL_EXTRACT_UPDATE, &version_info_from_item(item));
         }
 
         #[unsafe(method(updater:didExtractUpdate:))]
         fn updater_did_extract_update(&self, _updater: &NSObject, item: &SPUAppcastItem) {
-            self.emit(EVENT_DID_EXTRACT_UPDATE, &VersionInfo {
-                version: item.display_version_string().to_string(),
-            });
+            self.emit(EVENT_DID_EXTRACT_UPDATE, &version_info_from_item(item));
         }
 
         #[unsafe(method(updaterWillRelaunchApplication:))]
@@ -265,7 +304,7 @@ define_class!(
 
         #[unsafe(method(updaterShouldPromptForPermissionToCheckForUpdates:))]
         fn updater_should_prompt_for_permission(&self, _updater: &NSObject) -> bool {
-            true
+            *self.ivars().should_prompt_for_permission.borrow()
         }
 
         #[unsafe(method(updater:willInstallUpdateOnQuit:immediateInstallationBlock:))]
@@ -273,12 +312,19 @@ define_class!(
             &self,
             _updater: &NSObject,
             item: &SPUAppcastItem,
-            _handler: &NSObject,
+            immediate_installation: &Block<dyn Fn()>,
         ) -> bool {
-            self.emit(EVENT_WILL_INSTALL_UPDATE_ON_QUIT, &VersionInfo {
-                version: item.display_version_string().to_string(),
-            });
-            true
+            self.will_install_on_quit(item, immediate_installation)
+        }
+
+        #[unsafe(method(updater:shouldPostponeRelaunchForUpdate:untilInvokingBlock:))]
+        fn updater_should_postpone_relaunch(
+            &self,
+            _updater: &NSObject,
+            item: &SPUAppcastItem,
+            install_handler: &Block<dyn Fn()>,
+        ) -> bool {
+            self.postpone_relaunch(item, install_handler)
         }
 
         #[unsafe(method(allowedChannelsForUpdater:))]
@@ -360,21 +406,52 @@ define_class!(
         fn updater_may_perform_update_check(
             &self,
             _updater: &NSObject,
-            _update_check: isize,
-            _error: *mut *mut NSObject,
+            update_check: isize,
+            error: *mut *mut NSError,
         ) -> bool {
-            *self.ivars().may_check_for_updates.borrow()
+            let update_check = UpdateCheck::from_raw(update_check);
+
+            let decision = if *self.ivars().may_check_for_updates.borrow() {
+                let policies = self.ivars().may_check_policies.borrow().clone();
+                policies.evaluate(update_check)
+            } else {
+                CheckDecision::Deny("Update checks are disabled".to_string())
+            };
+
+            match decision {
+                CheckDecision::Allow if self.should_escalate_check(update_check) => {
+                    !self.escalate_check(error)
+                }
+                CheckDecision::Allow => true,
+                CheckDecision::Deny(reason) => {
+                    write_error(error, CHECK_DENIED_CODE, &reason);
+                    self.emit(EVENT_DID_DENY_UPDATE_CHECK, &UpdateCheckDeniedInfo {
+                        update_check: update_check.as_str().to_string(),
+                        reason,
+                    });
+                    false
+                }
+            }
         }
 
         #[unsafe(method(updater:shouldProceedWithUpdate:updateCheck:error:))]
         fn updater_should_proceed_with_update(
             &self,
             _updater: &NSObject,
-            _item: &SPUAppcastItem,
-            _update_check: isize,
-            _error: *mut *mut NSObject,
+            item: &SPUAppcastItem,
+            update_check: isize,
+            error: *mut *mut NSError,
         ) -> bool {
-            *self.ivars().should_proceed_with_update.borrow()
+            self.should_proceed_with(item, UpdateCheck::from_raw(update_check), error)
+        }
+
+        #[unsafe(method(versionComparatorForUpdater:))]
+        fn version_comparator_for_updater(&self, _updater: &NSObject) -> *mut NSObject {
+            let comparator = self.ivars().sparkle_comparator.borrow();
+            match comparator.as_ref() {
+                Some(c) => Retained::autorelease_return(Retained::into_super(c.clone())),
+                None => std::ptr::null_mut(),
+            }
         }
 
         #[unsafe(method(decryptionPasswordForUpdater:))]
@@ -391,6 +468,135 @@ define_class!(
     }
 );
 
+fn url_to_string(url: &NSURL) -> String {
+    let abs: Option<Retained<NSString>> = unsafe { msg_send![url, absoluteString] };
+    abs.map(|s| s.to_string()).unwrap_or_default()
+}
+
+/// Caches the item Sparkle installs, so the next launch can report its release notes.
+fn remember_installing_update(item: &SPUAppcastItem) {
+    match serde_json::to_string(&update_info_from_item(item)) {
+        Ok(json) => defaults::set_string(INSTALLING_UPDATE_KEY, &json),
+        Err(e) => error!("Failed to cache the installing update: {}", e),
+    }
+}
+
+fn content_length(item: &SPUAppcastItem) -> Option<u64> {
+    Some(item.content_length()).filter(|&length| length > 0)
+}
+
+fn version_info_from_item(item: &SPUAppcastItem) -> VersionInfo {
+    VersionInfo {
+        version: item.display_version_string().to_string(),
+        build_version: item.version_string().to_string(),
+        file_url: item.file_url().map(|u| url_to_string(&u)),
+        content_length: content_length(item),
+        is_delta: item.is_delta_update(),
+    }
+}
+
+pub(super) fn update_info_from_item(item: &SPUAppcastItem) -> UpdateInfo {
+    let number_to_f64 = |num: &NSNumber| -> f64 { unsafe { msg_send![num, doubleValue] } };
+
+    UpdateInfo {
+        version: item.display_version_string().to_string(),
+        build_version: item.version_string().to_string(),
+        file_url: item.file_url().map(|u| url_to_string(&u)),
+        content_length: content_length(item),
+        is_delta: item.is_delta_update(),
+        release_notes: item.item_description().map(|s| s.to_string()),
+        title: item.title().map(|s| s.to_string()),
+        release_notes_url: item.release_notes_url().map(|u| url_to_string(&u)),
+        info_url: item.info_url().map(|u| url_to_string(&u)),
+        minimum_system_version: item.minimum_system_version().map(|s| s.to_string()),
+        channel: item.channel().map(|s| s.to_string()),
+        date: item.date().map(|d| {
+            let seconds: f64 = unsafe { msg_send![&d, timeIntervalSince1970] };
+            seconds * 1000.0
+        }),
+        is_critical: item.is_critical_update(),
+        is_major_upgrade: item.is_major_upgrade(),
+        is_information_only: item.is_information_only_update(),
+        maximum_system_version: item.maximum_system_version().map(|s| s.to_string()),
+        minimum_os_version_ok: item.minimum_operating_system_version_is_ok(),
+        maximum_os_version_ok: item.maximum_operating_system_version_is_ok(),
+        installation_type: item.installation_type().to_string(),
+        phased_rollout_interval: item.phased_rollout_interval().map(|n| number_to_f64(&n)),
+        full_release_notes_url: item.full_release_notes_url().map(|u| url_to_string(&u)),
+        minimum_autoupdate_version: item.minimum_autoupdate_version().map(|s| s.to_string()),
+        ignore_skipped_upgrades_below_version: item
+            .ignore_skipped_upgrades_below_version()
+            .map(|s| s.to_string()),
+        date_string: item.date_string().map(|s| s.to_string()),
+        item_description_format: item.item_description_format().map(|s| s.to_string()),
+        extra: extra_properties(item),
+    }
+}
+
+/// Appcast elements Sparkle has no dedicated property for, keyed by qualified name.
+fn extra_properties(item: &SPUAppcastItem) -> Map<String, Value> {
+    let (keys, values) = item.properties_dictionary().to_vecs();
+    keys.iter()
+        .zip(values.iter())
+        .map(|(key, value)| (key.to_string(), value))
+        .filter(|(key, _)| !is_standard_element(key))
+        .map(|(key, value)| (key, ns_object_to_json(value)))
+        .collect()
+}
+
+/// Converts the property list values Sparkle stores for appcast elements.
+fn ns_object_to_json(object: &AnyObject) -> Value {
+    if let Some(string) = object.downcast_ref::<NSString>() {
+        Value::String(string.to_string())
+    } else if let Some(number) = object.downcast_ref::<NSNumber>() {
+        serde_json::Number::from_f64(number.doubleValue()).map_or(Value::Null, Value::Number)
+    } else if let Some(dictionary) = object.downcast_ref::<NSDictionary>() {
+        let (keys, values) = dictionary.to_vecs();
+        keys.iter()
+            .zip(values.iter())
+            .map(|(key, value)| (description(key), ns_object_to_json(value)))
+            .collect::<Map<String, Value>>()
+            .into()
+    } else if let Some(array) = object.downcast_ref::<NSArray>() {
+        array
+            .iter()
+            .map(|element| ns_object_to_json(&element))
+            .collect()
+    } else {
+        Value::String(description(object))
+    }
+}
+
+fn description(object: &AnyObject) -> String {
+    let description: Retained<NSString> = unsafe { msg_send![object, description] };
+    description.to_string()
+}
+
+/// Writes an autoreleased `NSError` in the plugin's error domain to a Sparkle out-parameter.
+fn write_error(out: *mut *mut NSError, code: i64, message: &str) {
+    if out.is_null() {
+        return;
+    }
+    let domain = NSString::from_str(PLUGIN_ERROR_DOMAIN);
+    let key = NSString::from_str("NSLocalizedDescription");
+    let value = NSString::from_str(message);
+    let user_info = NSDictionary::from_slices(&[&*key], &[&*value]);
+    let ns_error: Retained<NSError> = unsafe {
+        msg_send![
+            NSError::class(),
+            errorWithDomain: &*domain,
+            code: code as isize,
+            userInfo: &*user_info
+        ]
+    };
+    unsafe { *out = Retained::autorelease_ptr(ns_error) };
+}
+
+pub(super) fn error_payload(error: &NSObject) -> ErrorPayload {
+    let code: i64 = unsafe { msg_send![error, code] };
+    ErrorPayload::new(nserror_description(error), code, nserror_domain(error))
+}
+
 fn nserror_description(error: &NSObject) -> String {
     let desc: Retained<NSString> = unsafe { msg_send![error, localizedDescription] };
     desc.to_string()
@@ -407,16 +613,37 @@ impl SparkleDelegate {
         let this = this.set_ivars(DelegateIvars {
             emitter: RefCell::new(None),
             event_callback: RefCell::new(None),
+            event_listeners: RefCell::new(Vec::new()),
             allowed_channels: RefCell::new(None),
             feed_url_override: RefCell::new(None),
             feed_parameters: RefCell::new(None),
             should_download_release_notes: RefCell::new(true),
             should_relaunch: RefCell::new(true),
+            relaunch_override: RefCell::new(None),
+            should_prompt_for_permission: RefCell::new(true),
             may_check_for_updates: RefCell::new(true),
             should_proceed_with_update: RefCell::new(true),
             decryption_password: RefCell::new(None),
             last_found_update: RefCell::new(None),
             download_request_headers: RefCell::new(None),
+            installed_version: RefCell::new(None),
+            highest_installed_version: RefCell::new(None),
+            proceed_policy: RefCell::new(None),
+            may_check_policies: RefCell::new(MayCheckPolicies::default()),
+            latest_stable_version: RefCell::new(None),
+            appcast_releases: RefCell::new(None),
+            version_comparator: RefCell::new(Arc::new(StandardVersionComparator)),
+            sparkle_comparator: RefCell::new(None),
+            pending_install: RefCell::new(None),
+            pre_install_hooks: RefCell::new(PreInstallHooks::new()),
+            pre_install_completion: RefCell::new(None),
+            pre_install_running: RefCell::new(false),
+            pre_install_passed: RefCell::new(None),
+            minimum_version_element: RefCell::new(None),
+            minimum_version: RefCell::new(MinimumVersionGate::new()),
+            revoked_releases_element: RefCell::new(None),
+            revocation: RefCell::new(RevocationMonitor::new()),
+            check_escalation: RefCell::new(None),
         });
         unsafe { msg_send![super(this), init] }
     }
@@ -429,10 +656,445 @@ impl SparkleDelegate {
         *self.ivars().event_callback.borrow_mut() = callback;
     }
 
-    fn emit<T: Serialize>(&self, event: &str, payload: &T) {
+    /// Adds a listener called with every event, alongside the event callback.
+    pub fn add_event_listener(&self, listener: EventCallback) {
+        self.ivars().event_listeners.borrow_mut().push(listener);
+    }
+
+    /// Replaces Sparkle's version comparison and the one used by the downgrade checks.
+    pub fn set_version_comparator(&self, comparator: Arc<dyn VersionComparator>) {
+        *self.ivars().sparkle_comparator.borrow_mut() =
+            Some(SparkleVersionComparator::new(comparator.clone()));
+        *self.ivars().version_comparator.borrow_mut() = comparator;
+    }
+
+    pub fn version_comparator(&self) -> Arc<dyn VersionComparator> {
+        self.ivars().version_comparator.borrow().clone()
+    }
+
+    /// Sets the build versions used to refuse downgrades and replayed updates.
+    pub fn set_installed_versions(&self, installed: String, highest: String) {
+        *self.ivars().installed_version.borrow_mut() = Some(installed);
+        *self.ivars().highest_installed_version.borrow_mut() = Some(highest);
+    }
+
+    pub fn set_proceed_policy(&self, policy: Option<ProceedPolicy>) {
+        *self.ivars().proceed_policy.borrow_mut() = policy;
+    }
+
+    pub fn add_may_check_policy(&self, policy: Arc<dyn MayCheckPolicy>) {
+        self.ivars().may_check_policies.borrow_mut().push(policy);
+    }
+
+    pub fn has_proceed_policy(&self) -> bool {
+        self.ivars().proceed_policy.borrow().is_some()
+    }
+
+    pub fn may_check_policy_count(&self) -> usize {
+        self.ivars().may_check_policies.borrow().len()
+    }
+
+    pub fn set_may_check_policies(&self, policies: MayCheckPolicies) {
+        *self.ivars().may_check_policies.borrow_mut() = policies;
+    }
+
+    pub fn clear_may_check_policies(&self) {
+        self.ivars().may_check_policies.borrow_mut().clear();
+    }
+
+    /// Whether an update is staged to install when the app quits.
+    pub fn has_immediate_installation(&self) -> bool {
+        self.ivars()
+            .pending_install
+            .borrow()
+            .as_ref()
+            .is_some_and(|pending| pending.on_quit)
+    }
+
+    /// Whether an update waits for `install_update_now`: one staged on quit, or one whose
+    /// relaunch is postponed after a pre-install hook aborted.
+    pub fn has_pending_install(&self) -> bool {
+        self.ivars().pending_install.borrow().is_some()
+    }
+
+    /// Installs the pending update right away, once the pre-install hooks passed for it.
+    /// Returns `false` when no update is waiting.
+    pub fn install_update_now(&self) -> bool {
+        let pending = self
+            .ivars()
+            .pending_install
+            .borrow()
+            .as_ref()
+            .map(|pending| (pending.update.clone(), pending.on_quit));
+        let Some((update, on_quit)) = pending else {
+            return false;
+        };
+        if !self.start_pre_install_hooks(update, on_quit) {
+            self.install_pending();
+        }
+        true
+    }
+
+    fn install_pending(&self) {
+        // Sparkle terminates the app from within the block, so the ivar must not stay
+        // borrowed while it runs.
+        let pending = self.ivars().pending_install.borrow_mut().take();
+        if let Some(pending) = pending {
+            pending.install.call(());
+        }
+    }
+
+    /// Decides `updater:willInstallUpdateOnQuit:immediateInstallationBlock:`. The plugin
+    /// takes the install over: the quit is held while install blockers are held, and
+    /// `install_update_now` can install it early after running the pre-install hooks.
+    fn will_install_on_quit(
+        &self,
+        item: &SPUAppcastItem,
+        immediate_installation: &Block<dyn Fn()>,
+    ) -> bool {
+        self.ivars().pending_install.replace(Some(PendingInstall {
+            update: update_info_from_item(item),
+            on_quit: true,
+            install: immediate_installation.copy(),
+        }));
+        remember_installing_update(item);
+        self.emit(
+            EVENT_WILL_INSTALL_UPDATE_ON_QUIT,
+            &version_info_from_item(item),
+        );
+        true
+    }
+
+    pub fn set_pre_install_hooks(&self, hooks: PreInstallHooks) {
+        *self.ivars().pre_install_hooks.borrow_mut() = hooks;
+    }
+
+    pub fn set_pre_install_completion(&self, completion: Option<PreInstallCompletion>) {
+        *self.ivars().pre_install_completion.borrow_mut() = completion;
+    }
+
+    /// Decides `updater:shouldPostponeRelaunchForUpdate:untilInvokingBlock:`. The relaunch
+    /// is postponed while the pre-install hooks run, and kept postponed after an abort so
+    /// `install_update_now` can retry.
+    fn postpone_relaunch(&self, item: &SPUAppcastItem, install_handler: &Block<dyn Fn()>) -> bool {
+        let update = update_info_from_item(item);
+        self.ivars().pending_install.replace(Some(PendingInstall {
+            update: update.clone(),
+            on_quit: false,
+            install: install_handler.copy(),
+        }));
+        if self.start_pre_install_hooks(update, false) {
+            return true;
+        }
+        self.ivars().pending_install.borrow_mut().take();
+        false
+    }
+
+    /// Starts the pre-install hooks for `update` off the main thread, unless they already
+    /// passed for it. Returns whether the install has to wait for
+    /// [`finish_pre_install_hooks`](Self::finish_pre_install_hooks).
+    fn start_pre_install_hooks(&self, update: UpdateInfo, on_quit: bool) -> bool {
+        let hooks = self.ivars().pre_install_hooks.borrow().clone();
+        let passed =
+            self.ivars().pre_install_passed.borrow().as_ref() == Some(&update.build_version);
+        let completion = self.ivars().pre_install_completion.borrow().clone();
+        let Some(completion) = completion.filter(|_| !hooks.is_empty() && !passed) else {
+            return false;
+        };
+        if self.ivars().pre_install_running.replace(true) {
+            return true;
+        }
+
+        let context = PreInstallContext {
+            from: self.installed_version().unwrap_or_default(),
+            update,
+            on_quit,
+        };
+        if let Err(e) = hooks.run_in_background(context, move |report| completion(report)) {
+            // The install stays pending, so `install_update_now` can try again.
+            *self.ivars().pre_install_running.borrow_mut() = false;
+            warn!("Failed to start the pre-install hooks: {}", e);
+        }
+        true
+    }
+
+    /// Takes the report of the pre-install hooks on the main thread. Installs the pending
+    /// update when they passed; after an abort it waits for `install_update_now`.
+    pub fn finish_pre_install_hooks(&self, report: PreInstallReport) {
+        *self.ivars().pre_install_running.borrow_mut() = false;
+        if let Some(abort) = report.aborted {
+            warn!(
+                "Pre-install hook {} aborted installing {}: {}",
+                abort.hook, abort.version, abort.reason
+            );
+            self.emit(EVENT_PRE_INSTALL_ABORTED, &abort);
+            return;
+        }
+        let version = self
+            .ivars()
+            .pending_install
+            .borrow()
+            .as_ref()
+            .map(|pending| pending.update.build_version.clone());
+        *self.ivars().pre_install_passed.borrow_mut() = version;
+        self.install_pending();
+    }
+
+    pub fn set_minimum_version_element(&self, element: Option<String>) {
+        *self.ivars().minimum_version_element.borrow_mut() = element;
+    }
+
+    /// Records a declared minimum supported version and emits `sparkle://update-required`
+    /// when the running build falls below it.
+    pub fn declare_minimum_version(
+        &self,
+        minimum: &str,
+        message: Option<String>,
+        source: MinimumVersionSource,
+    ) {
+        let Some(current) = self.installed_version() else {
+            return;
+        };
+        let comparator = self.version_comparator();
+        let required = self.ivars().minimum_version.borrow_mut().declare(
+            &*comparator,
+            &current,
+            minimum,
+            message,
+            source,
+        );
+        if let Some(required) = required {
+            warn!(
+                "Build {} is below the minimum supported version {}",
+                required.current_version, required.minimum_version
+            );
+            self.emit(EVENT_UPDATE_REQUIRED, &required);
+        }
+    }
+
+    pub fn update_required(&self) -> Option<UpdateRequiredInfo> {
+        self.ivars().minimum_version.borrow().required().cloned()
+    }
+
+    fn declare_appcast_minimum_version(&self, appcast: &SPUAppcast) {
+        let element = self.ivars().minimum_version_element.borrow().clone();
+        let Some(element) = element else {
+            return;
+        };
+        let values: Vec<String> = appcast
+            .items()
+            .iter()
+            .filter_map(|item| {
+                extra_properties(&item)
+                    .get(&element)
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .collect();
+        let comparator = self.version_comparator();
+        if let Some(minimum) = highest_minimum(&*comparator, values.iter().map(String::as_str)) {
+            self.declare_minimum_version(minimum, None, MinimumVersionSource::Appcast);
+        }
+    }
+
+    pub fn set_revoked_releases_element(&self, element: Option<String>) {
+        *self.ivars().revoked_releases_element.borrow_mut() = element;
+    }
+
+    /// Sets what starts a user-initiated check when a background check is escalated. It is
+    /// called while Sparkle is deciding on the background check, so it must defer the check.
+    pub fn set_check_escalation(&self, escalation: Option<Arc<dyn Fn()>>) {
+        *self.ivars().check_escalation.borrow_mut() = escalation;
+    }
+
+    /// Restores the revoked releases persisted by an earlier launch and compares them
+    /// against the running build.
+    pub fn restore_revoked_releases(&self, releases: RevokedReleases) {
+        self.ivars().revocation.borrow_mut().restore(releases);
+        self.evaluate_revocation();
+    }
+
+    /// Replaces the revoked releases listed by `source` and emits
+    /// `sparkle://current-version-revoked` when the running build is among them.
+    pub fn set_revoked_releases(&self, source: RevocationSource, revoked: Vec<RevokedRelease>) {
+        self.ivars()
+            .revocation
+            .borrow_mut()
+            .set_revoked(source, revoked);
+        self.persist_revoked_releases();
+        self.evaluate_revocation();
+    }
+
+    pub fn current_version_revoked(&self) -> Option<CurrentVersionRevoked> {
+        self.ivars().revocation.borrow().revoked().cloned()
+    }
+
+    /// Whether a check of kind `update_check` is made user-initiated because the running
+    /// build is revoked.
+    pub fn should_escalate_check(&self, update_check: UpdateCheck) -> bool {
+        self.ivars()
+            .revocation
+            .borrow()
+            .should_escalate(update_check)
+    }
+
+    fn persist_revoked_releases(&self) {
+        let json = serde_json::to_string(self.ivars().revocation.borrow().releases());
+        match json {
+            Ok(json) => defaults::set_string(REVOKED_RELEASES_KEY, &json),
+            Err(e) => error!("Failed to record the revoked releases: {}", e),
+        }
+    }
+
+    fn evaluate_revocation(&self) {
+        let Some(current) = self.installed_version() else {
+            return;
+        };
+        let comparator = self.version_comparator();
+        let revoked = self
+            .ivars()
+            .revocation
+            .borrow_mut()
+            .evaluate(&*comparator, &current);
+        if let Some(revoked) = revoked {
+            warn!("Build {} has been revoked", revoked.current_version);
+            self.emit(EVENT_CURRENT_VERSION_REVOKED, &revoked);
+        }
+    }
+
+    fn update_appcast_revocations(&self, appcast: &SPUAppcast) {
+        let allowed_channels = self.allowed_channels().unwrap_or_default();
+        let items = appcast.items();
+        let candidates = items
+            .iter()
+            .filter(|item| match item.channel() {
+                Some(channel) => allowed_channels.contains(&channel.to_string()),
+                None => true,
+            })
+            .filter(|item| item.minimum_operating_system_version_is_ok())
+            .map(|item| ReleaseCandidate {
+                version: item.display_version_string().to_string(),
+                build_version: item.version_string().to_string(),
+            })
+            .collect();
+        self.ivars()
+            .revocation
+            .borrow_mut()
+            .set_candidates(candidates);
+
+        let element = self.ivars().revoked_releases_element.borrow().clone();
+        match element {
+            Some(element) => {
+                let revoked = items
+                    .iter()
+                    .filter_map(|item| {
+                        extra_properties(&item)
+                            .get(&element)
+                            .and_then(Value::as_str)
+                            .map(parse_revoked_versions)
+                    })
+                    .flatten()
+                    .collect();
+                self.set_revoked_releases(RevocationSource::Appcast, revoked);
+            }
+            None => self.evaluate_revocation(),
+        }
+    }
+
+    /// Replaces a check [`should_escalate_check`](Self::should_escalate_check) picked with a
+    /// user-initiated one. Returns whether the check was escalated.
+    fn escalate_check(&self, error: *mut *mut NSError) -> bool {
+        let escalation = self.ivars().check_escalation.borrow().clone();
+        let Some(escalation) = escalation else {
+            return false;
+        };
+        write_error(
+            error,
+            CHECK_ESCALATED_CODE,
+            "Replaced by a user-initiated check because the running build is revoked",
+        );
+        escalation();
+        true
+    }
+
+    /// Decides `updater:shouldProceedWithUpdate:updateCheck:error:`.
+    fn should_proceed_with(
+        &self,
+        item: &SPUAppcastItem,
+        update_check: UpdateCheck,
+        error: *mut *mut NSError,
+    ) -> bool {
+        if !*self.ivars().should_proceed_with_update.borrow() {
+            return false;
+        }
+
+        let installed = self.ivars().installed_version.borrow();
+        if let Some(installed) = installed.as_deref() {
+            let highest = self.ivars().highest_installed_version.borrow();
+            let candidate = item.version_string().to_string();
+            let comparator = self.version_comparator();
+            if let Err(rejection) =
+                check_candidate_version(&*comparator, &candidate, installed, highest.as_deref())
+            {
+                let reason = rejection.to_string();
+                self.reject_update(item, update_check, error, rejection.code(), &reason);
+                return false;
+            }
+        }
+
+        let policy = self.ivars().proceed_policy.borrow().clone();
+        if let Some(policy) = policy {
+            let decision = policy(&update_info_from_item(item), update_check);
+            if let ProceedDecision::Reject(reason) = decision {
+                self.reject_update(item, update_check, error, POLICY_REJECTION_CODE, &reason);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn reject_update(
+        &self,
+        item: &SPUAppcastItem,
+        update_check: UpdateCheck,
+        error: *mut *mut NSError,
+        code: i64,
+        reason: &str,
+    ) {
+        warn!("Refusing update: {}", reason);
+        write_error(error, code, reason);
+        self.emit(
+            EVENT_DID_REJECT_UPDATE,
+            &UpdateRejectedInfo {
+                version: item.display_version_string().to_string(),
+                update_check: update_check.as_str().to_string(),
+                reason: reason.to_string(),
+            },
+        );
+    }
+
+    pub fn installed_version(&self) -> Option<String> {
+        self.ivars().installed_version.borrow().clone()
+    }
+
+    /// Highest build on the default channel seen in the last loaded appcast.
+    pub fn latest_stable_version(&self) -> Option<String> {
+        self.ivars().latest_stable_version.borrow().clone()
+    }
+
+    /// Releases of the last loaded appcast, or `None` before one has loaded.
+    pub fn appcast_releases(&self) -> Option<Vec<AppcastRelease>> {
+        self.ivars().appcast_releases.borrow().clone()
+    }
+
+    pub fn emit<T: Serialize>(&self, event: &str, payload: &T) {
         if let Some(ref emitter) = *self.ivars().emitter.borrow() {
             match serde_json::to_value(payload) {
                 Ok(value) => {
+                    for listener in self.ivars().event_listeners.borrow().iter() {
+                        listener(event, &value);
+                    }
                     if let Some(ref callback) = *self.ivars().event_callback.borrow() {
                         callback(event, &value);
                     }
@@ -443,6 +1105,28 @@ impl SparkleDelegate {
         }
     }
 
+    pub fn persisted_settings(&self) -> PersistedSettings {
+        let ivars = self.ivars();
+        PersistedSettings {
+            allowed_channels: ivars.allowed_channels.borrow().clone(),
+            feed_parameters: ivars.feed_parameters.borrow().clone(),
+            download_request_headers: ivars.download_request_headers.borrow().clone(),
+            feed_url_override: ivars.feed_url_override.borrow().clone(),
+            should_relaunch: *ivars.relaunch_override.borrow(),
+        }
+    }
+
+    pub fn restore_settings(&self, settings: PersistedSettings) {
+        let ivars = self.ivars();
+        *ivars.allowed_channels.borrow_mut() = settings.allowed_channels;
+        *ivars.feed_parameters.borrow_mut() = settings.feed_parameters;
+        *ivars.download_request_headers.borrow_mut() = settings.download_request_headers;
+        *ivars.feed_url_override.borrow_mut() = settings.feed_url_override;
+        if let Some(should_relaunch) = settings.should_relaunch {
+            self.override_should_relaunch(should_relaunch);
+        }
+    }
+
     pub fn allowed_channels(&self) -> Option<Vec<String>> {
         self.ivars().allowed_channels.borrow().clone()
     }
@@ -479,10 +1163,25 @@ impl SparkleDelegate {
         *self.ivars().should_relaunch.borrow()
     }
 
+    /// Sets the relaunch behaviour from the config, without persisting it.
     pub fn set_should_relaunch(&self, enabled: bool) {
         *self.ivars().should_relaunch.borrow_mut() = enabled;
     }
 
+    /// Sets the relaunch behaviour chosen at runtime, which is persisted.
+    pub fn override_should_relaunch(&self, enabled: bool) {
+        self.set_should_relaunch(enabled);
+        *self.ivars().relaunch_override.borrow_mut() = Some(enabled);
+    }
+
+    pub fn should_prompt_for_permission(&self) -> bool {
+        *self.ivars().should_prompt_for_permission.borrow()
+    }
+
+    pub fn set_should_prompt_for_permission(&self, enabled: bool) {
+        *self.ivars().should_prompt_for_permission.borrow_mut() = enabled;
+    }
+
     pub fn may_check_for_updates(&self) -> bool {
         *self.ivars().may_check_for_updates.borrow()
     }
@@ -0,0 +1,144 @@
+use objc2::rc::Retained;
+use objc2::runtime::NSObject;
+use objc2::{msg_send, ClassType};
+use objc2_foundation::{NSBundle, NSDictionary, NSString, NSUserDefaults};
+use tauri::Runtime;
+
+use super::bindings::SPUUpdater;
+use super::updater::SparkleUpdater;
+use crate::diagnostics::{
+    resolve_feed_url, BundleStatus, DiagnosticsReport, PlistKeyStatus, StartupInfo, UpdaterStatus,
+};
+
+/// Info.plist keys included in the report.
+const REPORTED_PLIST_KEYS: &[&str] = &[
+    "SUFeedURL",
+    "SUPublicEDKey",
+    "SUEnableAutomaticChecks",
+    "SUAutomaticallyUpdate",
+    "SUAllowsAutomaticUpdates",
+    "SUScheduledCheckInterval",
+    "SUEnableSystemProfiling",
+    "SUEnableInstallerLauncherService",
+    "SUVerifyUpdateBeforeExtraction",
+];
+
+pub fn bundle_status() -> BundleStatus {
+    unsafe {
+        let bundle = NSBundle::mainBundle();
+        let identifier: Option<Retained<NSString>> = msg_send![&bundle, bundleIdentifier];
+        let path: Option<Retained<NSString>> = msg_send![&bundle, bundlePath];
+        let identifier = identifier.map(|id| id.to_string());
+
+        let reason = match identifier.as_deref() {
+            None | Some("") => Some("The executable has no bundle identifier".to_string()),
+            Some("com.apple.dt.Xcode.tool") => {
+                Some("The executable is running as a command line tool".to_string())
+            }
+            Some(_) => None,
+        };
+
+        BundleStatus {
+            detected: reason.is_none(),
+            identifier,
+            path: path.map(|p| p.to_string()),
+            reason,
+        }
+    }
+}
+
+fn info_plist_value(key: &str) -> Option<String> {
+    unsafe {
+        let bundle = NSBundle::mainBundle();
+        let key = NSString::from_str(key);
+        let value: Option<Retained<NSObject>> =
+            msg_send![&bundle, objectForInfoDictionaryKey: &*key];
+        value.map(|v| {
+            let description: Retained<NSString> = msg_send![&v, description];
+            description.to_string()
+        })
+    }
+}
+
+fn sparkle_version() -> Option<String> {
+    unsafe {
+        let bundle: Option<Retained<NSBundle>> =
+            msg_send![NSBundle::class(), bundleForClass: SPUUpdater::class()];
+        let key = NSString::from_str("CFBundleShortVersionString");
+        let version: Option<Retained<NSString>> =
+            msg_send![&bundle?, objectForInfoDictionaryKey: &*key];
+        version.map(|v| v.to_string())
+    }
+}
+
+/// Feed URL stored by `set_feed_url`. Registered defaults from `tauri.conf.json` are not
+/// part of the persistent domain, so they don't show up here.
+fn user_defaults_feed_url(identifier: Option<&str>) -> Option<String> {
+    unsafe {
+        let defaults: Retained<NSUserDefaults> =
+            msg_send![NSUserDefaults::class(), standardUserDefaults];
+        let name = NSString::from_str(identifier?);
+        let domain: Option<Retained<NSDictionary<NSString, NSObject>>> =
+            msg_send![&defaults, persistentDomainForName: &*name];
+        let key = NSString::from_str("SUFeedURL");
+        let value: Option<Retained<NSObject>> = msg_send![&domain?, objectForKey: &*key];
+        value.map(|v| {
+            let description: Retained<NSString> = msg_send![&v, description];
+            description.to_string()
+        })
+    }
+}
+
+pub fn report<R: Runtime>(
+    startup: &StartupInfo,
+    updater: Option<&SparkleUpdater<R>>,
+) -> DiagnosticsReport {
+    let bundle = bundle_status();
+    let info_plist: Vec<PlistKeyStatus> = REPORTED_PLIST_KEYS
+        .iter()
+        .map(|key| {
+            let value = info_plist_value(key);
+            PlistKeyStatus {
+                key: key.to_string(),
+                present: value.is_some(),
+                value,
+            }
+        })
+        .collect();
+
+    let override_url = updater.and_then(|u| u.feed_url_override().ok().flatten());
+    let feed_url = resolve_feed_url(
+        override_url,
+        user_defaults_feed_url(bundle.identifier.as_deref()),
+        startup.config.feed_url.clone(),
+        info_plist_value("SUFeedURL"),
+    );
+
+    let (updater_status, policies) = match updater {
+        Some(updater) => {
+            let (status, policies) = updater.diagnostics_status();
+            (status, Some(policies))
+        }
+        None => (
+            UpdaterStatus {
+                created: false,
+                started: false,
+                start_mode: startup.start_mode,
+                startup_error: None,
+                custom_backend: false,
+                user_driver: startup.user_driver,
+            },
+            None,
+        ),
+    };
+
+    DiagnosticsReport {
+        plugin_version: env!("CARGO_PKG_VERSION").to_string(),
+        bundle,
+        info_plist,
+        sparkle_version: sparkle_version(),
+        feed_url,
+        updater: updater_status,
+        policies,
+    }
+}
@@ -0,0 +1,39 @@
+use std::ptr;
+
+use objc2::rc::Retained;
+use objc2::{msg_send, ClassType};
+use objc2_foundation::{NSData, NSError, NSString, NSURL};
+
+use crate::fetch::Fetcher;
+use crate::{Error, Result};
+
+/// Fetches URLs with `NSData`'s URL loading, which honours the system proxy settings.
+pub struct FoundationFetcher;
+
+impl Fetcher for FoundationFetcher {
+    fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        let string = NSString::from_str(url);
+        let ns_url: Option<Retained<NSURL>> =
+            unsafe { msg_send![NSURL::class(), URLWithString: &*string] };
+        let ns_url = ns_url.ok_or_else(|| Error::FetchFailed(format!("{}: invalid URL", url)))?;
+
+        let mut error: *mut NSError = ptr::null_mut();
+        let data: Option<Retained<NSData>> = unsafe {
+            msg_send![
+                NSData::class(),
+                dataWithContentsOfURL: &*ns_url,
+                options: 0usize,
+                error: &mut error
+            ]
+        };
+        match data {
+            Some(data) => Ok(data.to_vec()),
+            None if !error.is_null() => {
+                let description: Retained<NSString> =
+                    unsafe { msg_send![&*error, localizedDescription] };
+                Err(Error::FetchFailed(format!("{}: {}", url, description)))
+            }
+            None => Err(Error::FetchFailed(url.to_string())),
+        }
+    }
+}
@@ -1,5 +1,12 @@
 mod bindings;
+mod comparator;
+mod defaults;
 mod delegate;
+mod diagnostics;
+mod fetch;
 mod updater;
+mod user_driver;
 
-pub use updater::{init, SparkleUpdater};
+pub use delegate::EventCallback;
+pub use diagnostics::report as diagnostics;
+pub use updater::{init, Options, SparkleUpdater};
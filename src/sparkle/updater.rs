@@ -1,18 +1,60 @@
 use std::collections::HashMap;
 use std::ptr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use dispatch::Queue;
-use log::warn;
+use log::{error, warn};
 use objc2::rc::Retained;
 use objc2::runtime::NSObject;
 use objc2::{msg_send, ClassType, MainThreadMarker};
-use objc2_foundation::{NSBundle, NSDictionary, NSError, NSString, NSURL};
-use tauri::{AppHandle, Emitter, Runtime};
+use objc2_foundation::{
+    NSBundle, NSDictionary, NSError, NSNumber, NSString, NSUserDefaults, NSURL,
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
 
 use super::bindings::{SPUStandardUpdaterController, SPUUpdater};
+use super::defaults::{
+    self, HIGHEST_INSTALLED_VERSION_KEY, INSTALLING_UPDATE_KEY, LAST_LAUNCHED_VERSION_KEY,
+    REVOKED_RELEASES_KEY, UPDATE_VERIFICATION_KEY,
+};
 use super::delegate::{EventCallback, SparkleDelegate};
-use crate::events::UpdateInfo;
+use super::diagnostics::bundle_status;
+use super::fetch::FoundationFetcher;
+use super::user_driver::HeadlessUserDriver;
+use crate::appcast::{parse_releases, AppcastRelease};
+use crate::backend::UpdaterBackend;
+use crate::channels::{self, Channel, ChannelChange};
+use crate::config::{Config, FeedPolicy, StartMode, UserDriverMode};
+use crate::consent::{StartState, UpdateConsent};
+use crate::diagnostics::{PolicyStatus, UpdaterStatus};
+use crate::error::{SparkleErrorCode, SPARKLE_ERROR_DOMAIN};
+use crate::events::{
+    BeforeInstallQuitInfo, UpdateInfo, EVENT_BEFORE_INSTALL_QUIT, EVENT_CHANNEL_CHANGED,
+    EVENT_DID_UPDATE, EVENT_INSTALL_DEFERRED, EVENT_ROLLBACK_RECOMMENDED,
+};
+use crate::fetch::Fetcher;
+use crate::health::{HealthMonitor, PendingVerification, RollbackInfo, UpdateHealth};
+use crate::install_blockers::{InstallBlockStatus, InstallBlocker, InstallBlockers};
+use crate::migrations::{MigrationStore, Migrations};
+use crate::minimum_version::{MinimumVersionDocument, MinimumVersionSource, UpdateRequiredInfo};
+use crate::policy::{
+    CheckDecision, FeedUrlPolicy, MayCheckPolicies, MayCheckPolicy, ProceedPolicy, UpdateCheck,
+};
+use crate::pre_install::{PreInstallHooks, PreInstallReport};
+use crate::progress::DEFAULT_PROGRESS_INTERVAL;
+use crate::quit::{ExitDecision, QuitCoordinator, DEFAULT_QUIT_SAVE_TIMEOUT};
+use crate::release_notes::{
+    CumulativeNotesOptions, CumulativeReleaseNotes, ReleaseNotes, ReleaseNotesService,
+};
+use crate::revocation::{
+    CurrentVersionRevoked, RevocationSource, RevokedReleases, RevokedReleasesDocument,
+};
+use crate::signature::SignedDocumentSource;
+use crate::store::SettingsStore;
+use crate::user_driver::{PendingRequest, PromptKind, UserDriverReply, DEFAULT_REPLY_TIMEOUT};
+use crate::version::{newer_version, VersionComparator};
+use crate::whats_new::{detect_update, DidUpdateInfo, LaunchedVersion, WhatsNew};
 use crate::{Error, Result};
 
 /// Pointer wrapper for cross-thread dispatch. Only dereference on main thread.
@@ -40,26 +82,44 @@ impl<T> SendPtr<T> {
     }
 }
 
-fn is_valid_bundle() -> bool {
-    unsafe {
-        let bundle = NSBundle::mainBundle();
-        let identifier: Option<Retained<NSString>> = msg_send![&bundle, bundleIdentifier];
-        match identifier {
-            Some(id) => {
-                let id_str = id.to_string();
-                !id_str.is_empty() && id_str != "com.apple.dt.Xcode.tool"
-            }
-            None => false,
-        }
-    }
+/// Hooks installed before Sparkle starts, collected by [`crate::Builder`].
+#[derive(Default)]
+pub struct Options {
+    /// Delegate-side settings are restored from the store and written back by every setter.
+    pub store: Option<Arc<dyn SettingsStore>>,
+    pub event_listeners: Vec<EventCallback>,
+    pub proceed_policy: Option<ProceedPolicy>,
+    pub may_check_policies: MayCheckPolicies,
+    pub feed_url_policy: Option<Arc<dyn FeedUrlPolicy>>,
+    pub version_comparator: Option<Arc<dyn VersionComparator>>,
+    pub backend: Option<Arc<dyn UpdaterBackend>>,
+    /// Overrides `startMode` from the plugin configuration.
+    pub start_mode: Option<StartMode>,
+    /// Overrides `promptForPermission` from the plugin configuration.
+    pub prompt_for_permission: Option<bool>,
+    /// Overrides `userDriver` from the plugin configuration.
+    pub user_driver: Option<UserDriverMode>,
+    /// Run on the first launch after an update.
+    pub migrations: Migrations,
+    pub migration_store: Option<Arc<dyn MigrationStore>>,
+    /// Run right before Sparkle installs an update.
+    pub pre_install_hooks: PreInstallHooks,
+    /// Downloads documents besides the appcast. Defaults to Foundation's URL loading.
+    pub fetcher: Option<Arc<dyn Fetcher>>,
 }
 
 /// Returns `None` if running outside a valid macOS bundle (e.g., during `tauri dev`).
-pub fn init<R: Runtime>(app: &AppHandle<R>) -> Result<Option<SparkleUpdater<R>>> {
+///
+/// `config` is registered below the values Sparkle keeps in user defaults.
+pub fn init<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &Config,
+    options: Options,
+) -> Result<Option<SparkleUpdater<R>>> {
     let mtm = MainThreadMarker::new()
         .ok_or_else(|| Error::SparkleInit("Must be called on main thread".to_string()))?;
 
-    if !is_valid_bundle() {
+    if !bundle_status().detected {
         warn!(
             "Sparkle updater disabled: not running inside a valid macOS bundle. \
              This is expected during development (tauri dev). \
@@ -68,52 +128,382 @@ pub fn init<R: Runtime>(app: &AppHandle<R>) -> Result<Option<SparkleUpdater<R>>>
         return Ok(None);
     }
 
+    let Options {
+        store,
+        event_listeners,
+        proceed_policy,
+        may_check_policies,
+        feed_url_policy,
+        version_comparator,
+        backend,
+        start_mode,
+        prompt_for_permission,
+        user_driver,
+        migrations,
+        migration_store,
+        pre_install_hooks,
+        fetcher,
+    } = options;
+    let fetcher = fetcher.unwrap_or_else(|| Arc::new(FoundationFetcher));
+    let minimum_version_document = match &config.minimum_version {
+        Some(minimum_version) => {
+            SignedDocumentSource::new("minimumVersion", minimum_version, || {
+                info_plist_string("SUPublicEDKey")
+            })?
+        }
+        None => None,
+    };
+    let revoked_releases_document = match &config.revoked_releases {
+        Some(revoked_releases) => {
+            SignedDocumentSource::new("revokedReleases", revoked_releases, || {
+                info_plist_string("SUPublicEDKey")
+            })?
+        }
+        None => None,
+    };
+    let start_mode = start_mode.or(config.start_mode).unwrap_or_default();
+    let user_driver_mode = user_driver.or(config.user_driver).unwrap_or_default();
+
+    if let (Some(url), Some(policy)) = (&config.feed_url, &feed_url_policy) {
+        check_feed_url_policy(policy.as_ref(), url)?;
+    }
+
+    register_config_defaults(config);
     check_info_plist_keys();
 
     let delegate = SparkleDelegate::new(mtm);
+    if let Some(comparator) = version_comparator {
+        delegate.set_version_comparator(comparator);
+    }
+    for listener in event_listeners {
+        delegate.add_event_listener(listener);
+    }
+    delegate.set_proceed_policy(proceed_policy);
+    delegate.set_may_check_policies(may_check_policies);
+    delegate.set_pre_install_hooks(pre_install_hooks);
+    let app_handle = app.clone();
+    delegate.set_pre_install_completion(Some(Arc::new(move |report: PreInstallReport| {
+        let app = app_handle.clone();
+        Queue::main().exec_async(move || {
+            if let Some(updater) = app.try_state::<SparkleUpdater<R>>() {
+                updater.dispatch_delegate(move |d| d.finish_pre_install_hooks(report));
+            }
+        });
+    })));
+    delegate.set_minimum_version_element(
+        config
+            .minimum_version
+            .as_ref()
+            .and_then(|minimum_version| minimum_version.appcast_element.clone()),
+    );
+
+    let installed_version =
+        bundle_version().unwrap_or_else(|| app.package_info().version.to_string());
+    let highest_installed_version =
+        record_highest_installed_version(&*delegate.version_comparator(), &installed_version);
+    let current_version = LaunchedVersion {
+        version: app.package_info().version.to_string(),
+        build_version: installed_version.clone(),
+    };
+    let did_update = record_launch(&*delegate.version_comparator(), &current_version);
+    let mut health = config.health_check.as_ref().map(HealthMonitor::new);
+    let rollback = health
+        .as_mut()
+        .and_then(|health| record_health_launch(health, did_update.as_ref(), &current_version));
+    let http_headers = config.render_http_headers(&[
+        ("version", &app.package_info().version.to_string()),
+        ("build", &installed_version),
+        ("arch", std::env::consts::ARCH),
+        ("identifier", &app.config().identifier),
+    ])?;
+    delegate.set_installed_versions(installed_version, highest_installed_version);
+    if let Some(relaunch) = config.relaunch {
+        delegate.set_should_relaunch(relaunch);
+    }
+    if let Some(prompt) = prompt_for_permission.or(config.prompt_for_permission) {
+        delegate.set_should_prompt_for_permission(prompt);
+    }
+    if let Some(store) = &store {
+        match store.load() {
+            Ok(Some(mut settings)) => {
+                // The policy may have been tightened since the override was saved.
+                if let Some(url) = &settings.feed_url_override {
+                    if let Err(e) = check_feed_url(&config.feed_policy, &feed_url_policy, url) {
+                        warn!("Ignoring the persisted feed URL override {}: {}", url, e);
+                        settings.feed_url_override = None;
+                    }
+                }
+                delegate.restore_settings(settings);
+            }
+            Ok(None) => {}
+            Err(e) => error!("Failed to load persisted Sparkle settings: {}", e),
+        }
+    }
     let app_clone = app.clone();
     delegate.set_emitter(Arc::new(move |event: &str, payload: serde_json::Value| {
         if let Err(e) = app_clone.emit(event, payload) {
             log::error!("Failed to emit event {}: {}", event, e);
         }
     }));
+    // No webview is listening yet: only Rust listeners see this, windows poll
+    // `consume_whats_new`.
+    if let Some(update) = &did_update {
+        delegate.emit(EVENT_DID_UPDATE, update);
+    }
+    if let Some(revoked_releases) = &config.revoked_releases {
+        delegate.set_revoked_releases_element(revoked_releases.appcast_element.clone());
+        let app = app.clone();
+        delegate.set_check_escalation(Some(Arc::new(move || {
+            let app = app.clone();
+            Queue::main().exec_async(move || {
+                let Some(updater) = app.try_state::<SparkleUpdater<R>>() else {
+                    return;
+                };
+                if let Err(e) = updater.check_for_updates() {
+                    warn!("Failed to start a check for the revoked build: {}", e);
+                }
+            });
+        })));
+        if let Some(releases) = defaults::string(REVOKED_RELEASES_KEY)
+            .and_then(|json| serde_json::from_str::<RevokedReleases>(&json).ok())
+        {
+            delegate.restore_revoked_releases(releases);
+        }
+    }
+    if let Some(store) = migration_store.filter(|_| !migrations.is_empty()) {
+        let previous = did_update.as_ref().map(|u| u.from.build_version.as_str());
+        migrations.run_on_launch(
+            &*store,
+            &*delegate.version_comparator(),
+            previous,
+            &current_version.build_version,
+            |event, failure| delegate.emit(event, failure),
+        );
+    }
 
-    let controller = unsafe {
-        let alloc: objc2::rc::Allocated<SPUStandardUpdaterController> =
-            objc2::msg_send![SPUStandardUpdaterController::class(), alloc];
-        let delegate_obj: &NSObject = &delegate;
-        SPUStandardUpdaterController::init_with_starting_updater(
-            alloc,
-            false,
-            Some(delegate_obj),
-            None,
-        )
+    let delegate_obj: &NSObject = &delegate;
+    let (controller, updater, user_driver) = match user_driver_mode {
+        UserDriverMode::Standard => {
+            let controller = unsafe {
+                let alloc: objc2::rc::Allocated<SPUStandardUpdaterController> =
+                    objc2::msg_send![SPUStandardUpdaterController::class(), alloc];
+                SPUStandardUpdaterController::init_with_starting_updater(
+                    alloc,
+                    false,
+                    Some(delegate_obj),
+                    None,
+                )
+            };
+            let updater: Retained<SPUUpdater> = controller.updater();
+            (Some(controller), updater, None)
+        }
+        UserDriverMode::Headless => {
+            let reply_timeout = config
+                .reply_timeout
+                .map(Duration::from_secs_f64)
+                .unwrap_or(DEFAULT_REPLY_TIMEOUT);
+            let progress_interval = config
+                .download_progress_interval
+                .map(Duration::from_secs_f64)
+                .unwrap_or(DEFAULT_PROGRESS_INTERVAL);
+            let user_driver =
+                HeadlessUserDriver::new(mtm, delegate.clone(), reply_timeout, progress_interval);
+            let bundle = NSBundle::mainBundle();
+            let updater = unsafe {
+                let alloc: objc2::rc::Allocated<SPUUpdater> =
+                    objc2::msg_send![SPUUpdater::class(), alloc];
+                SPUUpdater::init_with_host_bundle(
+                    alloc,
+                    &bundle,
+                    &bundle,
+                    &user_driver,
+                    Some(delegate_obj),
+                )
+            };
+            (None, updater, Some(user_driver))
+        }
     };
 
-    let updater: Retained<SPUUpdater> = controller.updater();
-    let mut error: *mut NSError = ptr::null_mut();
-    let success = updater.start_updater(&mut error);
+    if !http_headers.is_empty() {
+        set_updater_http_headers(&updater, Some(http_headers));
+    }
 
-    if !success {
-        if !error.is_null() {
-            let ns_error = unsafe { &*error };
-            let description: Retained<NSString> =
-                unsafe { objc2::msg_send![ns_error, localizedDescription] };
-            return Err(Error::SparkleInit(description.to_string()));
-        }
-        return Err(Error::SparkleInit("Failed to start updater".to_string()));
+    // A failed start leaves the plugin usable, so diagnostics can report the error and
+    // `start_updater` can retry, as with a deferred start.
+    let start = StartState::launch(start_mode, || start_updater(&updater));
+    if let Some(e) = start.error() {
+        error!("Failed to start Sparkle's updater: {}", e);
     }
+    let quit_timeout = config
+        .install_quit_timeout
+        .map(Duration::from_secs_f64)
+        .unwrap_or(DEFAULT_QUIT_SAVE_TIMEOUT);
 
-    let controller_ptr = SendPtr::new(Retained::as_ptr(&controller));
+    let updater_ptr = SendPtr::new(Retained::as_ptr(&updater));
     let delegate_ptr = SendPtr::new(Retained::as_ptr(&delegate));
-
-    Ok(Some(SparkleUpdater {
+    let user_driver_ptr = user_driver
+        .as_ref()
+        .map(|driver| SendPtr::new(Retained::as_ptr(driver)));
+    let health_timeout = config
+        .health_check
+        .as_ref()
+        .and_then(|health_check| health_check.timeout())
+        .filter(|_| rollback.is_none())
+        .filter(|_| health.as_ref().is_some_and(|h| h.pending().is_some()));
+
+    let sparkle_updater = SparkleUpdater {
         app: app.clone(),
         _controller: controller,
-        controller_ptr,
+        _updater: updater,
+        updater_ptr,
         _delegate: delegate,
         delegate_ptr,
-    }))
+        _user_driver: user_driver,
+        user_driver_ptr,
+        user_driver_mode,
+        store,
+        channels: Mutex::new(config.channels.clone()),
+        feed_policy: config.feed_policy.clone(),
+        feed_url_policy,
+        backend,
+        start: Mutex::new(start),
+        start_mode,
+        install_blockers: InstallBlockers::new(),
+        whats_new: Mutex::new(WhatsNew::new(did_update)),
+        quit: Mutex::new(QuitCoordinator::new(!quit_timeout.is_zero())),
+        quit_timeout,
+        health: health.map(Mutex::new),
+        fetcher: fetcher.clone(),
+        release_notes: ReleaseNotesService::new(fetcher.clone()),
+        minimum_version_document: minimum_version_document.clone(),
+        revoked_releases_document: revoked_releases_document.clone(),
+    };
+    // Like `did-update`, this reaches Rust listeners only; windows read it from
+    // `update_health`.
+    if let Some(info) = rollback {
+        sparkle_updater.recommend_rollback(info);
+    }
+    if let Some(timeout) = health_timeout {
+        let app = app.clone();
+        Queue::main().exec_after(timeout, move || {
+            if let Some(updater) = app.try_state::<SparkleUpdater<R>>() {
+                updater.health_timed_out();
+            }
+        });
+    }
+    if let Some(source) = minimum_version_document {
+        let fetcher = fetcher.clone();
+        std::thread::spawn(
+            move || match source.fetch::<MinimumVersionDocument>(&*fetcher) {
+                Ok(document) => Queue::main().exec_async(move || {
+                    let delegate = unsafe { delegate_ptr.as_ref() };
+                    delegate.declare_minimum_version(
+                        &document.minimum_version,
+                        document.message,
+                        MinimumVersionSource::Document,
+                    );
+                }),
+                Err(e) => warn!("Failed to check the minimum supported version: {}", e),
+            },
+        );
+    }
+    if let Some(source) = revoked_releases_document {
+        let fetcher = fetcher.clone();
+        std::thread::spawn(
+            move || match source.fetch::<RevokedReleasesDocument>(&*fetcher) {
+                Ok(document) => Queue::main().exec_async(move || {
+                    let delegate = unsafe { delegate_ptr.as_ref() };
+                    delegate.set_revoked_releases(RevocationSource::Document, document.revoked);
+                }),
+                Err(e) => warn!("Failed to check for revoked releases: {}", e),
+            },
+        );
+    }
+    Ok(Some(sparkle_updater))
+}
+
+/// Questions of the headless user driver that [`SparkleUpdater::install_update_now`]
+/// answers.
+const INSTALL_PROMPTS: &[PromptKind] = &[PromptKind::ReadyToInstall, PromptKind::UpdateFound];
+
+/// Installs the update the headless user driver is asking about, or else the one waiting for
+/// the plugin. Returns `false` when there is none.
+fn install_now(driver: Option<&HeadlessUserDriver>, delegate: &SparkleDelegate) -> bool {
+    if let Some(driver) = driver {
+        match driver.answer(INSTALL_PROMPTS, &UserDriverReply::Install) {
+            Ok(true) => return true,
+            Ok(false) => {}
+            Err(e) => warn!("Failed to answer the user driver: {}", e),
+        }
+    }
+    delegate.install_update_now()
+}
+
+fn start_updater(updater: &SPUUpdater) -> Result<()> {
+    let mut error: *mut NSError = ptr::null_mut();
+    if updater.start_updater(&mut error) {
+        return Ok(());
+    }
+
+    if !error.is_null() {
+        let ns_error = unsafe { &*error };
+        let description: Retained<NSString> =
+            unsafe { objc2::msg_send![ns_error, localizedDescription] };
+        let domain: Retained<NSString> = unsafe { objc2::msg_send![ns_error, domain] };
+        let code: i64 = unsafe { objc2::msg_send![ns_error, code] };
+        if domain.to_string() == SPARKLE_ERROR_DOMAIN {
+            return Err(Error::Sparkle {
+                code: SparkleErrorCode::from_code(code),
+                raw_code: code,
+                message: description.to_string(),
+            });
+        }
+        return Err(Error::SparkleInit(description.to_string()));
+    }
+    Err(Error::SparkleInit("Failed to start updater".to_string()))
+}
+
+/// Registers `tauri.conf.json` values in the user defaults registration domain, so they
+/// override Info.plist but not the values Sparkle persists when settings change at runtime.
+fn register_config_defaults(config: &Config) {
+    let feed_url = config.feed_url.as_deref().map(NSString::from_str);
+    let automatic_checks = config
+        .automatically_checks_for_updates
+        .map(NSNumber::new_bool);
+    let automatic_downloads = config
+        .automatically_downloads_updates
+        .map(NSNumber::new_bool);
+    let interval = config.check_interval.map(NSNumber::new_f64);
+
+    let mut keys: Vec<Retained<NSString>> = Vec::new();
+    let mut values: Vec<&NSObject> = Vec::new();
+    if let Some(url) = &feed_url {
+        keys.push(NSString::from_str("SUFeedURL"));
+        values.push(url);
+    }
+    if let Some(enabled) = &automatic_checks {
+        keys.push(NSString::from_str("SUEnableAutomaticChecks"));
+        values.push(enabled);
+    }
+    if let Some(enabled) = &automatic_downloads {
+        keys.push(NSString::from_str("SUAutomaticallyUpdate"));
+        values.push(enabled);
+    }
+    if let Some(interval) = &interval {
+        keys.push(NSString::from_str("SUScheduledCheckInterval"));
+        values.push(interval);
+    }
+    if keys.is_empty() {
+        return;
+    }
+
+    let key_refs: Vec<&NSString> = keys.iter().map(|k| k.as_ref()).collect();
+    let registered = NSDictionary::from_slices(&key_refs, &values);
+    unsafe {
+        let defaults: Retained<NSUserDefaults> =
+            msg_send![NSUserDefaults::class(), standardUserDefaults];
+        let _: () = msg_send![&defaults, registerDefaults: &*registered];
+    }
 }
 
 const PLIST_KEY_VALIDATIONS: &[(&str, &str)] = &[
@@ -136,7 +526,11 @@ fn check_info_plist_keys() {
             for (key_name, warning) in PLIST_KEY_VALIDATIONS {
                 let key = NSString::from_str(key_name);
                 let value: Option<Retained<NSObject>> = msg_send![&dict, objectForKey: &*key];
-                if value.is_none() {
+                let defaults: Retained<NSUserDefaults> =
+                    msg_send![NSUserDefaults::class(), standardUserDefaults];
+                let default_value: Option<Retained<NSObject>> =
+                    msg_send![&defaults, objectForKey: &*key];
+                if value.is_none() && default_value.is_none() {
                     warn!("{} not found in Info.plist. {}", key_name, warning);
                 }
             }
@@ -144,13 +538,137 @@ fn check_info_plist_keys() {
     }
 }
 
+fn bundle_version() -> Option<String> {
+    info_plist_string("CFBundleVersion")
+}
+
+fn info_plist_string(key: &str) -> Option<String> {
+    unsafe {
+        let bundle = NSBundle::mainBundle();
+        let key = NSString::from_str(key);
+        let value: Option<Retained<NSString>> =
+            msg_send![&bundle, objectForInfoDictionaryKey: &*key];
+        value.map(|v| v.to_string())
+    }
+}
+
+/// Checks a feed URL against `feedPolicy` from `tauri.conf.json` and the Rust policy.
+fn check_feed_url(
+    feed_policy: &FeedPolicy,
+    feed_url_policy: &Option<Arc<dyn FeedUrlPolicy>>,
+    url: &str,
+) -> Result<()> {
+    feed_policy.check(url)?;
+    match feed_url_policy {
+        Some(policy) => check_feed_url_policy(policy.as_ref(), url),
+        None => Ok(()),
+    }
+}
+
+fn check_feed_url_policy(policy: &dyn FeedUrlPolicy, url: &str) -> Result<()> {
+    match policy.evaluate(url) {
+        CheckDecision::Allow => Ok(()),
+        CheckDecision::Deny(reason) => Err(Error::FeedUrlRejected(reason)),
+    }
+}
+
+/// Raises the persisted high-water mark to `installed` if needed and returns the new mark.
+fn record_highest_installed_version(comparator: &dyn VersionComparator, installed: &str) -> String {
+    let stored = defaults::string(HIGHEST_INSTALLED_VERSION_KEY);
+    let highest = match stored.as_deref() {
+        Some(mark) => newer_version(comparator, mark, installed).to_string(),
+        None => installed.to_string(),
+    };
+    if stored.as_deref() != Some(highest.as_str()) {
+        defaults::set_string(HIGHEST_INSTALLED_VERSION_KEY, &highest);
+    }
+    highest
+}
+
+/// Records this launch's version and returns the update it follows, if any.
+fn record_launch(
+    comparator: &dyn VersionComparator,
+    current: &LaunchedVersion,
+) -> Option<DidUpdateInfo> {
+    let previous = defaults::string(LAST_LAUNCHED_VERSION_KEY)
+        .and_then(|json| serde_json::from_str::<LaunchedVersion>(&json).ok());
+    let installed = defaults::string(INSTALLING_UPDATE_KEY)
+        .and_then(|json| serde_json::from_str::<UpdateInfo>(&json).ok());
+    let did_update = detect_update(comparator, previous.as_ref(), current, installed);
+
+    if previous.as_ref() != Some(current) {
+        match serde_json::to_string(current) {
+            Ok(json) => defaults::set_string(LAST_LAUNCHED_VERSION_KEY, &json),
+            Err(e) => error!("Failed to record the launched version: {}", e),
+        }
+    }
+    if did_update.is_some() {
+        defaults::remove(INSTALLING_UPDATE_KEY);
+    }
+    did_update
+}
+
+/// Counts this launch against the pending update verification and persists it. Returns the
+/// rollback recommendation when too many launches failed.
+fn record_health_launch(
+    health: &mut HealthMonitor,
+    did_update: Option<&DidUpdateInfo>,
+    current: &LaunchedVersion,
+) -> Option<RollbackInfo> {
+    let stored = defaults::string(UPDATE_VERIFICATION_KEY)
+        .and_then(|json| serde_json::from_str::<PendingVerification>(&json).ok());
+    let rollback = health.launch(stored, did_update, current);
+    persist_verification(health.pending());
+    rollback
+}
+
+fn persist_verification(pending: Option<&PendingVerification>) {
+    match pending.map(serde_json::to_string) {
+        Some(Ok(json)) => defaults::set_string(UPDATE_VERIFICATION_KEY, &json),
+        Some(Err(e)) => error!("Failed to record the update verification: {}", e),
+        None => defaults::remove(UPDATE_VERIFICATION_KEY),
+    }
+}
+
+fn set_updater_http_headers(updater: &SPUUpdater, headers: Option<HashMap<String, String>>) {
+    let ns_dict = headers.map(|h| {
+        let keys: Vec<Retained<NSString>> = h.keys().map(|k| NSString::from_str(k)).collect();
+        let values: Vec<Retained<NSString>> = h.values().map(|v| NSString::from_str(v)).collect();
+        let key_refs: Vec<&NSString> = keys.iter().map(|k| k.as_ref()).collect();
+        let value_refs: Vec<&NSString> = values.iter().map(|v| v.as_ref()).collect();
+        NSDictionary::from_slices(&key_refs, &value_refs)
+    });
+    updater.set_http_headers(ns_dict.as_deref());
+}
+
 pub struct SparkleUpdater<R: Runtime> {
-    #[allow(dead_code)]
     app: AppHandle<R>,
-    _controller: Retained<SPUStandardUpdaterController>,
-    controller_ptr: SendPtr<SPUStandardUpdaterController>,
+    /// Owns the updater and Sparkle's windows in [`UserDriverMode::Standard`].
+    _controller: Option<Retained<SPUStandardUpdaterController>>,
+    _updater: Retained<SPUUpdater>,
+    updater_ptr: SendPtr<SPUUpdater>,
     _delegate: Retained<SparkleDelegate>,
     delegate_ptr: SendPtr<SparkleDelegate>,
+    _user_driver: Option<Retained<HeadlessUserDriver>>,
+    user_driver_ptr: Option<SendPtr<HeadlessUserDriver>>,
+    user_driver_mode: UserDriverMode,
+    store: Option<Arc<dyn SettingsStore>>,
+    channels: Mutex<Vec<Channel>>,
+    feed_policy: FeedPolicy,
+    feed_url_policy: Option<Arc<dyn FeedUrlPolicy>>,
+    backend: Option<Arc<dyn UpdaterBackend>>,
+    start: Mutex<StartState>,
+    start_mode: StartMode,
+    install_blockers: InstallBlockers,
+    quit: Mutex<QuitCoordinator>,
+    quit_timeout: Duration,
+    whats_new: Mutex<WhatsNew>,
+    /// Set when `healthCheck` is configured.
+    health: Option<Mutex<HealthMonitor>>,
+    fetcher: Arc<dyn Fetcher>,
+    release_notes: ReleaseNotesService,
+    minimum_version_document: Option<SignedDocumentSource>,
+    revoked_releases_document: Option<SignedDocumentSource>,
 }
 
 // All operations dispatched to main thread via GCD
@@ -161,17 +679,17 @@ impl<R: Runtime> SparkleUpdater<R> {
     fn dispatch<T, F>(&self, f: F) -> T
     where
         T: Send,
-        F: FnOnce(&SPUStandardUpdaterController) -> T + Send,
+        F: FnOnce(&SPUUpdater) -> T + Send,
     {
-        let ptr = self.controller_ptr;
+        let ptr = self.updater_ptr;
         if MainThreadMarker::new().is_some() {
             // Already on main thread — call directly to avoid dispatch_sync deadlock
-            let controller = unsafe { ptr.as_ref() };
-            f(controller)
+            let updater = unsafe { ptr.as_ref() };
+            f(updater)
         } else {
             Queue::main().exec_sync(move || {
-                let controller = unsafe { ptr.as_ref() };
-                f(controller)
+                let updater = unsafe { ptr.as_ref() };
+                f(updater)
             })
         }
     }
@@ -194,27 +712,518 @@ impl<R: Runtime> SparkleUpdater<R> {
         }
     }
 
+    fn dispatch_user_driver<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send,
+        F: FnOnce(&HeadlessUserDriver) -> Result<T> + Send,
+    {
+        let ptr = self.user_driver_ptr.ok_or(Error::UserDriverNotHeadless)?;
+        if MainThreadMarker::new().is_some() {
+            // Already on main thread — call directly to avoid dispatch_sync deadlock
+            let user_driver = unsafe { ptr.as_ref() };
+            f(user_driver)
+        } else {
+            Queue::main().exec_sync(move || {
+                let user_driver = unsafe { ptr.as_ref() };
+                f(user_driver)
+            })
+        }
+    }
+
+    fn check_feed_url(&self, url: &str) -> Result<()> {
+        check_feed_url(&self.feed_policy, &self.feed_url_policy, url)
+    }
+
+    fn persist_settings(&self) -> Result<()> {
+        match &self.store {
+            Some(store) => store.save(&self.dispatch_delegate(|d| d.persisted_settings())),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts Sparkle's updater when it was created with [`StartMode::Deferred`]. Does
+    /// nothing if it is already running.
+    pub fn start_updater(&self) -> Result<()> {
+        let start = &self.start;
+        self.dispatch(move |u| {
+            if start.lock().unwrap().is_started() {
+                return Ok(());
+            }
+            // Not locked while Sparkle starts, as it may call back into the plugin.
+            let result = start_updater(u);
+            start.lock().unwrap().record(result)
+        })
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.start.lock().unwrap().is_started()
+    }
+
+    pub(crate) fn diagnostics_status(&self) -> (UpdaterStatus, PolicyStatus) {
+        let status = UpdaterStatus {
+            created: true,
+            started: self.is_started(),
+            start_mode: self.start_mode,
+            startup_error: self.start.lock().unwrap().error().map(str::to_string),
+            custom_backend: self.backend.is_some(),
+            user_driver: self.user_driver_mode,
+        };
+
+        let (automatically_checks, automatically_downloads, interval) = self.dispatch(|updater| {
+            (
+                updater.automatically_checks_for_updates(),
+                updater.automatically_downloads_updates(),
+                updater.update_check_interval(),
+            )
+        });
+        let policies = self.dispatch_delegate(|d| PolicyStatus {
+            may_check_for_updates: d.may_check_for_updates(),
+            should_proceed_with_update: d.should_proceed_with_update(),
+            prompt_for_permission: d.should_prompt_for_permission(),
+            automatically_checks_for_updates: automatically_checks,
+            automatically_downloads_updates: automatically_downloads,
+            update_check_interval: interval,
+            allowed_channels: d.allowed_channels(),
+            proceed_policy: d.has_proceed_policy(),
+            may_check_policies: d.may_check_policy_count(),
+            feed_url_policy: self.feed_url_policy.is_some(),
+            feed_policy: self.feed_policy.clone(),
+        });
+
+        (status, policies)
+    }
+
+    fn ensure_started(&self) -> Result<()> {
+        self.start.lock().unwrap().ensure_started()
+    }
+
+    /// Records the user's answer to the app's update consent prompt.
+    pub fn record_update_consent(&self, consent: UpdateConsent) -> Result<()> {
+        self.dispatch(move |updater| {
+            updater.set_automatically_checks_for_updates(consent.automatically_checks_for_updates);
+            updater.set_automatically_downloads_updates(consent.automatically_downloads_updates);
+        });
+        Ok(())
+    }
+
+    pub fn should_prompt_for_permission(&self) -> Result<bool> {
+        Ok(self.dispatch_delegate(|d| d.should_prompt_for_permission()))
+    }
+
+    pub fn set_should_prompt_for_permission(&self, enabled: bool) -> Result<()> {
+        self.dispatch_delegate(|d| d.set_should_prompt_for_permission(enabled));
+        Ok(())
+    }
+
+    pub fn user_driver_mode(&self) -> UserDriverMode {
+        self.user_driver_mode
+    }
+
+    /// Answers a question of the headless user driver.
+    pub fn reply_user_driver(&self, request_id: u64, reply: UserDriverReply) -> Result<()> {
+        self.dispatch_user_driver(move |driver| driver.reply(request_id, &reply))
+    }
+
+    /// Questions of the headless user driver that are waiting for an answer, e.g. to
+    /// restore the update UI after the webview reloaded.
+    pub fn pending_user_driver_requests(&self) -> Result<Vec<PendingRequest>> {
+        self.dispatch_user_driver(|driver| Ok(driver.pending_requests()))
+    }
+
+    /// Cancels the running user-initiated check or download. Requires the headless user
+    /// driver.
+    pub fn cancel_update(&self) -> Result<()> {
+        self.answer_user_driver(
+            &[PromptKind::Download, PromptKind::UserInitiatedCheck],
+            UserDriverReply::Cancel,
+            "cancel",
+        )
+    }
+
+    /// Skips the update the user is being asked about. Requires the headless user driver.
+    pub fn skip_update(&self) -> Result<()> {
+        self.answer_user_driver(
+            &[PromptKind::UpdateFound, PromptKind::ReadyToInstall],
+            UserDriverReply::Skip,
+            "skip",
+        )
+    }
+
+    /// Dismisses the update the user is being asked about until the next scheduled check.
+    /// Requires the headless user driver.
+    pub fn remind_later(&self) -> Result<()> {
+        self.answer_user_driver(
+            &[PromptKind::UpdateFound, PromptKind::ReadyToInstall],
+            UserDriverReply::RemindLater,
+            "remind later",
+        )
+    }
+
+    /// The minimum supported version the running build is below, if any.
+    pub fn update_required(&self) -> Option<UpdateRequiredInfo> {
+        self.dispatch_delegate(|d| d.update_required())
+    }
+
+    /// Downloads the signed minimum version document again, if one is configured, and
+    /// returns whether an update is required.
+    pub fn check_minimum_version(&self) -> Result<Option<UpdateRequiredInfo>> {
+        if let Some(source) = &self.minimum_version_document {
+            let document = source.fetch::<MinimumVersionDocument>(&*self.fetcher)?;
+            self.dispatch_delegate(move |d| {
+                d.declare_minimum_version(
+                    &document.minimum_version,
+                    document.message,
+                    MinimumVersionSource::Document,
+                )
+            });
+        }
+        Ok(self.update_required())
+    }
+
+    /// The release notes of `update` as sanitized HTML, downloading them if they are not
+    /// inline. Rendered notes are cached per build version.
+    pub fn release_notes(&self, update: &UpdateInfo) -> Result<ReleaseNotes> {
+        self.release_notes.notes(update)
+    }
+
+    /// The notes of every release between the running build, or `current_version`, and
+    /// the newest release, or `target_version`, so users see everything an update skips.
+    /// Only the default channel and the allowed channels are considered.
+    pub fn cumulative_release_notes(
+        &self,
+        options: CumulativeNotesOptions,
+    ) -> Result<CumulativeReleaseNotes> {
+        let releases = match (options.appcast_xml, options.appcast_url) {
+            (Some(xml), _) => parse_releases(&xml)?,
+            (None, Some(url)) => self.fetch_releases(&url)?,
+            (None, None) => match self.dispatch_delegate(|d| d.appcast_releases()) {
+                Some(releases) => releases,
+                None => {
+                    let url = match self.feed_url_override()? {
+                        Some(url) => url,
+                        None => self.feed_url()?.ok_or_else(|| {
+                            Error::InvalidAppcast("no appcast loaded and no feed URL".to_string())
+                        })?,
+                    };
+                    self.fetch_releases(&url)?
+                }
+            },
+        };
+        let (installed, channels, comparator) = self.dispatch_delegate(|d| {
+            (
+                d.installed_version(),
+                d.allowed_channels().unwrap_or_default(),
+                d.version_comparator(),
+            )
+        });
+        let current = options.current_version.or(installed).unwrap_or_default();
+        self.release_notes.cumulative(
+            &*comparator,
+            &releases,
+            &channels,
+            &current,
+            options.target_version.as_deref(),
+            options.include_target,
+        )
+    }
+
+    fn fetch_releases(&self, url: &str) -> Result<Vec<AppcastRelease>> {
+        let xml = self.fetcher.fetch(url)?;
+        parse_releases(&String::from_utf8_lossy(&xml))
+    }
+
+    /// The revocation of the running build, if it has been revoked.
+    pub fn current_version_revoked(&self) -> Option<CurrentVersionRevoked> {
+        self.dispatch_delegate(|d| d.current_version_revoked())
+    }
+
+    /// Downloads the signed revoked releases document again, if one is configured, and
+    /// returns whether the running build is revoked.
+    pub fn check_revoked_releases(&self) -> Result<Option<CurrentVersionRevoked>> {
+        if let Some(source) = &self.revoked_releases_document {
+            let document = source.fetch::<RevokedReleasesDocument>(&*self.fetcher)?;
+            self.dispatch_delegate(move |d| {
+                d.set_revoked_releases(RevocationSource::Document, document.revoked)
+            });
+        }
+        Ok(self.current_version_revoked())
+    }
+
+    /// Starts installing the update the running build requires. With the headless user
+    /// driver, every question about installing it is answered with install, so the update
+    /// cannot be skipped or postponed. Sparkle's own windows still offer to skip.
+    pub fn install_required_update(&self) -> Result<()> {
+        if self.update_required().is_none() {
+            return Err(Error::NoMatchingSession(
+                "install a required update".to_string(),
+            ));
+        }
+        match self.install_update_now() {
+            Err(Error::NoMatchingSession(_)) => {}
+            result => return result,
+        }
+
+        if self.user_driver_ptr.is_some() {
+            let app = self.app.clone();
+            self.dispatch_user_driver(move |driver| {
+                driver.on_install_prompt(Arc::new(move || {
+                    let Some(updater) = app.try_state::<SparkleUpdater<R>>() else {
+                        return;
+                    };
+                    if let Err(e) = updater.install_update_now() {
+                        warn!("Failed to install the required update: {}", e);
+                    }
+                }));
+                Ok(())
+            })?;
+        }
+        self.check_for_updates()
+    }
+
+    /// Installs an update right away: the one the headless user driver is asking about, or
+    /// else the downloaded update Sparkle would install on quit or whose install a
+    /// pre-install hook aborted. While install blockers are held, the install is deferred
+    /// until the last one is released. The pre-install hooks run before Sparkle installs.
+    pub fn install_update_now(&self) -> Result<()> {
+        let driver_ptr = self.user_driver_ptr;
+        let has_update = self.dispatch_delegate(move |d| {
+            let driver = driver_ptr.map(|ptr| unsafe { ptr.as_ref() });
+            driver.is_some_and(|driver| driver.is_asking(INSTALL_PROMPTS))
+                || d.has_pending_install()
+        });
+        if !has_update {
+            return Err(Error::NoMatchingSession("install".to_string()));
+        }
+
+        let delegate_ptr = self.delegate_ptr;
+        let installed = self.install_blockers.run_or_defer(move || {
+            Queue::main().exec_async(move || {
+                let driver = driver_ptr.map(|ptr| unsafe { ptr.as_ref() });
+                let delegate = unsafe { delegate_ptr.as_ref() };
+                if !install_now(driver, delegate) {
+                    warn!("The update to install went away while installation was blocked");
+                }
+            });
+        });
+        if !installed {
+            let status = self.install_blockers.status();
+            self.dispatch_delegate(move |d| d.emit(EVENT_INSTALL_DEFERRED, &status));
+        }
+        Ok(())
+    }
+
+    /// Blocks installation until the returned guard is dropped, e.g. while a document has
+    /// unsaved changes.
+    pub fn block_install(&self, reason: impl Into<String>) -> InstallBlocker {
+        self.install_blockers.block(reason)
+    }
+
+    /// Token-based [`block_install`](Self::block_install) for the webview.
+    pub fn acquire_install_blocker(&self, reason: String) -> u64 {
+        self.install_blockers.acquire(reason)
+    }
+
+    pub fn release_install_blocker(&self, token: u64) -> Result<()> {
+        self.install_blockers.release(token)
+    }
+
+    pub fn install_block_status(&self) -> InstallBlockStatus {
+        self.install_blockers.status()
+    }
+
+    /// Decides Tauri's `ExitRequested`. Returns whether the exit must be prevented so the
+    /// webview can save, and install blockers be released, before Sparkle installs the
+    /// staged update.
+    pub(crate) fn handle_exit_requested(&self, code: Option<i32>) -> bool {
+        let install_pending = self.dispatch_delegate(|d| d.has_immediate_installation());
+        let blocked = self.install_blockers.is_blocked();
+        let decision = self
+            .quit
+            .lock()
+            .unwrap()
+            .exit_requested(code, install_pending, blocked);
+        match decision {
+            ExitDecision::Proceed => false,
+            ExitDecision::Hold { notify } => {
+                if notify {
+                    let info = BeforeInstallQuitInfo {
+                        timeout: self.quit_timeout.as_secs_f64(),
+                        blocked_by: self.install_blockers.status().blocked_by,
+                    };
+                    self.dispatch_delegate(move |d| d.emit(EVENT_BEFORE_INSTALL_QUIT, &info));
+                    if self.quit.lock().unwrap().is_saving() {
+                        let app = self.app.clone();
+                        Queue::main().exec_after(self.quit_timeout, move || {
+                            if let Some(updater) = app.try_state::<SparkleUpdater<R>>() {
+                                updater.finish_install_save();
+                            }
+                        });
+                    } else {
+                        self.wait_for_unblocked();
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Tells the plugin the webview has saved its state after `before-install-quit`. The
+    /// app quits once no install blockers are held.
+    pub fn ready_to_quit(&self) -> Result<()> {
+        if !self.quit.lock().unwrap().is_saving() {
+            return Err(Error::NoMatchingSession("quit".to_string()));
+        }
+        self.finish_install_save();
+        Ok(())
+    }
+
+    /// Ends the save window. The app quits right away, or once the last install blocker is
+    /// released; the save timeout does not cut blockers short.
+    fn finish_install_save(&self) {
+        let (code, waiting) = {
+            let mut quit = self.quit.lock().unwrap();
+            if !quit.is_saving() {
+                return;
+            }
+            let code = quit.save_finished(self.install_blockers.is_blocked());
+            (code, quit.is_waiting_for_blockers())
+        };
+        if let Some(code) = code {
+            self.app.exit(code);
+        } else if waiting {
+            self.wait_for_unblocked();
+        }
+    }
+
+    /// Quits once the last install blocker is released, right away if it already was.
+    fn wait_for_unblocked(&self) {
+        let app = self.app.clone();
+        self.install_blockers.when_unblocked(move || {
+            if let Some(updater) = app.try_state::<SparkleUpdater<R>>() {
+                updater.finish_install_unblocked();
+            }
+        });
+    }
+
+    fn finish_install_unblocked(&self) {
+        let code = self.quit.lock().unwrap().unblocked();
+        if let Some(code) = code {
+            self.app.exit(code);
+        }
+    }
+
+    fn answer_user_driver(
+        &self,
+        kinds: &'static [PromptKind],
+        reply: UserDriverReply,
+        action: &str,
+    ) -> Result<()> {
+        if self.dispatch_user_driver(move |driver| driver.answer(kinds, &reply))? {
+            Ok(())
+        } else {
+            Err(Error::NoMatchingSession(action.to_string()))
+        }
+    }
+
     pub fn check_for_updates(&self) -> Result<()> {
-        self.dispatch(|c| c.check_for_updates(None));
+        if let Some(backend) = &self.backend {
+            return backend.check_for_updates();
+        }
+        self.ensure_started()?;
+        self.dispatch(|u| u.check_for_updates());
         Ok(())
     }
 
+    /// Checks for updates without showing UI unless one is found. While the running build
+    /// is revoked, the check is made user-initiated instead.
     pub fn check_for_updates_in_background(&self) -> Result<()> {
-        self.dispatch(|c| c.updater().check_for_updates_in_background());
+        if let Some(backend) = &self.backend {
+            return backend.check_for_updates_in_background();
+        }
+        self.ensure_started()?;
+        if self.dispatch_delegate(|d| d.should_escalate_check(UpdateCheck::Background)) {
+            self.dispatch(|u| u.check_for_updates());
+        } else {
+            self.dispatch(|u| u.check_for_updates_in_background());
+        }
         Ok(())
     }
 
     pub fn can_check_for_updates(&self) -> Result<bool> {
-        Ok(self.dispatch(|c| c.updater().can_check_for_updates()))
+        if let Some(backend) = &self.backend {
+            return backend.can_check_for_updates();
+        }
+        Ok(self.dispatch(|u| u.can_check_for_updates()))
     }
 
     pub fn current_version(&self) -> Result<String> {
         Ok(self.app.package_info().version.to_string())
     }
 
+    /// The update this launch follows, if the previous launch ran an older build.
+    pub fn did_update(&self) -> Option<DidUpdateInfo> {
+        self.whats_new.lock().unwrap().update().cloned()
+    }
+
+    /// Returns the update this launch follows the first time `window` asks, so every window
+    /// shows its release notes exactly once.
+    pub fn consume_whats_new(&self, window: &str) -> Option<DidUpdateInfo> {
+        self.whats_new.lock().unwrap().consume(window)
+    }
+
+    /// Confirms the running update works, ending its verification. Returns `false` when no
+    /// verification was pending or `healthCheck` is not configured.
+    pub fn mark_update_healthy(&self) -> bool {
+        let Some(health) = &self.health else {
+            return false;
+        };
+        let mut health = health.lock().unwrap();
+        let marked = health.mark_healthy();
+        if marked {
+            persist_verification(None);
+        }
+        marked
+    }
+
+    /// The pending update verification and the rollback recommended during this launch.
+    pub fn update_health(&self) -> UpdateHealth {
+        self.health
+            .as_ref()
+            .map(|health| health.lock().unwrap().status())
+            .unwrap_or_default()
+    }
+
+    fn health_timed_out(&self) {
+        let rollback = self
+            .health
+            .as_ref()
+            .and_then(|health| health.lock().unwrap().timed_out());
+        if let Some(info) = rollback {
+            self.recommend_rollback(info);
+        }
+    }
+
+    /// Points the feed at the rollback channel, if configured, and emits
+    /// `sparkle://rollback-recommended`.
+    fn recommend_rollback(&self, info: RollbackInfo) {
+        warn!(
+            "Update to {} looks unhealthy ({:?}), rolling back to {} is recommended",
+            info.current.build_version, info.reason, info.previous.build_version
+        );
+        if let Some(channel) = &info.rollback_channel {
+            let result = self.allowed_channels().and_then(|allowed| {
+                self.set_allowed_channels(Some(channels::enroll(allowed, channel)))
+            });
+            if let Err(e) = result {
+                error!("Failed to switch to rollback channel {}: {}", channel, e);
+            }
+        }
+        self.dispatch_delegate(move |d| d.emit(EVENT_ROLLBACK_RECOMMENDED, &info));
+    }
+
     pub fn feed_url(&self) -> Result<Option<String>> {
-        Ok(self.dispatch(|c| {
-            c.updater().feed_url().and_then(|url| {
+        Ok(self.dispatch(|u| {
+            u.feed_url().and_then(|url| {
                 let abs: Option<Retained<NSString>> =
                     unsafe { objc2::msg_send![&url, absoluteString] };
                 abs.map(|s| s.to_string())
@@ -223,41 +1232,41 @@ impl<R: Runtime> SparkleUpdater<R> {
     }
 
     pub fn set_feed_url(&self, url: &str) -> Result<()> {
-        url::Url::parse(url).map_err(|_| Error::InvalidFeedUrl(url.to_string()))?;
+        self.check_feed_url(url)?;
         let url_string = url.to_string();
 
-        self.dispatch(move |c| {
+        self.dispatch(move |u| {
             let ns_string = NSString::from_str(&url_string);
             let ns_url: Option<Retained<NSURL>> =
                 unsafe { objc2::msg_send![NSURL::class(), URLWithString: &*ns_string] };
             if let Some(url) = ns_url {
-                c.updater().set_feed_url(Some(&url));
+                u.set_feed_url(Some(&url));
             }
         });
         Ok(())
     }
 
     pub fn automatically_checks_for_updates(&self) -> Result<bool> {
-        Ok(self.dispatch(|c| c.updater().automatically_checks_for_updates()))
+        Ok(self.dispatch(|u| u.automatically_checks_for_updates()))
     }
 
     pub fn set_automatically_checks_for_updates(&self, enabled: bool) -> Result<()> {
-        self.dispatch(|c| c.updater().set_automatically_checks_for_updates(enabled));
+        self.dispatch(|u| u.set_automatically_checks_for_updates(enabled));
         Ok(())
     }
 
     pub fn automatically_downloads_updates(&self) -> Result<bool> {
-        Ok(self.dispatch(|c| c.updater().automatically_downloads_updates()))
+        Ok(self.dispatch(|u| u.automatically_downloads_updates()))
     }
 
     pub fn set_automatically_downloads_updates(&self, enabled: bool) -> Result<()> {
-        self.dispatch(|c| c.updater().set_automatically_downloads_updates(enabled));
+        self.dispatch(|u| u.set_automatically_downloads_updates(enabled));
         Ok(())
     }
 
     pub fn last_update_check_date(&self) -> Result<Option<f64>> {
-        Ok(self.dispatch(|c| {
-            c.updater().last_update_check_date().map(|date| {
+        Ok(self.dispatch(|u| {
+            u.last_update_check_date().map(|date| {
                 let seconds: f64 = unsafe { objc2::msg_send![&date, timeIntervalSince1970] };
                 seconds * 1000.0
             })
@@ -265,31 +1274,35 @@ impl<R: Runtime> SparkleUpdater<R> {
     }
 
     pub fn reset_update_cycle(&self) -> Result<()> {
-        self.dispatch(|c| c.updater().reset_update_cycle());
+        self.dispatch(|u| u.reset_update_cycle());
         Ok(())
     }
 
     pub fn update_check_interval(&self) -> Result<f64> {
-        Ok(self.dispatch(|c| c.updater().update_check_interval()))
+        Ok(self.dispatch(|u| u.update_check_interval()))
     }
 
     pub fn set_update_check_interval(&self, interval: f64) -> Result<()> {
-        self.dispatch(|c| c.updater().set_update_check_interval(interval));
+        self.dispatch(|u| u.set_update_check_interval(interval));
         Ok(())
     }
 
     pub fn check_for_update_information(&self) -> Result<()> {
-        self.dispatch(|c| c.updater().check_for_update_information());
+        if let Some(backend) = &self.backend {
+            return backend.check_for_update_information();
+        }
+        self.ensure_started()?;
+        self.dispatch(|u| u.check_for_update_information());
         Ok(())
     }
 
     pub fn session_in_progress(&self) -> Result<bool> {
-        Ok(self.dispatch(|c| c.updater().session_in_progress()))
+        Ok(self.dispatch(|u| u.session_in_progress()))
     }
 
     pub fn http_headers(&self) -> Result<Option<HashMap<String, String>>> {
-        Ok(self.dispatch(|c| {
-            c.updater().http_headers().map(|dict| {
+        Ok(self.dispatch(|u| {
+            u.http_headers().map(|dict| {
                 let mut map = HashMap::new();
                 let count: usize = unsafe { objc2::msg_send![&dict, count] };
                 if count > 0 {
@@ -310,57 +1323,44 @@ impl<R: Runtime> SparkleUpdater<R> {
     }
 
     pub fn set_http_headers(&self, headers: Option<HashMap<String, String>>) -> Result<()> {
-        self.dispatch(move |c| {
-            let ns_dict = headers.map(|h| {
-                let keys: Vec<Retained<NSString>> =
-                    h.keys().map(|k| NSString::from_str(k)).collect();
-                let values: Vec<Retained<NSString>> =
-                    h.values().map(|v| NSString::from_str(v)).collect();
-                let key_refs: Vec<&NSString> = keys.iter().map(|k| k.as_ref()).collect();
-                let value_refs: Vec<&NSString> = values.iter().map(|v| v.as_ref()).collect();
-                NSDictionary::from_slices(&key_refs, &value_refs)
-            });
-            c.updater().set_http_headers(ns_dict.as_deref());
-        });
+        self.dispatch(move |u| set_updater_http_headers(u, headers));
         Ok(())
     }
 
     pub fn user_agent_string(&self) -> Result<String> {
-        Ok(self.dispatch(|c| c.updater().user_agent_string().to_string()))
+        Ok(self.dispatch(|u| u.user_agent_string().to_string()))
     }
 
     pub fn set_user_agent_string(&self, user_agent: &str) -> Result<()> {
         let ua = user_agent.to_string();
-        self.dispatch(move |c| {
+        self.dispatch(move |u| {
             let ns_string = NSString::from_str(&ua);
-            c.updater().set_user_agent_string(&ns_string);
+            u.set_user_agent_string(&ns_string);
         });
         Ok(())
     }
 
     pub fn sends_system_profile(&self) -> Result<bool> {
-        Ok(self.dispatch(|c| c.updater().sends_system_profile()))
+        Ok(self.dispatch(|u| u.sends_system_profile()))
     }
 
     pub fn set_sends_system_profile(&self, sends: bool) -> Result<()> {
-        self.dispatch(|c| c.updater().set_sends_system_profile(sends));
+        self.dispatch(|u| u.set_sends_system_profile(sends));
         Ok(())
     }
 
     pub fn clear_feed_url_from_user_defaults(&self) -> Result<Option<String>> {
-        Ok(self.dispatch(|c| {
-            c.updater()
-                .clear_feed_url_from_user_defaults()
-                .and_then(|url| {
-                    let abs: Option<Retained<NSString>> =
-                        unsafe { objc2::msg_send![&url, absoluteString] };
-                    abs.map(|s| s.to_string())
-                })
+        Ok(self.dispatch(|u| {
+            u.clear_feed_url_from_user_defaults().and_then(|url| {
+                let abs: Option<Retained<NSString>> =
+                    unsafe { objc2::msg_send![&url, absoluteString] };
+                abs.map(|s| s.to_string())
+            })
         }))
     }
 
     pub fn reset_update_cycle_after_short_delay(&self) -> Result<()> {
-        self.dispatch(|c| c.updater().reset_update_cycle_after_short_delay());
+        self.dispatch(|u| u.reset_update_cycle_after_short_delay());
         Ok(())
     }
 
@@ -370,7 +1370,89 @@ impl<R: Runtime> SparkleUpdater<R> {
 
     pub fn set_allowed_channels(&self, channels: Option<Vec<String>>) -> Result<()> {
         self.dispatch_delegate(|d| d.set_allowed_channels(channels));
-        Ok(())
+        self.persist_settings()
+    }
+
+    /// Declares the channels users can enroll in with [`SparkleUpdater::enroll`].
+    pub fn register_channels(&self, channels: Vec<Channel>) {
+        *self.channels.lock().unwrap() = channels;
+    }
+
+    pub fn channels(&self) -> Result<Vec<Channel>> {
+        Ok(self.channels.lock().unwrap().clone())
+    }
+
+    pub fn current_enrollment(&self) -> Result<Vec<Channel>> {
+        let enrolled = self.allowed_channels()?.unwrap_or_default();
+        Ok(channels::resolve(&self.channels.lock().unwrap(), &enrolled))
+    }
+
+    /// Enrolls in a declared channel, persisting the choice and emitting
+    /// `sparkle://channel-changed`.
+    ///
+    /// With `reset_update_cycle`, the update cycle is reset so the channel is checked right away.
+    pub fn enroll(&self, channel: &str, reset_update_cycle: bool) -> Result<ChannelChange> {
+        self.ensure_known_channel(channel)?;
+        let channels = channels::enroll(self.allowed_channels()?, channel);
+        self.set_allowed_channels(Some(channels))?;
+        self.finish_channel_change(channel, true, reset_update_cycle)
+    }
+
+    /// Leaves a channel. The returned change reports whether the running build is now ahead
+    /// of the latest build on the default channel.
+    pub fn leave(&self, channel: &str, reset_update_cycle: bool) -> Result<ChannelChange> {
+        self.ensure_known_channel(channel)?;
+        let channels = channels::leave(self.allowed_channels()?, channel);
+        self.set_allowed_channels(channels)?;
+        self.finish_channel_change(channel, false, reset_update_cycle)
+    }
+
+    fn ensure_known_channel(&self, channel: &str) -> Result<()> {
+        if self
+            .channels
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.id == channel)
+        {
+            Ok(())
+        } else {
+            Err(Error::UnknownChannel(channel.to_string()))
+        }
+    }
+
+    fn finish_channel_change(
+        &self,
+        channel: &str,
+        enrolled: bool,
+        reset_update_cycle: bool,
+    ) -> Result<ChannelChange> {
+        let (installed, stable, comparator) = self.dispatch_delegate(|d| {
+            (
+                d.installed_version(),
+                d.latest_stable_version(),
+                d.version_comparator(),
+            )
+        });
+        let ahead_of_stable = installed
+            .as_deref()
+            .and_then(|v| channels::is_ahead_of_stable(&*comparator, v, stable.as_deref()))
+            .map(|ahead| ahead && !enrolled);
+
+        let change = ChannelChange {
+            channel: channel.to_string(),
+            enrolled,
+            enrollment: self.current_enrollment()?,
+            ahead_of_stable,
+            stable_version: stable,
+        };
+        let payload = change.clone();
+        self.dispatch_delegate(move |d| d.emit(EVENT_CHANNEL_CHANGED, &payload));
+
+        if reset_update_cycle {
+            self.reset_update_cycle()?;
+        }
+        Ok(change)
     }
 
     pub fn feed_url_override(&self) -> Result<Option<String>> {
@@ -378,8 +1460,11 @@ impl<R: Runtime> SparkleUpdater<R> {
     }
 
     pub fn set_feed_url_override(&self, url: Option<String>) -> Result<()> {
+        if let Some(url) = &url {
+            self.check_feed_url(url)?;
+        }
         self.dispatch_delegate(|d| d.set_feed_url_override(url));
-        Ok(())
+        self.persist_settings()
     }
 
     pub fn feed_parameters(&self) -> Result<Option<HashMap<String, String>>> {
@@ -388,7 +1473,7 @@ impl<R: Runtime> SparkleUpdater<R> {
 
     pub fn set_feed_parameters(&self, params: Option<HashMap<String, String>>) -> Result<()> {
         self.dispatch_delegate(|d| d.set_feed_parameters(params));
-        Ok(())
+        self.persist_settings()
     }
 
     pub fn should_download_release_notes(&self) -> Result<bool> {
@@ -405,8 +1490,8 @@ impl<R: Runtime> SparkleUpdater<R> {
     }
 
     pub fn set_should_relaunch_application(&self, enabled: bool) -> Result<()> {
-        self.dispatch_delegate(|d| d.set_should_relaunch(enabled));
-        Ok(())
+        self.dispatch_delegate(|d| d.override_should_relaunch(enabled));
+        self.persist_settings()
     }
 
     pub fn may_check_for_updates_config(&self) -> Result<bool> {
@@ -427,6 +1512,27 @@ impl<R: Runtime> SparkleUpdater<R> {
         Ok(())
     }
 
+    /// Registers a policy consulted for every update Sparkle is about to proceed with.
+    ///
+    /// Runs after the `should_proceed_with_update` setting and the downgrade checks.
+    /// Pass `None` to remove it.
+    pub fn set_proceed_policy(&self, policy: Option<ProceedPolicy>) {
+        self.dispatch_delegate(|d| d.set_proceed_policy(policy))
+    }
+
+    /// Appends a policy to the chain consulted before every update check.
+    ///
+    /// Policies run in registration order after the `may_check_for_updates_config` setting,
+    /// and the first denial skips the check.
+    pub fn add_may_check_policy(&self, policy: impl MayCheckPolicy + 'static) {
+        let policy: Arc<dyn MayCheckPolicy> = Arc::new(policy);
+        self.dispatch_delegate(move |d| d.add_may_check_policy(policy))
+    }
+
+    pub fn clear_may_check_policies(&self) {
+        self.dispatch_delegate(|d| d.clear_may_check_policies())
+    }
+
     pub fn decryption_password(&self) -> Result<Option<String>> {
         Ok(self.dispatch_delegate(|d| d.decryption_password()))
     }
@@ -453,6 +1559,6 @@ impl<R: Runtime> SparkleUpdater<R> {
         headers: Option<HashMap<String, String>>,
     ) -> Result<()> {
         self.dispatch_delegate(|d| d.set_download_request_headers(headers));
-        Ok(())
+        self.persist_settings()
     }
 }
@@ -0,0 +1,382 @@
+use std::cell::RefCell;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use block2::{Block, RcBlock};
+use dispatch::Queue;
+use log::warn;
+use objc2::rc::Retained;
+use objc2::runtime::NSObject;
+use objc2::{
+    define_class, msg_send, AllocAnyThread, DeclaredClass, MainThreadMarker, MainThreadOnly,
+    Message,
+};
+use objc2_foundation::{NSData, NSNumber, NSString};
+
+use super::bindings::{SPUAppcastItem, SUUpdatePermissionResponse};
+use super::delegate::{error_payload, update_info_from_item, SparkleDelegate};
+use crate::events::{
+    AcknowledgementInfo, EmptyPayload, ExtractionProgressInfo, InstallingInfo, ReleaseNotesInfo,
+    UpdateFoundInfo, UpdateInstalledInfo, UserDriverRequestInfo, EVENT_DISMISS_UPDATE_INSTALLATION,
+    EVENT_DOWNLOAD_PROGRESS, EVENT_SHOW_DOWNLOAD_INITIATED, EVENT_SHOW_EXTRACTING_UPDATE,
+    EVENT_SHOW_EXTRACTION_PROGRESS, EVENT_SHOW_INSTALLING_UPDATE, EVENT_SHOW_READY_TO_INSTALL,
+    EVENT_SHOW_UPDATER_ERROR, EVENT_SHOW_UPDATE_FOUND, EVENT_SHOW_UPDATE_INSTALLED,
+    EVENT_SHOW_UPDATE_IN_FOCUS, EVENT_SHOW_UPDATE_NOT_FOUND, EVENT_SHOW_UPDATE_PERMISSION_REQUEST,
+    EVENT_SHOW_UPDATE_RELEASE_NOTES, EVENT_SHOW_UPDATE_RELEASE_NOTES_FAILED,
+    EVENT_SHOW_USER_INITIATED_UPDATE_CHECK,
+};
+use crate::progress::DownloadProgress;
+use crate::user_driver::{PendingRequest, PromptKind, ReplyRouter, UserDriverReply};
+use crate::Result;
+
+/// `SPUUserUpdateChoice`
+const CHOICE_SKIP: isize = 0;
+const CHOICE_INSTALL: isize = 1;
+const CHOICE_DISMISS: isize = 2;
+
+/// One of Sparkle's reply blocks.
+enum Responder {
+    Permission(RcBlock<dyn Fn(NonNull<SUUpdatePermissionResponse>)>),
+    Choice(RcBlock<dyn Fn(isize)>),
+    /// Cancellation, acknowledgement or retry.
+    Action(RcBlock<dyn Fn()>),
+}
+
+impl Responder {
+    /// Calls the block. `reply` has been checked against the question by the router.
+    fn respond(self, reply: &UserDriverReply) {
+        match self {
+            Responder::Permission(block) => {
+                let UserDriverReply::Permission {
+                    consent,
+                    send_system_profile,
+                } = reply
+                else {
+                    warn!("Ignoring {:?} for a permission request", reply);
+                    return;
+                };
+                let downloads = NSNumber::new_bool(consent.automatically_downloads_updates);
+                let response = SUUpdatePermissionResponse::init_with_automatic_update_checks(
+                    SUUpdatePermissionResponse::alloc(),
+                    consent.automatically_checks_for_updates,
+                    Some(&downloads),
+                    *send_system_profile,
+                );
+                block.call((NonNull::from(&*response),));
+            }
+            Responder::Choice(block) => {
+                let choice = match reply {
+                    UserDriverReply::Install => CHOICE_INSTALL,
+                    UserDriverReply::Skip => CHOICE_SKIP,
+                    _ => CHOICE_DISMISS,
+                };
+                block.call((choice,));
+            }
+            Responder::Action(block) => block.call(()),
+        }
+    }
+}
+
+/// Keeps the driver alive until a reply timeout fires.
+struct MainQueueDriver(Retained<HeadlessUserDriver>);
+
+// Only used and released on the main queue.
+unsafe impl Send for MainQueueDriver {}
+
+impl MainQueueDriver {
+    fn expire_replies(self) {
+        self.0.expire_replies();
+    }
+}
+
+pub struct UserDriverIvars {
+    delegate: Retained<SparkleDelegate>,
+    router: RefCell<ReplyRouter<Responder>>,
+    progress: RefCell<DownloadProgress>,
+    /// Called when Sparkle asks whether to install, instead of waiting for the webview.
+    install_prompt_handler: RefCell<Option<Arc<dyn Fn()>>>,
+}
+
+define_class!(
+    /// `SPUUserDriver` that forwards every UI decision to the webview as events.
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "TauriSparkleUserDriver"]
+    #[ivars = UserDriverIvars]
+    pub struct HeadlessUserDriver;
+
+    impl HeadlessUserDriver {
+        #[unsafe(method(showUpdatePermissionRequest:reply:))]
+        fn show_update_permission_request(
+            &self,
+            _request: &NSObject,
+            reply: &Block<dyn Fn(NonNull<SUUpdatePermissionResponse>)>,
+        ) {
+            let request_id =
+                self.register(PromptKind::Permission, Responder::Permission(reply.copy()));
+            self.emit(EVENT_SHOW_UPDATE_PERMISSION_REQUEST, &UserDriverRequestInfo { request_id });
+        }
+
+        #[unsafe(method(showUserInitiatedUpdateCheckWithCancellation:))]
+        fn show_user_initiated_update_check(&self, cancellation: &Block<dyn Fn()>) {
+            let request_id = self.register(
+                PromptKind::UserInitiatedCheck,
+                Responder::Action(cancellation.copy()),
+            );
+            self.emit(
+                EVENT_SHOW_USER_INITIATED_UPDATE_CHECK,
+                &UserDriverRequestInfo { request_id },
+            );
+        }
+
+        #[unsafe(method(showUpdateFoundWithAppcastItem:state:reply:))]
+        fn show_update_found(
+            &self,
+            item: &SPUAppcastItem,
+            state: &NSObject,
+            reply: &Block<dyn Fn(isize)>,
+        ) {
+            self.discard(&[PromptKind::UserInitiatedCheck]);
+            self.ivars().progress.borrow_mut().set_fallback_length(item.content_length());
+            let stage: isize = unsafe { msg_send![state, stage] };
+            let user_initiated: bool = unsafe { msg_send![state, userInitiated] };
+            let request_id =
+                self.register(PromptKind::UpdateFound, Responder::Choice(reply.copy()));
+            self.emit(EVENT_SHOW_UPDATE_FOUND, &UpdateFoundInfo {
+                request_id,
+                update: update_info_from_item(item),
+                stage: match stage {
+                    0 => "notDownloaded",
+                    1 => "downloaded",
+                    _ => "installing",
+                }
+                .to_string(),
+                user_initiated,
+            });
+            self.handle_install_prompt();
+        }
+
+        #[unsafe(method(showUpdateReleaseNotesWithDownloadData:))]
+        fn show_update_release_notes(&self, download_data: &NSObject) {
+            let data: Retained<NSData> = unsafe { msg_send![download_data, data] };
+            let mime_type: Option<Retained<NSString>> =
+                unsafe { msg_send![download_data, MIMEType] };
+            self.emit(EVENT_SHOW_UPDATE_RELEASE_NOTES, &ReleaseNotesInfo {
+                notes: String::from_utf8_lossy(&data.to_vec()).into_owned(),
+                mime_type: mime_type.map(|s| s.to_string()),
+            });
+        }
+
+        #[unsafe(method(showUpdateReleaseNotesFailedToDownloadWithError:))]
+        fn show_update_release_notes_failed(&self, error: &NSObject) {
+            self.emit(EVENT_SHOW_UPDATE_RELEASE_NOTES_FAILED, &error_payload(error));
+        }
+
+        #[unsafe(method(showUpdateNotFoundWithError:acknowledgement:))]
+        fn show_update_not_found(&self, error: &NSObject, acknowledgement: &Block<dyn Fn()>) {
+            self.discard(&[PromptKind::UserInitiatedCheck]);
+            let request_id = self.register(
+                PromptKind::UpdateNotFound,
+                Responder::Action(acknowledgement.copy()),
+            );
+            self.emit(EVENT_SHOW_UPDATE_NOT_FOUND, &AcknowledgementInfo {
+                request_id,
+                error: error_payload(error),
+            });
+        }
+
+        #[unsafe(method(showUpdaterError:acknowledgement:))]
+        fn show_updater_error(&self, error: &NSObject, acknowledgement: &Block<dyn Fn()>) {
+            self.discard(&[PromptKind::UserInitiatedCheck, PromptKind::Download]);
+            let request_id = self.register(
+                PromptKind::UpdaterError,
+                Responder::Action(acknowledgement.copy()),
+            );
+            self.emit(EVENT_SHOW_UPDATER_ERROR, &AcknowledgementInfo {
+                request_id,
+                error: error_payload(error),
+            });
+        }
+
+        #[unsafe(method(showDownloadInitiatedWithCancellation:))]
+        fn show_download_initiated(&self, cancellation: &Block<dyn Fn()>) {
+            let request_id =
+                self.register(PromptKind::Download, Responder::Action(cancellation.copy()));
+            self.emit(EVENT_SHOW_DOWNLOAD_INITIATED, &UserDriverRequestInfo { request_id });
+        }
+
+        #[unsafe(method(showDownloadDidReceiveExpectedContentLength:))]
+        fn show_download_did_receive_expected_content_length(&self, content_length: u64) {
+            let info = self.ivars().progress.borrow_mut().start(content_length, Instant::now());
+            self.emit(EVENT_DOWNLOAD_PROGRESS, &info);
+        }
+
+        #[unsafe(method(showDownloadDidReceiveDataOfLength:))]
+        fn show_download_did_receive_data_of_length(&self, length: u64) {
+            let info = self.ivars().progress.borrow_mut().receive(length, Instant::now());
+            if let Some(info) = info {
+                self.emit(EVENT_DOWNLOAD_PROGRESS, &info);
+            }
+        }
+
+        #[unsafe(method(showDownloadDidStartExtractingUpdate))]
+        fn show_download_did_start_extracting_update(&self) {
+            self.discard(&[PromptKind::Download]);
+            self.emit(EVENT_SHOW_EXTRACTING_UPDATE, &EmptyPayload {});
+        }
+
+        #[unsafe(method(showExtractionReceivedProgress:))]
+        fn show_extraction_received_progress(&self, progress: f64) {
+            self.emit(EVENT_SHOW_EXTRACTION_PROGRESS, &ExtractionProgressInfo { progress });
+        }
+
+        #[unsafe(method(showReadyToInstallAndRelaunch:))]
+        fn show_ready_to_install_and_relaunch(&self, reply: &Block<dyn Fn(isize)>) {
+            let request_id =
+                self.register(PromptKind::ReadyToInstall, Responder::Choice(reply.copy()));
+            self.emit(EVENT_SHOW_READY_TO_INSTALL, &UserDriverRequestInfo { request_id });
+            self.handle_install_prompt();
+        }
+
+        #[unsafe(method(showInstallingUpdateWithApplicationTerminated:retryTerminatingApplication:))]
+        fn show_installing_update(
+            &self,
+            application_terminated: bool,
+            retry_terminating_application: &Block<dyn Fn()>,
+        ) {
+            self.discard(&[PromptKind::Installing]);
+            let request_id = self.register(
+                PromptKind::Installing,
+                Responder::Action(retry_terminating_application.copy()),
+            );
+            self.emit(EVENT_SHOW_INSTALLING_UPDATE, &InstallingInfo {
+                request_id,
+                application_terminated,
+            });
+        }
+
+        #[unsafe(method(showUpdateInstalledAndRelaunched:acknowledgement:))]
+        fn show_update_installed(&self, relaunched: bool, acknowledgement: &Block<dyn Fn()>) {
+            self.discard(&[PromptKind::Installing]);
+            let request_id = self.register(
+                PromptKind::UpdateInstalled,
+                Responder::Action(acknowledgement.copy()),
+            );
+            self.emit(EVENT_SHOW_UPDATE_INSTALLED, &UpdateInstalledInfo {
+                request_id,
+                relaunched,
+            });
+        }
+
+        #[unsafe(method(showUpdateInFocus))]
+        fn show_update_in_focus(&self) {
+            self.emit(EVENT_SHOW_UPDATE_IN_FOCUS, &EmptyPayload {});
+        }
+
+        #[unsafe(method(dismissUpdateInstallation))]
+        fn dismiss_update_installation(&self) {
+            // Sparkle no longer waits on any reply of this session.
+            self.ivars().router.borrow_mut().clear();
+            self.ivars().progress.borrow_mut().reset();
+            self.ivars().install_prompt_handler.borrow_mut().take();
+            self.emit(EVENT_DISMISS_UPDATE_INSTALLATION, &EmptyPayload {});
+        }
+    }
+);
+
+impl HeadlessUserDriver {
+    pub fn new(
+        mtm: MainThreadMarker,
+        delegate: Retained<SparkleDelegate>,
+        reply_timeout: Duration,
+        progress_interval: Duration,
+    ) -> Retained<Self> {
+        let this = Self::alloc(mtm);
+        let this = this.set_ivars(UserDriverIvars {
+            delegate,
+            router: RefCell::new(ReplyRouter::new(reply_timeout)),
+            progress: RefCell::new(DownloadProgress::new(progress_interval)),
+            install_prompt_handler: RefCell::new(None),
+        });
+        unsafe { msg_send![super(this), init] }
+    }
+
+    /// Answers a pending question.
+    pub fn reply(&self, request_id: u64, reply: &UserDriverReply) -> Result<()> {
+        reply.check_required(self.ivars().delegate.update_required().as_ref())?;
+        // Sparkle may ask the next question from within the block, so the router must not
+        // stay borrowed while it runs.
+        let responder = self.ivars().router.borrow_mut().reply(request_id, reply)?;
+        responder.respond(reply);
+        Ok(())
+    }
+
+    /// Answers the most recent pending question of one of `kinds` with `reply`. Returns
+    /// `false` when there is none.
+    pub fn answer(&self, kinds: &[PromptKind], reply: &UserDriverReply) -> Result<bool> {
+        let request_id = self.ivars().router.borrow().find(kinds);
+        match request_id {
+            Some(request_id) => self.reply(request_id, reply).map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Calls `handler` whenever Sparkle asks whether to install, until the update session
+    /// ends.
+    pub fn on_install_prompt(&self, handler: Arc<dyn Fn()>) {
+        *self.ivars().install_prompt_handler.borrow_mut() = Some(handler);
+    }
+
+    fn handle_install_prompt(&self) {
+        // The handler may answer the question, which can end the session, so the ivar must
+        // not stay borrowed while it runs.
+        let handler = self.ivars().install_prompt_handler.borrow().clone();
+        if let Some(handler) = handler {
+            handler();
+        }
+    }
+
+    /// Whether a question of one of `kinds` is pending.
+    pub fn is_asking(&self, kinds: &[PromptKind]) -> bool {
+        self.ivars().router.borrow().find(kinds).is_some()
+    }
+
+    pub fn pending_requests(&self) -> Vec<PendingRequest> {
+        self.ivars().router.borrow().pending()
+    }
+
+    fn register(&self, kind: PromptKind, responder: Responder) -> u64 {
+        let (request_id, timeout) = {
+            let mut router = self.ivars().router.borrow_mut();
+            (
+                router.register(kind, responder, Instant::now()),
+                router.timeout(),
+            )
+        };
+        if kind.timeout_reply().is_some() {
+            let driver = MainQueueDriver(self.retain());
+            Queue::main().exec_after(timeout, move || driver.expire_replies());
+        }
+        request_id
+    }
+
+    fn discard(&self, kinds: &[PromptKind]) {
+        self.ivars().router.borrow_mut().discard(kinds);
+    }
+
+    fn expire_replies(&self) {
+        let expired = self.ivars().router.borrow_mut().expire(Instant::now());
+        for (request_id, responder, reply) in expired {
+            warn!(
+                "User driver request {} was not answered in time, replying {:?}",
+                request_id, reply
+            );
+            responder.respond(&reply);
+        }
+    }
+
+    fn emit<T: serde::Serialize>(&self, event: &str, payload: &T) {
+        self.ivars().delegate.emit(event, payload);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// File name used for the settings file in the app config directory.
+pub const SETTINGS_FILE_NAME: &str = "sparkle-updater.json";
+
+/// Delegate-side settings that are restored on launch when persistence is enabled.
+///
+/// Sparkle keeps its own settings (automatic checks, check interval, ...) in user defaults;
+/// these are the ones the plugin answers through delegate callbacks.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PersistedSettings {
+    pub allowed_channels: Option<Vec<String>>,
+    pub feed_parameters: Option<HashMap<String, String>>,
+    pub download_request_headers: Option<HashMap<String, String>>,
+    pub feed_url_override: Option<String>,
+    /// Only set once the relaunch behaviour was changed at runtime, so `relaunch` from the
+    /// config applies until then.
+    pub should_relaunch: Option<bool>,
+}
+
+/// Storage backend for [`PersistedSettings`].
+pub trait SettingsStore: Send + Sync {
+    /// Returns the stored settings, or `None` if nothing has been saved yet.
+    fn load(&self) -> Result<Option<PersistedSettings>>;
+
+    fn save(&self, settings: &PersistedSettings) -> Result<()>;
+}
+
+/// Stores settings as a JSON file.
+#[derive(Clone, Debug)]
+pub struct JsonFileStore {
+    path: PathBuf,
+}
+
+impl JsonFileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SettingsStore for JsonFileStore {
+    fn load(&self) -> Result<Option<PersistedSettings>> {
+        read_json(&self.path)
+    }
+
+    fn save(&self, settings: &PersistedSettings) -> Result<()> {
+        write_json(&self.path, settings)
+    }
+}
+
+/// Keeps settings in memory only. Useful for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    settings: Mutex<Option<PersistedSettings>>,
+}
+
+impl SettingsStore for MemoryStore {
+    fn load(&self) -> Result<Option<PersistedSettings>> {
+        Ok(self.settings.lock().unwrap().clone())
+    }
+
+    fn save(&self, settings: &PersistedSettings) -> Result<()> {
+        *self.settings.lock().unwrap() = Some(settings.clone());
+        Ok(())
+    }
+}
+
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+/// Writes through a temporary file so a crash never leaves a truncated file behind.
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn settings() -> PersistedSettings {
+        PersistedSettings {
+            allowed_channels: Some(vec!["beta".to_string()]),
+            feed_parameters: Some(HashMap::from([("tier".to_string(), "pro".to_string())])),
+            download_request_headers: None,
+            feed_url_override: Some("https://example.com/appcast.xml".to_string()),
+            should_relaunch: Some(false),
+        }
+    }
+
+    #[test]
+    fn json_file_store_round_trips() {
+        let dir = TempDir::new("store");
+        let store = JsonFileStore::new(dir.path().join("nested").join(SETTINGS_FILE_NAME));
+
+        store.save(&settings()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(settings()));
+        assert!(!store.path().with_extension("json.tmp").exists());
+
+        store.save(&PersistedSettings::default()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(PersistedSettings::default()));
+    }
+
+    #[test]
+    fn json_file_store_without_file_loads_nothing() {
+        let dir = TempDir::new("store-missing");
+        let store = JsonFileStore::new(dir.path().join(SETTINGS_FILE_NAME));
+        assert_eq!(store.load().unwrap(), None);
+    }
+
+    #[test]
+    fn json_file_store_fills_in_missing_fields() {
+        let dir = TempDir::new("store-partial");
+        let store = JsonFileStore::new(dir.path().join(SETTINGS_FILE_NAME));
+        fs::write(store.path(), r#"{"allowedChannels":["beta"]}"#).unwrap();
+
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.allowed_channels, Some(vec!["beta".to_string()]));
+        assert_eq!(loaded.should_relaunch, None);
+    }
+
+    #[test]
+    fn json_file_store_rejects_corrupt_file() {
+        let dir = TempDir::new("store-corrupt");
+        let store = JsonFileStore::new(dir.path().join(SETTINGS_FILE_NAME));
+        fs::write(store.path(), "{").unwrap();
+        assert!(store.load().is_err());
+    }
+
+    #[test]
+    fn memory_store_round_trips() {
+        let store = MemoryStore::default();
+        assert_eq!(store.load().unwrap(), None);
+
+        store.save(&settings()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(settings()));
+    }
+}
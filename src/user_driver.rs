@@ -0,0 +1,378 @@
+//! Routing of the frontend's answers to the questions Sparkle's user driver asks in
+//! [`UserDriverMode::Headless`](crate::UserDriverMode::Headless).
+//!
+//! Every question is emitted with a request id. The webview answers it with
+//! `reply_user_driver`, and questions left unanswered for too long get a default answer so
+//! Sparkle's update session does not hang.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::consent::UpdateConsent;
+use crate::minimum_version::UpdateRequiredInfo;
+use crate::{Error, Result};
+
+/// Time the frontend has to answer a question before the default answer is given.
+pub const DEFAULT_REPLY_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// What the user driver is waiting on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PromptKind {
+    /// Whether Sparkle may check for updates automatically.
+    Permission,
+    /// A user-initiated check is running and can be cancelled.
+    UserInitiatedCheck,
+    /// An update was found.
+    UpdateFound,
+    /// No update was found. Needs to be acknowledged.
+    UpdateNotFound,
+    /// The update session failed. Needs to be acknowledged.
+    UpdaterError,
+    /// The update is downloading and can be cancelled.
+    Download,
+    /// The update is extracted and ready to install.
+    ReadyToInstall,
+    /// Installing waits for the app to terminate. Installing again retries terminating it.
+    Installing,
+    /// The update was installed. Needs to be acknowledged.
+    UpdateInstalled,
+}
+
+impl PromptKind {
+    /// Whether `reply` answers this kind of question.
+    pub fn accepts(&self, reply: &UserDriverReply) -> bool {
+        match self {
+            PromptKind::Permission => matches!(reply, UserDriverReply::Permission { .. }),
+            PromptKind::UserInitiatedCheck | PromptKind::Download => {
+                matches!(reply, UserDriverReply::Cancel)
+            }
+            PromptKind::UpdateFound | PromptKind::ReadyToInstall => matches!(
+                reply,
+                UserDriverReply::Install | UserDriverReply::Skip | UserDriverReply::RemindLater
+            ),
+            PromptKind::UpdateNotFound | PromptKind::UpdaterError | PromptKind::UpdateInstalled => {
+                matches!(reply, UserDriverReply::Acknowledge)
+            }
+            PromptKind::Installing => matches!(reply, UserDriverReply::Install),
+        }
+    }
+
+    /// Answer given when the frontend does not reply in time. `None` for questions that
+    /// may stay open, such as cancelling a running download.
+    pub fn timeout_reply(&self) -> Option<UserDriverReply> {
+        match self {
+            PromptKind::UpdateFound | PromptKind::ReadyToInstall => {
+                Some(UserDriverReply::RemindLater)
+            }
+            PromptKind::UpdateNotFound | PromptKind::UpdaterError | PromptKind::UpdateInstalled => {
+                Some(UserDriverReply::Acknowledge)
+            }
+            // Sparkle asks again on the next launch when permission is left unanswered.
+            PromptKind::Permission
+            | PromptKind::UserInitiatedCheck
+            | PromptKind::Download
+            | PromptKind::Installing => None,
+        }
+    }
+}
+
+/// The frontend's answer to a user driver question.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum UserDriverReply {
+    /// Installs the update, or retries terminating the app while installing.
+    Install,
+    /// Skips this version.
+    Skip,
+    /// Dismisses the update until the next scheduled check.
+    RemindLater,
+    /// Cancels a running check or download.
+    Cancel,
+    Acknowledge,
+    /// Answers the permission request.
+    #[serde(rename_all = "camelCase")]
+    Permission {
+        #[serde(flatten)]
+        consent: UpdateConsent,
+        #[serde(default)]
+        send_system_profile: bool,
+    },
+}
+
+impl UserDriverReply {
+    /// Refuses skipping or postponing the update while `required` says the running build
+    /// is below the minimum version.
+    pub fn check_required(&self, required: Option<&UpdateRequiredInfo>) -> Result<()> {
+        match required {
+            Some(required)
+                if matches!(self, UserDriverReply::Skip | UserDriverReply::RemindLater) =>
+            {
+                Err(Error::UpdateRequired(required.minimum_version.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A question waiting for an answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingRequest {
+    pub request_id: u64,
+    pub kind: PromptKind,
+}
+
+struct Pending<T> {
+    kind: PromptKind,
+    deadline: Option<Instant>,
+    responder: T,
+}
+
+/// Pending user driver questions and whatever answers them (`T`, Sparkle's reply blocks).
+pub struct ReplyRouter<T> {
+    next_id: u64,
+    timeout: Duration,
+    pending: BTreeMap<u64, Pending<T>>,
+}
+
+impl<T> ReplyRouter<T> {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            next_id: 1,
+            timeout,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Registers a question and returns its request id. Kinds without a
+    /// [`PromptKind::timeout_reply`] never expire.
+    pub fn register(&mut self, kind: PromptKind, responder: T, now: Instant) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let deadline = kind.timeout_reply().map(|_| now + self.timeout);
+        self.pending.insert(
+            id,
+            Pending {
+                kind,
+                deadline,
+                responder,
+            },
+        );
+        id
+    }
+
+    /// Removes the question `request_id` and returns what answers it. A reply that does not
+    /// fit the question leaves it pending.
+    pub fn reply(&mut self, request_id: u64, reply: &UserDriverReply) -> Result<T> {
+        let pending = self
+            .pending
+            .get(&request_id)
+            .ok_or(Error::UnknownUserDriverRequest(request_id))?;
+        if !pending.kind.accepts(reply) {
+            return Err(Error::InvalidUserDriverReply(format!(
+                "{:?} does not answer {:?}",
+                reply, pending.kind
+            )));
+        }
+        Ok(self.pending.remove(&request_id).unwrap().responder)
+    }
+
+    /// Removes the questions Sparkle no longer waits on, e.g. the cancellation of a
+    /// download that has finished.
+    pub fn discard(&mut self, kinds: &[PromptKind]) -> Vec<T> {
+        self.drain(|pending| kinds.contains(&pending.kind))
+            .into_values()
+            .map(|pending| pending.responder)
+            .collect()
+    }
+
+    /// Removes every pending question.
+    pub fn clear(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.pending)
+            .into_values()
+            .map(|pending| pending.responder)
+            .collect()
+    }
+
+    /// Removes the questions whose deadline has passed, with the answer to give them.
+    pub fn expire(&mut self, now: Instant) -> Vec<(u64, T, UserDriverReply)> {
+        self.drain(|pending| pending.deadline.is_some_and(|deadline| deadline <= now))
+            .into_iter()
+            .filter_map(|(id, pending)| {
+                let reply = pending.kind.timeout_reply()?;
+                Some((id, pending.responder, reply))
+            })
+            .collect()
+    }
+
+    /// Most recent pending question of one of `kinds`.
+    pub fn find(&self, kinds: &[PromptKind]) -> Option<u64> {
+        self.pending
+            .iter()
+            .rev()
+            .find(|(_, pending)| kinds.contains(&pending.kind))
+            .map(|(id, _)| *id)
+    }
+
+    pub fn pending(&self) -> Vec<PendingRequest> {
+        self.pending
+            .iter()
+            .map(|(id, pending)| PendingRequest {
+                request_id: *id,
+                kind: pending.kind,
+            })
+            .collect()
+    }
+
+    fn drain(&mut self, mut matches: impl FnMut(&Pending<T>) -> bool) -> BTreeMap<u64, Pending<T>> {
+        let (matched, kept) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(_, pending)| matches(pending));
+        self.pending = kept;
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minimum_version::MinimumVersionSource;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn reply_to_unknown_request_fails() {
+        let mut router = ReplyRouter::<&str>::new(TIMEOUT);
+        assert!(matches!(
+            router.reply(42, &UserDriverReply::Install),
+            Err(Error::UnknownUserDriverRequest(42))
+        ));
+    }
+
+    #[test]
+    fn mismatched_reply_leaves_question_pending() {
+        let mut router = ReplyRouter::new(TIMEOUT);
+        let id = router.register(PromptKind::UpdateFound, "found", Instant::now());
+
+        assert!(matches!(
+            router.reply(id, &UserDriverReply::Acknowledge),
+            Err(Error::InvalidUserDriverReply(_))
+        ));
+        assert_eq!(
+            router.pending(),
+            vec![PendingRequest {
+                request_id: id,
+                kind: PromptKind::UpdateFound,
+            }]
+        );
+
+        assert_eq!(router.reply(id, &UserDriverReply::Skip).unwrap(), "found");
+        assert!(router.pending().is_empty());
+    }
+
+    #[test]
+    fn expire_only_answers_kinds_with_a_timeout_reply() {
+        let now = Instant::now();
+        let mut router = ReplyRouter::new(TIMEOUT);
+        let download = router.register(PromptKind::Download, "download", now);
+        let found = router.register(PromptKind::UpdateFound, "found", now);
+        let error = router.register(PromptKind::UpdaterError, "error", now);
+
+        assert!(router.expire(now + TIMEOUT / 2).is_empty());
+        assert_eq!(
+            router.expire(now + TIMEOUT),
+            vec![
+                (found, "found", UserDriverReply::RemindLater),
+                (error, "error", UserDriverReply::Acknowledge),
+            ]
+        );
+        assert_eq!(
+            router.pending(),
+            vec![PendingRequest {
+                request_id: download,
+                kind: PromptKind::Download,
+            }]
+        );
+        assert!(router.expire(now + TIMEOUT * 100).is_empty());
+    }
+
+    #[test]
+    fn discard_removes_only_the_given_kinds() {
+        let now = Instant::now();
+        let mut router = ReplyRouter::new(TIMEOUT);
+        router.register(PromptKind::Download, "download", now);
+        let found = router.register(PromptKind::UpdateFound, "found", now);
+        router.register(PromptKind::UserInitiatedCheck, "check", now);
+
+        let mut discarded = router.discard(&[PromptKind::Download, PromptKind::UserInitiatedCheck]);
+        discarded.sort();
+        assert_eq!(discarded, vec!["check", "download"]);
+        assert_eq!(router.find(&[PromptKind::UpdateFound]), Some(found));
+        assert_eq!(router.pending().len(), 1);
+    }
+
+    #[test]
+    fn find_returns_the_newest_question() {
+        let now = Instant::now();
+        let mut router = ReplyRouter::new(TIMEOUT);
+        router.register(PromptKind::UpdateFound, "first", now);
+        let ready = router.register(PromptKind::ReadyToInstall, "second", now);
+        router.register(PromptKind::Download, "download", now);
+
+        let kinds = [PromptKind::UpdateFound, PromptKind::ReadyToInstall];
+        assert_eq!(router.find(&kinds), Some(ready));
+        assert_eq!(router.find(&[PromptKind::Permission]), None);
+    }
+
+    #[test]
+    fn required_update_refuses_skip_and_remind_later() {
+        let required = UpdateRequiredInfo {
+            minimum_version: "120".to_string(),
+            current_version: "100".to_string(),
+            message: None,
+            source: MinimumVersionSource::Appcast,
+        };
+        for reply in [UserDriverReply::Skip, UserDriverReply::RemindLater] {
+            assert!(matches!(
+                reply.check_required(Some(&required)),
+                Err(Error::UpdateRequired(version)) if version == "120"
+            ));
+            assert!(reply.check_required(None).is_ok());
+        }
+        for reply in [
+            UserDriverReply::Install,
+            UserDriverReply::Cancel,
+            UserDriverReply::Acknowledge,
+        ] {
+            assert!(reply.check_required(Some(&required)).is_ok());
+        }
+    }
+
+    #[test]
+    fn permission_reply_carries_consent() {
+        let reply: UserDriverReply = serde_json::from_value(serde_json::json!({
+            "action": "permission",
+            "automaticallyChecksForUpdates": true,
+            "automaticallyDownloadsUpdates": false,
+        }))
+        .unwrap();
+        assert_eq!(
+            reply,
+            UserDriverReply::Permission {
+                consent: UpdateConsent {
+                    automatically_checks_for_updates: true,
+                    automatically_downloads_updates: false,
+                },
+                send_system_profile: false,
+            }
+        );
+        assert!(PromptKind::Permission.accepts(&reply));
+    }
+}
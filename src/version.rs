@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PartType {
+    Number,
+    String,
+    Period,
+    Separator,
+}
+
+fn part_type(c: char) -> PartType {
+    if c.is_ascii_digit() {
+        PartType::Number
+    } else if c == '.' {
+        PartType::Period
+    } else if c.is_whitespace() || c.is_ascii_punctuation() {
+        PartType::Separator
+    } else {
+        PartType::String
+    }
+}
+
+/// Splits a version string into typed parts, mirroring Sparkle's `SUStandardVersionComparator`.
+///
+/// Consecutive characters of the same type form one part, every period is its own part and
+/// separators (whitespace, punctuation other than `.`) are dropped.
+fn split_version(version: &str) -> Vec<(PartType, &str)> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut current: Option<PartType> = None;
+
+    for (index, c) in version.char_indices() {
+        let kind = part_type(c);
+        if let Some(previous) = current {
+            if previous != kind || previous == PartType::Period {
+                if previous != PartType::Separator {
+                    parts.push((previous, &version[start..index]));
+                }
+                start = index;
+            }
+        }
+        current = Some(kind);
+    }
+
+    if let Some(previous) = current {
+        if previous != PartType::Separator {
+            parts.push((previous, &version[start..]));
+        }
+    }
+
+    parts
+}
+
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => {
+            let a = a.trim_start_matches('0');
+            let b = b.trim_start_matches('0');
+            a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+        }
+    }
+}
+
+/// Compares two version strings the same way Sparkle's standard version comparator does.
+///
+/// `1.10` is newer than `1.9`, `1.0` is newer than `1.0b1` and `1.0.1` is newer than `1.0`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts_a = split_version(a);
+    let parts_b = split_version(b);
+
+    for ((type_a, part_a), (type_b, part_b)) in parts_a.iter().zip(parts_b.iter()) {
+        let ordering = if type_a == type_b {
+            match type_a {
+                PartType::Number => compare_numbers(part_a, part_b),
+                PartType::String => part_a.cmp(part_b),
+                _ => Ordering::Equal,
+            }
+        } else if *type_b == PartType::String {
+            Ordering::Greater
+        } else if *type_a == PartType::String {
+            Ordering::Less
+        } else if *type_a == PartType::Number {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    let shared = parts_a.len().min(parts_b.len());
+    match parts_a.len().cmp(&parts_b.len()) {
+        Ordering::Equal => Ordering::Equal,
+        Ordering::Greater => match parts_a[shared].0 {
+            PartType::String => Ordering::Less,
+            _ => Ordering::Greater,
+        },
+        Ordering::Less => match parts_b[shared].0 {
+            PartType::String => Ordering::Greater,
+            _ => Ordering::Less,
+        },
+    }
+}
+
+/// Orders build versions for the downgrade checks and for Sparkle itself, which receives it
+/// through `versionComparatorForUpdater:`.
+///
+/// Implemented for closures taking two versions and returning an [`Ordering`].
+pub trait VersionComparator: Send + Sync {
+    fn compare(&self, a: &str, b: &str) -> Ordering;
+}
+
+impl<F> VersionComparator for F
+where
+    F: Fn(&str, &str) -> Ordering + Send + Sync,
+{
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// Sparkle's default ordering, see [`compare_versions`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StandardVersionComparator;
+
+impl VersionComparator for StandardVersionComparator {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        compare_versions(a, b)
+    }
+}
+
+/// Returns the newer of two versions.
+pub fn newer_version<'a>(comparator: &dyn VersionComparator, a: &'a str, b: &'a str) -> &'a str {
+    match comparator.compare(a, b) {
+        Ordering::Less => b,
+        _ => a,
+    }
+}
+
+/// Reason an appcast item was refused by the downgrade and replay protection.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum VersionRejection {
+    #[error("Update {candidate} is not newer than the running version {current}")]
+    NotNewer { candidate: String, current: String },
+
+    #[error("Update {candidate} is older than the highest installed version {high_water_mark}")]
+    BelowHighWaterMark {
+        candidate: String,
+        high_water_mark: String,
+    },
+}
+
+impl VersionRejection {
+    /// Error code reported in the `NSError` handed back to Sparkle.
+    pub fn code(&self) -> i64 {
+        match self {
+            VersionRejection::NotNewer { .. } => 1,
+            VersionRejection::BelowHighWaterMark { .. } => 2,
+        }
+    }
+}
+
+/// Checks that `candidate` is strictly newer than `current` and not below `high_water_mark`.
+///
+/// All arguments are build versions (`sparkle:version` / `CFBundleVersion`).
+pub fn check_candidate_version(
+    comparator: &dyn VersionComparator,
+    candidate: &str,
+    current: &str,
+    high_water_mark: Option<&str>,
+) -> std::result::Result<(), VersionRejection> {
+    if comparator.compare(candidate, current) != Ordering::Greater {
+        return Err(VersionRejection::NotNewer {
+            candidate: candidate.to_string(),
+            current: current.to_string(),
+        });
+    }
+
+    if let Some(mark) = high_water_mark {
+        if comparator.compare(candidate, mark) == Ordering::Less {
+            return Err(VersionRejection::BelowHighWaterMark {
+                candidate: candidate.to_string(),
+                high_water_mark: mark.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_segments_compare_as_numbers() {
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.09", "1.9"), Ordering::Equal);
+        assert_eq!(compare_versions("2", "10"), Ordering::Less);
+        assert_eq!(
+            compare_versions("99999999999999999999", "100000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn numbers_are_newer_than_strings() {
+        assert_eq!(compare_versions("1.1", "1.a"), Ordering::Greater);
+        assert_eq!(compare_versions("1.a", "1.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.0b", "1.0a"), Ordering::Greater);
+    }
+
+    #[test]
+    fn extra_numeric_segment_is_newer() {
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0", "1.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.1", "1.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn prerelease_is_older_than_release() {
+        assert_eq!(compare_versions("1.0b1", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0b1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0b1", "1.0b2"), Ordering::Less);
+        assert_eq!(compare_versions("1.0 beta", "1.0-beta"), Ordering::Equal);
+    }
+
+    #[test]
+    fn accepts_newer_candidate() {
+        assert_eq!(
+            check_candidate_version(&StandardVersionComparator, "101", "100", Some("100")),
+            Ok(())
+        );
+        assert_eq!(
+            check_candidate_version(&StandardVersionComparator, "101", "100", None),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_candidate_not_newer_with_code_1() {
+        for candidate in ["100", "99"] {
+            let rejection =
+                check_candidate_version(&StandardVersionComparator, candidate, "100", None)
+                    .unwrap_err();
+            assert_eq!(
+                rejection,
+                VersionRejection::NotNewer {
+                    candidate: candidate.to_string(),
+                    current: "100".to_string(),
+                }
+            );
+            assert_eq!(rejection.code(), 1);
+        }
+    }
+
+    #[test]
+    fn rejects_candidate_below_high_water_mark_with_code_2() {
+        let rejection =
+            check_candidate_version(&StandardVersionComparator, "101", "100", Some("102"))
+                .unwrap_err();
+        assert_eq!(
+            rejection,
+            VersionRejection::BelowHighWaterMark {
+                candidate: "101".to_string(),
+                high_water_mark: "102".to_string(),
+            }
+        );
+        assert_eq!(rejection.code(), 2);
+    }
+
+    #[test]
+    fn uses_the_given_comparator() {
+        let reversed = |a: &str, b: &str| compare_versions(b, a);
+        assert!(check_candidate_version(&reversed, "99", "100", None).is_ok());
+        assert_eq!(newer_version(&reversed, "99", "100"), "99");
+    }
+}
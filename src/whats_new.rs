@@ -0,0 +1,173 @@
+//! Detection of the first launch after an update, and the "What's New" shown for it.
+//!
+//! The version of every launch is recorded. When the next launch runs a newer build, the
+//! plugin emits `sparkle://did-update` with the cached [`UpdateInfo`] of the installed
+//! item. The event is emitted during setup, so only Rust listeners receive it; windows
+//! consume the update once each through `consume_whats_new`.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::UpdateInfo;
+use crate::version::VersionComparator;
+
+/// Version of a launch of the app.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchedVersion {
+    pub version: String,
+    /// `CFBundleVersion`, compared with Sparkle's version comparison.
+    pub build_version: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidUpdateInfo {
+    pub from: LaunchedVersion,
+    pub to: LaunchedVersion,
+    /// The installed appcast item with its release notes, if Sparkle installed it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<UpdateInfo>,
+}
+
+/// Compares this launch with the previous one. Returns the update when this launch runs a
+/// newer build. `installed` is the item cached when Sparkle started installing, used only
+/// if it is the build that is running now.
+pub fn detect_update(
+    comparator: &dyn VersionComparator,
+    previous: Option<&LaunchedVersion>,
+    current: &LaunchedVersion,
+    installed: Option<UpdateInfo>,
+) -> Option<DidUpdateInfo> {
+    let previous = previous?;
+    if comparator.compare(&current.build_version, &previous.build_version) != Ordering::Greater {
+        return None;
+    }
+    Some(DidUpdateInfo {
+        from: previous.clone(),
+        to: current.clone(),
+        update: installed.filter(|update| update.build_version == current.build_version),
+    })
+}
+
+/// Hands out the update of this launch once per window.
+#[derive(Debug, Default)]
+pub struct WhatsNew {
+    update: Option<DidUpdateInfo>,
+    consumed: HashSet<String>,
+}
+
+impl WhatsNew {
+    pub fn new(update: Option<DidUpdateInfo>) -> Self {
+        Self {
+            update,
+            consumed: HashSet::new(),
+        }
+    }
+
+    pub fn update(&self) -> Option<&DidUpdateInfo> {
+        self.update.as_ref()
+    }
+
+    /// Returns the update the first time `window` asks, `None` afterwards or when this
+    /// launch did not follow an update.
+    pub fn consume(&mut self, window: &str) -> Option<DidUpdateInfo> {
+        let update = self.update.as_ref()?;
+        self.consumed
+            .insert(window.to_string())
+            .then(|| update.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::StandardVersionComparator;
+
+    fn launch(version: &str, build: &str) -> LaunchedVersion {
+        LaunchedVersion {
+            version: version.to_string(),
+            build_version: build.to_string(),
+        }
+    }
+
+    fn item(build: &str) -> UpdateInfo {
+        serde_json::from_value(serde_json::json!({
+            "version": "2.0",
+            "buildVersion": build,
+            "isDelta": false,
+            "isCritical": false,
+            "isMajorUpgrade": false,
+            "isInformationOnly": false,
+            "minimumOsVersionOk": true,
+            "maximumOsVersionOk": true,
+            "installationType": "application",
+        }))
+        .unwrap()
+    }
+
+    fn detect(
+        previous: Option<&LaunchedVersion>,
+        current: &LaunchedVersion,
+        installed: Option<UpdateInfo>,
+    ) -> Option<DidUpdateInfo> {
+        detect_update(&StandardVersionComparator, previous, current, installed)
+    }
+
+    #[test]
+    fn first_launch_is_not_an_update() {
+        assert!(detect(None, &launch("1.0", "100"), None).is_none());
+    }
+
+    #[test]
+    fn same_or_older_build_is_not_an_update() {
+        let current = launch("1.0", "100");
+        assert!(detect(Some(&launch("1.0", "100")), &current, None).is_none());
+        assert!(detect(Some(&launch("1.1", "101")), &current, None).is_none());
+    }
+
+    #[test]
+    fn newer_build_is_an_update() {
+        let previous = launch("1.0", "100");
+        let current = launch("1.0.1", "101");
+        let update = detect(Some(&previous), &current, None).unwrap();
+        assert_eq!(update.from, previous);
+        assert_eq!(update.to, current);
+        assert!(update.update.is_none());
+    }
+
+    #[test]
+    fn build_version_decides_not_the_marketing_version() {
+        let update = detect(Some(&launch("2.0", "100")), &launch("1.9", "200"), None);
+        assert!(update.is_some());
+    }
+
+    #[test]
+    fn keeps_the_installed_item_only_if_it_is_the_running_build() {
+        let previous = launch("1.0", "100");
+        let current = launch("2.0", "200");
+        let matching = detect(Some(&previous), &current, Some(item("200"))).unwrap();
+        assert_eq!(matching.update.unwrap().build_version, "200");
+        let stale = detect(Some(&previous), &current, Some(item("150"))).unwrap();
+        assert!(stale.update.is_none());
+    }
+
+    #[test]
+    fn each_window_consumes_the_update_once() {
+        let update = detect(Some(&launch("1.0", "100")), &launch("2.0", "200"), None);
+        let mut whats_new = WhatsNew::new(update);
+        assert_eq!(whats_new.consume("main").unwrap().to.build_version, "200");
+        assert!(whats_new.consume("main").is_none());
+        assert!(whats_new.consume("settings").is_some());
+        assert!(whats_new.update().is_some());
+    }
+
+    #[test]
+    fn nothing_to_consume_without_an_update() {
+        let mut whats_new = WhatsNew::new(None);
+        assert!(whats_new.consume("main").is_none());
+        assert!(whats_new.update().is_none());
+    }
+}